@@ -13,6 +13,19 @@ pub struct Model {
     pub mode: protocol::ModeKind,
     #[sea_orm(nullable)]
     pub title: Option<String>,
+    /// summary of history dropped to fit the context window
+    #[sea_orm(nullable)]
+    pub summary: Option<String>,
+    /// id of the last message covered by `summary`
+    #[sea_orm(nullable)]
+    pub summary_until: Option<i32>,
+    /// last message of the selected branch
+    #[sea_orm(nullable)]
+    pub leaf_id: Option<i32>,
+    /// unix timestamp in seconds
+    pub created_at: i64,
+    /// unix timestamp in seconds, bumped by new messages
+    pub updated_at: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -54,5 +67,3 @@ impl Related<super::user::Entity> for Entity {
         Relation::User.def()
     }
 }
-
-impl ActiveModelBehavior for ActiveModel {}
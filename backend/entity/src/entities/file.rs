@@ -11,6 +11,10 @@ pub struct Model {
     pub owner_id: Option<i32>,
     // plan to remove
     pub mime_type: Option<String>,
+    /// unix timestamp in seconds
+    pub created_at: i64,
+    /// unix timestamp in seconds
+    pub updated_at: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -44,5 +48,3 @@ impl Related<super::user::Entity> for Entity {
         Relation::User.def()
     }
 }
-
-impl ActiveModelBehavior for ActiveModel {}
@@ -8,9 +8,16 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub chat_id: i32,
+    /// previous message in the branch, siblings share a parent
+    #[sea_orm(nullable)]
+    pub parent_id: Option<i32>,
     pub price: f32,
     pub token_count: i32,
     pub inner: protocol::MessageInner,
+    /// unix timestamp in seconds
+    pub created_at: i64,
+    /// unix timestamp in seconds
+    pub updated_at: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -30,5 +37,3 @@ impl Related<super::chat::Entity> for Entity {
         Relation::Chat.def()
     }
 }
-
-impl ActiveModelBehavior for ActiveModel {}
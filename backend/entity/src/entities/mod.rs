@@ -2,10 +2,14 @@
 
 pub mod prelude;
 
+pub mod api_key;
 pub mod chat;
 pub mod config;
 pub mod file;
 pub mod message;
 pub mod model;
+pub mod provider;
+pub mod session;
 pub mod tool;
+pub mod usage;
 pub mod user;
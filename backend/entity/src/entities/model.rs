@@ -8,6 +8,7 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub config: String,
+    pub access: protocol::ModelAccess,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,8 +1,12 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.14
 
+pub use super::api_key::Entity as ApiKey;
 pub use super::chat::Entity as Chat;
 pub use super::config::Entity as Config;
 pub use super::message::Entity as Message;
 pub use super::model::Entity as Model;
+pub use super::provider::Entity as Provider;
+pub use super::session::Entity as Session;
 pub use super::tool::Entity as Tool;
+pub use super::usage::Entity as Usage;
 pub use super::user::Entity as User;
@@ -10,6 +10,20 @@ pub struct Model {
     pub name: String,
     pub password: String,
     pub preference: protocol::UserPreference,
+    pub budget: protocol::UserBudget,
+    pub role: protocol::UserRole,
+    pub groups: protocol::UserGroups,
+    /// `sub` claim of the OpenID Connect issuer, for users provisioned by it
+    #[sea_orm(nullable, unique)]
+    pub oidc_subject: Option<String>,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    /// password has to be changed on next login, set for the seeded default password
+    pub must_change_password: bool,
+    /// unix timestamp in seconds
+    pub created_at: i64,
+    /// unix timestamp in seconds
+    pub updated_at: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -23,5 +37,3 @@ impl Related<super::chat::Entity> for Entity {
         Relation::Chat.def()
     }
 }
-
-impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,6 @@
-use sea_orm::ActiveValue;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sea_orm::{ActiveModelBehavior, ActiveValue, ConnectionTrait, DbErr};
 
 impl crate::message::ActiveModel {
     pub fn full_change(&mut self) {
@@ -12,3 +14,38 @@ impl crate::message::ActiveModel {
         change!(token_count);
     }
 }
+
+/// Fill `created_at` on insert and bump `updated_at` on every save
+///
+/// Only applies to `ActiveModel::insert`/`update`/`save`, not to `Entity::insert` or
+/// `update_many`, which have to set them by hand.
+macro_rules! timestamps {
+    ($($entity:ident),*) => {
+        $(
+            #[sea_orm::prelude::async_trait::async_trait]
+            impl ActiveModelBehavior for crate::$entity::ActiveModel {
+                async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+                where
+                    C: ConnectionTrait,
+                {
+                    let now = now();
+                    if insert && self.created_at.is_not_set() {
+                        self.created_at = ActiveValue::Set(now);
+                    }
+                    self.updated_at = ActiveValue::Set(now);
+                    Ok(self)
+                }
+            }
+        )*
+    };
+}
+
+timestamps!(chat, message, file, user);
+
+/// Current unix timestamp in seconds
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs() as i64)
+        .unwrap_or_default()
+}
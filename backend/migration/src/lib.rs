@@ -2,13 +2,47 @@ pub use sea_orm_migration::prelude::*;
 use sea_orm_migration::sea_orm::Database;
 
 mod m20250908_082005_create_table;
+mod m20261017_000001_create_provider;
+mod m20261017_000002_provider_flags;
+mod m20261017_000003_chat_summary;
+mod m20261017_000004_usage_budget;
+mod m20261017_000005_created_at;
+mod m20261017_000006_timestamps;
+mod m20261017_000007_user_role;
+mod m20261017_000008_model_access;
+mod m20261017_000009_session;
+mod m20261017_000010_oidc_subject;
+mod m20261017_000011_user_profile;
+mod m20261017_000012_api_key;
+mod m20261017_000013_usage_api_key;
+mod m20261017_000014_password_change;
+mod m20261017_000015_message_tree;
+mod m20261017_000016_search_index;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20250908_082005_create_table::Migration)]
+        vec![
+            Box::new(m20250908_082005_create_table::Migration),
+            Box::new(m20261017_000001_create_provider::Migration),
+            Box::new(m20261017_000002_provider_flags::Migration),
+            Box::new(m20261017_000003_chat_summary::Migration),
+            Box::new(m20261017_000004_usage_budget::Migration),
+            Box::new(m20261017_000005_created_at::Migration),
+            Box::new(m20261017_000006_timestamps::Migration),
+            Box::new(m20261017_000007_user_role::Migration),
+            Box::new(m20261017_000008_model_access::Migration),
+            Box::new(m20261017_000009_session::Migration),
+            Box::new(m20261017_000010_oidc_subject::Migration),
+            Box::new(m20261017_000011_user_profile::Migration),
+            Box::new(m20261017_000012_api_key::Migration),
+            Box::new(m20261017_000013_usage_api_key::Migration),
+            Box::new(m20261017_000014_password_change::Migration),
+            Box::new(m20261017_000015_message_tree::Migration),
+            Box::new(m20261017_000016_search_index::Migration),
+        ]
     }
 }
 
@@ -0,0 +1,66 @@
+use pasetors::keys::Generate;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(Provider::Table)
+                    .col(pk_auto(Provider::Id))
+                    .col(string(Provider::Name))
+                    .col(string(Provider::Kind))
+                    .col(string_null(Provider::BaseUrl))
+                    .col(binary(Provider::ApiKey))
+                    .to_owned(),
+            )
+            .await?;
+
+        // key for encrypting provider api keys, kept next to paseto_key
+        let key = pasetors::keys::SymmetricKey::generate().expect("Cannot generate");
+        let insert_key = Query::insert()
+            .into_table(Config::Table)
+            .columns([Config::Key, Config::Value])
+            .values_panic(["provider_key".into(), key.as_bytes().into()])
+            .to_owned();
+        manager.exec_stmt(insert_key).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let delete_key = Query::delete()
+            .from_table(Config::Table)
+            .and_where(Expr::col(Config::Key).eq("provider_key"))
+            .to_owned();
+        manager.exec_stmt(delete_key).await?;
+
+        manager
+            .drop_table(Table::drop().table(Provider::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Provider {
+    Table,
+    Id,
+    Name,
+    Kind,
+    BaseUrl,
+    ApiKey,
+}
+
+#[derive(DeriveIden)]
+pub enum Config {
+    Table,
+    Key,
+    Value,
+}
@@ -0,0 +1,168 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Messages billed before usage was tracked, so existing spending counts towards budgets.
+/// Messages carry no timestamp yet, they are dated at the time of migration.
+const BACKFILL: &str = "INSERT INTO usage (user_id, model_id, mode, price, token_count, created_at)
+    SELECT chat.owner_id, chat.model_id, chat.mode, message.price, message.token_count,
+        strftime('%s', 'now')
+    FROM message JOIN chat ON chat.id = message.chat_id
+    WHERE message.price > 0 OR message.token_count > 0
+    ORDER BY message.id";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(string(User::Budget).default("{}"))
+                    .to_owned(),
+            )
+            .await?;
+
+        // kept apart from message, so deleting a chat doesn't refund its usage
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(Usage::Table)
+                    .col(pk_auto(Usage::Id))
+                    .col(integer(Usage::UserId))
+                    .col(integer_null(Usage::ModelId))
+                    .col(integer(Usage::Mode))
+                    .col(float(Usage::Price).default(0.0))
+                    .col(integer(Usage::TokenCount).default(0))
+                    .col(big_integer(Usage::CreatedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-usage-user_id-user")
+                            .from(Usage::Table, Usage::UserId)
+                            .to(User::Table, User::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-usage-model_id-model")
+                            .from(Usage::Table, Usage::ModelId)
+                            .to(Model::Table, Model::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-usage-user_id-created_at")
+                    .table(Usage::Table)
+                    .col(Usage::UserId)
+                    .col(Usage::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(BACKFILL)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Usage::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::Budget)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+    Table,
+    Id,
+    Budget,
+}
+
+#[derive(DeriveIden)]
+pub enum Model {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+pub enum Usage {
+    Table,
+    Id,
+    UserId,
+    ModelId,
+    Mode,
+    Price,
+    TokenCount,
+    CreatedAt,
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm_migration::sea_orm::{ConnectionTrait, Database, Statement};
+
+    use crate::{Migrator, MigratorTrait};
+
+    #[tokio::test]
+    async fn backfill_usage_from_messages() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        // everything up to, but excluding, this migration
+        Migrator::up(&db, Some(4)).await.unwrap();
+
+        for sql in [
+            "INSERT INTO chat (id, owner_id, mode, model_id) VALUES (1, 1, 2, 1)",
+            "INSERT INTO message (chat_id, price, token_count, inner) VALUES (1, 0.5, 120, '')",
+            "INSERT INTO message (chat_id, price, token_count, inner) VALUES (1, 0, 0, '')",
+            "INSERT INTO message (chat_id, price, token_count, inner) VALUES (1, 0.25, 30, '')",
+        ] {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+        Migrator::up(&db, Some(1)).await.unwrap();
+
+        let rows = db
+            .query_all(Statement::from_string(
+                db.get_database_backend(),
+                "SELECT user_id, model_id, mode, price, token_count FROM usage ORDER BY id",
+            ))
+            .await
+            .unwrap();
+        let rows: Vec<(i32, Option<i32>, i32, f32, i32)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.try_get("", "user_id").unwrap(),
+                    row.try_get("", "model_id").unwrap(),
+                    row.try_get("", "mode").unwrap(),
+                    row.try_get("", "price").unwrap(),
+                    row.try_get("", "token_count").unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![(1, Some(1), 2, 0.5, 120), (1, Some(1), 2, 0.25, 30)]
+        );
+    }
+}
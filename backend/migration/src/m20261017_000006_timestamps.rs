@@ -0,0 +1,190 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Rows created before timestamps were tracked are backfilled, in order:
+/// messages get the time of migration, chats the span of their messages, files the creation of
+/// their chat and users their first chat.
+const BACKFILL: &[&str] = &[
+    "UPDATE message SET created_at = strftime('%s', 'now') WHERE created_at IS NULL",
+    "UPDATE message SET updated_at = created_at",
+    "UPDATE chat SET created_at = COALESCE(
+        (SELECT MIN(message.created_at) FROM message WHERE message.chat_id = chat.id),
+        strftime('%s', 'now')
+    ) WHERE created_at IS NULL",
+    "UPDATE chat SET updated_at = COALESCE(
+        (SELECT MAX(message.created_at) FROM message WHERE message.chat_id = chat.id),
+        created_at
+    )",
+    "UPDATE file SET created_at = COALESCE(
+        (SELECT chat.created_at FROM chat WHERE chat.id = file.chat_id),
+        strftime('%s', 'now')
+    )",
+    "UPDATE file SET updated_at = created_at",
+    "UPDATE user SET created_at = COALESCE(
+        (SELECT MIN(chat.created_at) FROM chat WHERE chat.owner_id = user.id),
+        strftime('%s', 'now')
+    )",
+    "UPDATE user SET updated_at = created_at",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .add_column(big_integer(Message::UpdatedAt).default(0))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Chat::Table)
+                    .add_column(big_integer(Chat::UpdatedAt).default(0))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(File::Table)
+                    .add_column(big_integer(File::CreatedAt).default(0))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(File::Table)
+                    .add_column(big_integer(File::UpdatedAt).default(0))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(big_integer(User::CreatedAt).default(0))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(big_integer(User::UpdatedAt).default(0))
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        for sql in BACKFILL {
+            db.execute_unprepared(sql).await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-chat-owner_id-updated_at")
+                    .table(Chat::Table)
+                    .col(Chat::OwnerId)
+                    .col(Chat::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-chat-owner_id-updated_at")
+                    .table(Chat::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(File::Table)
+                    .drop_column(File::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(File::Table)
+                    .drop_column(File::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Chat::Table)
+                    .drop_column(Chat::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .drop_column(Message::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Message {
+    Table,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum Chat {
+    Table,
+    OwnerId,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum File {
+    Table,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+    Table,
+    CreatedAt,
+    UpdatedAt,
+}
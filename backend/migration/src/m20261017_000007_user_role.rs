@@ -0,0 +1,51 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The seeded `admin` becomes an admin, or the oldest user if it was renamed or deleted, so
+/// existing deployments keep someone able to manage users
+const PROMOTE_ADMIN: &str = "UPDATE user SET role = 'admin' WHERE id = COALESCE(
+    (SELECT id FROM user WHERE name = 'admin'),
+    (SELECT MIN(id) FROM user)
+)";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(text(User::Role).default("member"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(PROMOTE_ADMIN)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::Role)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+    Table,
+    Role,
+}
@@ -0,0 +1,77 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(ApiKey::Table)
+                    .col(pk_auto(ApiKey::Id))
+                    .col(integer(ApiKey::UserId))
+                    .col(string(ApiKey::Name))
+                    .col(string(ApiKey::Prefix))
+                    .col(string_uniq(ApiKey::Hash))
+                    .col(string(ApiKey::Scope).default("full"))
+                    .col(big_integer(ApiKey::CreatedAt))
+                    .col(big_integer_null(ApiKey::ExpiresAt))
+                    .col(big_integer_null(ApiKey::LastUsedAt))
+                    .col(big_integer_null(ApiKey::RevokedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-api_key-user_id-user")
+                            .from(ApiKey::Table, ApiKey::UserId)
+                            .to(User::Table, User::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-api_key-user_id")
+                    .table(ApiKey::Table)
+                    .col(ApiKey::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiKey::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ApiKey {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Prefix,
+    Hash,
+    Scope,
+    CreatedAt,
+    ExpiresAt,
+    LastUsedAt,
+    RevokedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,76 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Hash of the seeded `P@88w0rd`, see `m20250908_082005_create_table::default`
+const DEFAULT_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=16,t=2,p=1$aTg5eTNyMmRzLTA$FM4qzh9B/+DdCVOiQQruGw";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(boolean(User::MustChangePassword).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        // whoever still has the default password must pick a new one, logged in devices included
+        let flag = Query::update()
+            .table(User::Table)
+            .value(User::MustChangePassword, true)
+            .and_where(Expr::col(User::Password).eq(DEFAULT_PASSWORD_HASH))
+            .to_owned();
+        manager.exec_stmt(flag).await?;
+
+        let revoke = Query::update()
+            .table(Session::Table)
+            .value(Session::RevokedAt, Expr::cust("strftime('%s', 'now')"))
+            .and_where(Expr::col(Session::RevokedAt).is_null())
+            .and_where(
+                Expr::col(Session::UserId).in_subquery(
+                    Query::select()
+                        .column(User::Id)
+                        .from(User::Table)
+                        .and_where(Expr::col(User::MustChangePassword).eq(true))
+                        .to_owned(),
+                ),
+            )
+            .to_owned();
+        manager.exec_stmt(revoke).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::MustChangePassword)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+    Table,
+    Id,
+    Password,
+    MustChangePassword,
+}
+
+#[derive(DeriveIden)]
+pub enum Session {
+    Table,
+    UserId,
+    RevokedAt,
+}
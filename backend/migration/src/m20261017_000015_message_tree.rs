@@ -0,0 +1,75 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Existing chats are linear, every message is a child of the one before it
+const BACKFILL: &[&str] = &[
+    "UPDATE message SET parent_id = (
+        SELECT MAX(prev.id) FROM message AS prev
+        WHERE prev.chat_id = message.chat_id AND prev.id < message.id
+    )",
+    "UPDATE chat SET leaf_id = (SELECT MAX(message.id) FROM message WHERE message.chat_id = chat.id)",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .add_column(integer_null(Message::ParentId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Chat::Table)
+                    .add_column(integer_null(Chat::LeafId))
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        for sql in BACKFILL {
+            db.execute_unprepared(sql).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Chat::Table)
+                    .drop_column(Chat::LeafId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .drop_column(Message::ParentId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Message {
+    Table,
+    ParentId,
+}
+
+#[derive(DeriveIden)]
+pub enum Chat {
+    Table,
+    LeafId,
+}
@@ -0,0 +1,94 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Searchable text of a message row: text of user messages, `Text` chunks of replies
+fn message_text(row: &str) -> String {
+    format!(
+        "CASE json_extract({row}.inner, '$.t')
+            WHEN 'user' THEN json_extract({row}.inner, '$.c.text')
+            ELSE (
+                SELECT group_concat(json_extract(chunk.value, '$.c'), char(10))
+                FROM json_each({row}.inner, '$.c') AS chunk
+                WHERE json_extract(chunk.value, '$.t') = 'text'
+            )
+        END"
+    )
+}
+
+/// Index tables and the triggers keeping them in sync, `rowid` is the id of the indexed row
+fn statements() -> Vec<String> {
+    let new = message_text("new");
+    vec![
+        "CREATE VIRTUAL TABLE message_fts USING fts5(text, tokenize = 'unicode61 remove_diacritics 2')"
+            .to_owned(),
+        "CREATE VIRTUAL TABLE chat_fts USING fts5(title, tokenize = 'unicode61 remove_diacritics 2')"
+            .to_owned(),
+        format!(
+            "CREATE TRIGGER message_fts_insert AFTER INSERT ON message BEGIN
+                INSERT INTO message_fts (rowid, text) VALUES (new.id, {new});
+            END"
+        ),
+        format!(
+            "CREATE TRIGGER message_fts_update AFTER UPDATE OF inner ON message BEGIN
+                DELETE FROM message_fts WHERE rowid = old.id;
+                INSERT INTO message_fts (rowid, text) VALUES (new.id, {new});
+            END"
+        ),
+        "CREATE TRIGGER message_fts_delete AFTER DELETE ON message BEGIN
+            DELETE FROM message_fts WHERE rowid = old.id;
+        END"
+        .to_owned(),
+        "CREATE TRIGGER chat_fts_insert AFTER INSERT ON chat BEGIN
+            INSERT INTO chat_fts (rowid, title) VALUES (new.id, new.title);
+        END"
+        .to_owned(),
+        "CREATE TRIGGER chat_fts_update AFTER UPDATE OF title ON chat BEGIN
+            DELETE FROM chat_fts WHERE rowid = old.id;
+            INSERT INTO chat_fts (rowid, title) VALUES (new.id, new.title);
+        END"
+        .to_owned(),
+        "CREATE TRIGGER chat_fts_delete AFTER DELETE ON chat BEGIN
+            DELETE FROM chat_fts WHERE rowid = old.id;
+        END"
+        .to_owned(),
+        format!(
+            "INSERT INTO message_fts (rowid, text) SELECT id, {} FROM message",
+            message_text("message")
+        ),
+        "INSERT INTO chat_fts (rowid, title) SELECT id, title FROM chat".to_owned(),
+    ]
+}
+
+const DROP: &[&str] = &[
+    "DROP TRIGGER IF EXISTS chat_fts_delete",
+    "DROP TRIGGER IF EXISTS chat_fts_update",
+    "DROP TRIGGER IF EXISTS chat_fts_insert",
+    "DROP TRIGGER IF EXISTS message_fts_delete",
+    "DROP TRIGGER IF EXISTS message_fts_update",
+    "DROP TRIGGER IF EXISTS message_fts_insert",
+    "DROP TABLE IF EXISTS chat_fts",
+    "DROP TABLE IF EXISTS message_fts",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for sql in statements() {
+            db.execute_unprepared(&sql).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for sql in DROP {
+            db.execute_unprepared(sql).await?;
+        }
+
+        Ok(())
+    }
+}
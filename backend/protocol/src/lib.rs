@@ -175,6 +175,72 @@ pub struct UserPreference {
     pub submit_on_enter: Option<String>,
 }
 
+/// Spending limits of a user, unset limits are unlimited
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[typeshare]
+pub struct UserBudget {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_cost: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_token: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_cost: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_token: Option<i32>,
+}
+
+/// Who can use a model besides admins, a model without any entry is open to everyone
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[typeshare]
+pub struct ModelAccess {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<i32>,
+    /// Group names, matched against the groups of a user
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+}
+
+/// Groups a user is in, free-form names matched against `ModelAccess::groups`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct UserGroups(pub Vec<String>);
+
+/// What a user is allowed to manage
+#[derive(
+    Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default, EnumIter, DeriveActiveEnum,
+)]
+#[typeshare]
+#[serde(rename_all = "lowercase")]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum UserRole {
+    /// Manages users, models and providers
+    #[sea_orm(string_value = "admin")]
+    Admin,
+    #[default]
+    #[sea_orm(string_value = "member")]
+    Member,
+}
+
+/// What an API key may be used for
+#[derive(
+    Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default, EnumIter, DeriveActiveEnum,
+)]
+#[typeshare]
+#[serde(rename_all = "lowercase")]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum ApiKeyScope {
+    /// Everything the user can do, except managing sessions and API keys
+    #[default]
+    #[sea_orm(string_value = "full")]
+    Full,
+    /// Chatting, along with everything `read` allows
+    #[sea_orm(string_value = "chat")]
+    Chat,
+    /// Reading chats, models and usage, nothing is changed
+    #[sea_orm(string_value = "read")]
+    Read,
+}
+
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq, Default, Serialize)]
 pub enum OcrEngine {
     Native,
@@ -210,14 +276,90 @@ pub struct ModelParameter {
     pub top_p: Option<f32>,
 }
 
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq)]
+pub struct ModelRetry {
+    /// Retries on rate limit(429) and server errors(5xx), default to 2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attempts: Option<u32>,
+    /// Seconds before the first retry, doubled on each retry, default to 1.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backoff: Option<f32>,
+}
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, Deserialize, Default, Serialize, PartialEq)]
+pub struct ModelPrice {
+    pub input: f32,
+    pub output: f32,
+}
+
+/// Upstream API used to serve a model.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Deserialize,
+    Serialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    EnumIter,
+    DeriveActiveEnum,
+)]
+#[typeshare]
+#[serde(rename_all = "lowercase")]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum ProviderType {
+    #[sea_orm(string_value = "openai")]
+    OpenAI,
+    #[sea_orm(string_value = "anthropic")]
+    Anthropic,
+    #[sea_orm(string_value = "google")]
+    Google,
+    #[default]
+    #[sea_orm(string_value = "openrouter")]
+    OpenRouter,
+}
+
+/// BPE encoding used for local token counts.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenizerFamily {
+    /// GPT-4, GPT-3.5 and most other models
+    Cl100k,
+    /// GPT-4o, GPT-4.1, GPT-5 and o-series
+    O200k,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct ModelConfig {
     pub display_name: String,
     pub model_id: String,
+    /// Backend serving this model, default to the OpenRouter(compatible) endpoint
+    #[serde(default)]
+    pub provider: ProviderType,
+    /// Stored provider serving this model, takes precedence over `provider`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_id: Option<i32>,
+    /// Context window in tokens, detected from provider if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<i32>,
+    /// Tokenizer used for local token counts, detected from `model_id` if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokenizer: Option<TokenizerFamily>,
     #[serde(default)]
     pub capability: ModelCapability,
     #[serde(default)]
     pub parameter: ModelParameter,
+    #[serde(default)]
+    pub retry: ModelRetry,
+    /// Price of native providers, taken from the built-in model table if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<ModelPrice>,
+    /// Model ids tried in order when this model keeps failing, served by the same provider
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -1,4 +1,6 @@
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use futures_util::future::BoxFuture;
@@ -7,9 +9,13 @@ use tokio_stream::StreamExt;
 use crate::chat::context::StreamEndReason;
 use crate::chat::converter::*;
 use crate::utils::model::ModelChecker;
-use crate::{chat::*, openrouter};
+use crate::{
+    chat::*,
+    openrouter,
+    providers::{BoxCompletionStream, Completion},
+};
 use entity::file;
-use protocol::ModelConfig;
+use protocol::{ModelConfig, ModelRetry};
 use sea_orm::ActiveValue;
 
 #[derive(Clone)]
@@ -29,15 +35,51 @@ pub struct Configuration {
 pub struct ProcessState {
     pub ctx: Arc<Context>,
     pub completion_ctx: CompletionContext,
+    pub provider: Arc<dyn Completion>,
     pub model: openrouter::Model,
+    /// Models taking over in order when `model` fails
+    pub fallback: VecDeque<openrouter::Model>,
+    pub retry: Retry,
     pub messages: Vec<openrouter::Message>,
+    /// Model of the last `Token::Model` sent to the client
+    pub announced: Option<String>,
+}
+
+/// Longest wait before a retry, however many attempts were made
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Retry policy of the answering model, see [`ModelRetry`]
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+    pub attempts: u32,
+    pub backoff: Duration,
+}
+
+impl Retry {
+    /// Wait before retrying after `attempt` failed retries, doubled each time
+    fn delay(&self, attempt: u32) -> Duration {
+        2u32.checked_pow(attempt)
+            .and_then(|factor| self.backoff.checked_mul(factor))
+            .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF))
+    }
+}
+
+impl From<&ModelRetry> for Retry {
+    fn from(value: &ModelRetry) -> Self {
+        Self {
+            attempts: value.attempts.unwrap_or(2),
+            backoff: Duration::try_from_secs_f32(value.backoff.unwrap_or(1.0))
+                .unwrap_or(MAX_BACKOFF)
+                .min(MAX_BACKOFF),
+        }
+    }
 }
 
 impl Configuration {
     pub fn process(
         &self,
         ctx: Arc<Context>,
-        completion_ctx: CompletionContext,
+        mut completion_ctx: CompletionContext,
     ) -> BoxFuture<'static, Result<()>> {
         let prompt = self.prompt;
         let completion_option = self.completion_option.clone();
@@ -46,20 +88,49 @@ impl Configuration {
         Box::pin(async move {
             let model = <ModelConfig as ModelChecker>::from_toml(&completion_ctx.model.config)
                 .expect("Failed to get model config");
-            let model = model.into();
-            let system_prompt = ctx.prompt.render(prompt, &completion_ctx)?;
+            let provider = ctx.provider_for(&model).await?;
+            let system_prompt =
+                openrouter::Message::System(ctx.prompt.render(prompt, &completion_ctx)?);
+            let counter = TokenCounter::for_model(&model);
+            let reserved = history::reserved_tokens(&counter, &system_prompt, &completion_option);
 
-            let mut messages = vec![openrouter::Message::System(system_prompt)];
-
-            for m in &completion_ctx.messages {
-                messages.extend(db_message_to_openrouter(&ctx, &m.inner).await?);
-            }
+            let mut messages = vec![system_prompt];
+            messages.extend(
+                history::load_history(
+                    &ctx,
+                    &mut completion_ctx,
+                    provider.as_ref(),
+                    &model,
+                    reserved,
+                )
+                .await?,
+            );
+            log::debug!(
+                "Estimated {} prompt tokens for {}",
+                counter.count_messages(&messages),
+                model.model_id
+            );
+            let retry = Retry::from(&model.retry);
+            let fallback_ids = model.fallback.clone();
+            let model: openrouter::Model = model.into();
+            let fallback = fallback_ids
+                .into_iter()
+                .map(|id| openrouter::Model {
+                    id,
+                    price: None,
+                    ..model.clone()
+                })
+                .collect();
 
             let mut state = ProcessState {
                 ctx,
                 completion_ctx,
+                provider,
                 model,
+                fallback,
+                retry,
                 messages,
+                announced: None,
             };
 
             if let Some(err) = Self::process_loop(&mut state, completion_option, tool_handler)
@@ -86,18 +157,23 @@ impl Configuration {
                 + Sync,
         >,
     ) -> Result<(), anyhow::Error> {
-        let message = state.messages.clone();
+        let (mut res, buffered) = Self::open_stream(state, &completion_option).await?;
 
-        let model = openrouter::ModelBuilder::from_model(&state.model).build();
-        let mut res: openrouter::StreamCompletion = state
-            .ctx
-            .openrouter
-            .stream(model, message, completion_option.clone())
-            .await?;
+        // the client learns which model answers, the last event wins after a fallback
+        if state.announced.as_ref() != Some(&state.model.id) {
+            state.announced = Some(state.model.id.clone());
+            state
+                .completion_ctx
+                .add_token(Token::Model(state.model.id.clone()));
+        }
 
         let halt_with_error = state
             .completion_ctx
-            .put_stream((&mut res).map(|resp| resp.map(openrouter_to_buffer_token)))
+            .put_stream(
+                tokio_stream::iter(buffered.into_iter().map(Ok))
+                    .chain(&mut res)
+                    .map(|resp| resp.map(openrouter_to_buffer_token)),
+            )
             .await;
 
         let result = res.get_result();
@@ -106,6 +182,20 @@ impl Configuration {
             .completion_ctx
             .update_usage(result.usage.cost as f32, result.usage.token as i32);
 
+        // nothing reached the client yet, let the next model answer instead
+        if matches!(halt_with_error, Ok(StreamEndReason::Exhausted))
+            && matches!(result.stop_reason, openrouter::FinishReason::Error)
+            && result.toolcalls.is_empty()
+            && result.image.is_empty()
+            && result.responses.iter().all(|resp| {
+                resp.is_empty() || matches!(resp, openrouter::StreamCompletionResp::Usage { .. })
+            })
+            && let Some(next) = state.fallback.pop_front()
+        {
+            Self::switch_model(state, next, "finished with error");
+            return Box::pin(Self::process_loop(state, completion_option, tool_handler)).await;
+        }
+
         state
             .completion_ctx
             .message
@@ -190,4 +280,107 @@ impl Configuration {
         }
         Box::pin(Self::process_loop(state, completion_option, tool_handler)).await
     }
+
+    /// Start streaming, retrying rate limits and server errors before falling back to other models
+    ///
+    /// Leading empty chunks are buffered until the stream yields content, so failures handled
+    /// here never reach the client.
+    async fn open_stream(
+        state: &mut ProcessState,
+        completion_option: &openrouter::CompletionOption,
+    ) -> Result<(BoxCompletionStream, Vec<openrouter::StreamCompletionResp>), openrouter::Error>
+    {
+        loop {
+            let err = match Self::try_stream(state, completion_option).await {
+                Ok(stream) => return Ok(stream),
+                Err(err) => err,
+            };
+            let Some(next) = state.fallback.pop_front() else {
+                return Err(err);
+            };
+            Self::switch_model(state, next, &err.to_string());
+        }
+    }
+
+    async fn try_stream(
+        state: &ProcessState,
+        completion_option: &openrouter::CompletionOption,
+    ) -> Result<(BoxCompletionStream, Vec<openrouter::StreamCompletionResp>), openrouter::Error>
+    {
+        let mut attempt = 0;
+        loop {
+            let model = openrouter::ModelBuilder::from_model(&state.model).build();
+            let err = match state
+                .provider
+                .stream(model, state.messages.clone(), completion_option.clone())
+                .await
+            {
+                Ok(mut res) => {
+                    let mut buffered = Vec::new();
+                    let err = loop {
+                        match res.next().await {
+                            Some(Ok(resp)) if resp.is_empty() => buffered.push(resp),
+                            Some(Ok(resp)) => {
+                                buffered.push(resp);
+                                break None;
+                            }
+                            Some(Err(err)) => break Some(err),
+                            None => break None,
+                        }
+                    };
+                    match err {
+                        Some(err) => err,
+                        None => return Ok((res, buffered)),
+                    }
+                }
+                Err(err) => err,
+            };
+
+            if attempt >= state.retry.attempts || !err.is_retryable() {
+                return Err(err);
+            }
+            let delay = state.retry.delay(attempt);
+            log::warn!(
+                "{} failed, retrying in {:?}: {}",
+                state.model.id,
+                delay,
+                err
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn switch_model(state: &mut ProcessState, next: openrouter::Model, reason: &str) {
+        log::warn!(
+            "{} failed, falling back to {}: {}",
+            state.model.id,
+            next.id,
+            reason
+        );
+        state.model = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay() {
+        let retry = Retry::from(&ModelRetry {
+            attempts: Some(40),
+            backoff: Some(0.5),
+        });
+        assert_eq!(retry.delay(0), Duration::from_millis(500));
+        assert_eq!(retry.delay(3), Duration::from_secs(4));
+        assert_eq!(retry.delay(10), MAX_BACKOFF);
+        assert_eq!(retry.delay(39), MAX_BACKOFF);
+
+        let retry = Retry::from(&ModelRetry {
+            attempts: None,
+            backoff: Some(f32::INFINITY),
+        });
+        assert_eq!(retry.delay(0), MAX_BACKOFF);
+    }
 }
@@ -12,11 +12,13 @@ use crate::chat::deep_prompt::{CompletedStep, ReportInputContext, StepInputConte
 use crate::chat::tools::{get_crawl_tool_def, get_lua_repl_def, get_web_search_tool_def};
 use crate::chat::{CompletionContext, Context, Token};
 use crate::openrouter::{self, ReasoningEffort};
+use crate::providers::Completion;
 
 /// Deep research agent that orchestrates multiple agents for comprehensive research
 pub struct DeepAgent<'a> {
     ctx: Arc<Context>,
     completion_ctx: &'a mut CompletionContext,
+    provider: Arc<dyn Completion>,
     model: openrouter::Model,
     state: Option<Deep>,
     enhanced_prompt: String,
@@ -33,11 +35,13 @@ impl<'a> DeepAgent<'a> {
 
         let model = <ModelConfig as ModelChecker>::from_toml(&completion_ctx.model.config)
             .context("Failed to get model config")?;
+        let provider = ctx.provider_for(&model).await?;
         let model: openrouter::Model = model.into();
 
         let mut agent = DeepAgent {
             ctx: ctx.clone(),
             completion_ctx,
+            provider,
             model,
             state: None,
             enhanced_prompt: String::new(),
@@ -84,9 +88,8 @@ impl<'a> DeepAgent<'a> {
         let enhanced_text = {
             let model = openrouter::ModelBuilder::from_model(&self.model).build();
 
-            let mut stream: openrouter::StreamCompletion = self
-                .ctx
-                .openrouter
+            let mut stream = self
+                .provider
                 .stream(model, messages, openrouter::CompletionOption::default())
                 .await?;
 
@@ -128,8 +131,7 @@ impl<'a> DeepAgent<'a> {
         let model = openrouter::ModelBuilder::from_model(&self.model).build();
 
         let result = self
-            .ctx
-            .openrouter
+            .provider
             .structured::<PlannerResponse>(messages, model, openrouter::CompletionOption::default())
             .await?;
 
@@ -223,9 +225,8 @@ impl<'a> DeepAgent<'a> {
         loop {
             let model = openrouter::ModelBuilder::from_model(&self.model).build();
             let option = openrouter::CompletionOption::tools(&tools);
-            let mut stream: openrouter::StreamCompletion = self
-                .ctx
-                .openrouter
+            let mut stream = self
+                .provider
                 .stream(model, messages.clone(), option)
                 .await?;
 
@@ -330,8 +331,7 @@ impl<'a> DeepAgent<'a> {
         let option = openrouter::CompletionOption::builder()
             .reasoning_effort(ReasoningEffort::Auto)
             .build();
-        let mut stream: openrouter::StreamCompletion =
-            self.ctx.openrouter.stream(model, messages, option).await?;
+        let mut stream = self.provider.stream(model, messages, option).await?;
 
         let halt = self
             .completion_ctx
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use ::entity::*;
 use anyhow::Context as _;
+use pasetors::{keys::SymmetricKey, version4::V4};
 use sea_orm::*;
 use tokio::join;
 use tokio_stream::{Stream, StreamExt};
@@ -18,7 +19,12 @@ use crate::utils::model::ModelChecker;
 use crate::{
     chat::prompt::PromptKind,
     openrouter::{self, ReasoningEffort},
-    utils::blob::BlobDB,
+    providers::{Completion, ModelInfo, ProviderConfig, Providers},
+    utils::{
+        blob::BlobDB,
+        branch::{self, Tree},
+        secret,
+    },
 };
 use protocol::*;
 
@@ -29,10 +35,11 @@ pub enum StreamEndReason {
 }
 
 /// The global context for the chat system.
-/// It holds the database connection, the OpenRouter client, and the channel context.
+/// It holds the database connection, the completion providers, and the channel context.
 pub struct Context {
     pub(super) db: DatabaseConnection,
-    pub(super) openrouter: openrouter::Openrouter,
+    pub(super) providers: Providers,
+    provider_key: SymmetricKey<V4>,
     pub(super) channel: Arc<channel::Context<Token>>,
     pub(super) prompt: Prompt,
     pub(super) blob: Arc<BlobDB>,
@@ -47,12 +54,14 @@ impl Context {
     // TODO: put API Key in main
     pub fn new(
         db: DatabaseConnection,
-        openrouter: openrouter::Openrouter,
+        providers: Providers,
+        provider_key: SymmetricKey<V4>,
         blob: Arc<BlobDB>,
     ) -> Result<Self, anyhow::Error> {
         Ok(Self {
             db,
-            openrouter,
+            providers,
+            provider_key,
             channel: Arc::new(channel::Context::new()),
             prompt: Prompt::new(),
             blob,
@@ -69,8 +78,9 @@ impl Context {
         user_id: i32,
         chat_id: i32,
         model_id: i32,
+        parent_id: i32,
     ) -> impl std::future::Future<Output = Result<CompletionContext, anyhow::Error>> + '_ {
-        CompletionContext::new(self.clone(), user_id, chat_id, model_id)
+        CompletionContext::new(self.clone(), user_id, chat_id, model_id, parent_id)
     }
     pub async fn halt_completion(&self, chat_id: i32) {
         self.channel.stop(chat_id).await
@@ -87,11 +97,74 @@ impl Context {
     }
 
     pub fn get_model_ids(&self) -> Vec<String> {
-        self.openrouter.get_model_ids()
+        self.providers.openrouter().get_model_ids()
     }
 
-    pub fn get_capability(&self, model: &openrouter::Model) -> openrouter::Capability {
-        self.openrouter.get_capability(model)
+    pub fn model_cache_status(&self) -> openrouter::ModelCacheStatus {
+        self.providers.openrouter().cache_status()
+    }
+
+    /// Get the completion backend serving the model
+    pub async fn provider_for(&self, config: &ModelConfig) -> anyhow::Result<Arc<dyn Completion>> {
+        let Some(id) = config.provider_id else {
+            return self.providers.get(config.provider);
+        };
+        if let Some(provider) = self.providers.get_stored(id) {
+            return Ok(provider);
+        }
+
+        let provider = self
+            .stored_provider(id)
+            .await?
+            .with_context(|| format!("provider {} not found", id))?
+            .build();
+
+        self.providers.insert_stored(id, provider.clone());
+        Ok(provider)
+    }
+
+    /// Config of a stored provider with its API key decrypted
+    async fn stored_provider(&self, id: i32) -> anyhow::Result<Option<ProviderConfig>> {
+        let Some(stored) = ::entity::provider::Entity::find_by_id(id)
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(ProviderConfig {
+            provider_type: stored.kind,
+            api_key: secret::decrypt(&self.provider_key, &stored.api_key)?,
+            base_url: stored.base_url,
+            plugins: stored.plugins,
+            usage: stored.usage,
+        }))
+    }
+
+    /// Drop cached client of a stored provider
+    pub fn invalidate_provider(&self, id: i32) {
+        self.providers.invalidate(id);
+    }
+
+    /// List models of a stored provider from its API, cached for a while
+    ///
+    /// `None` if there is no such provider.
+    pub async fn list_models(
+        &self,
+        id: i32,
+    ) -> anyhow::Result<Option<(Vec<ModelInfo>, Option<String>)>> {
+        let Some(config) = self.stored_provider(id).await? else {
+            return Ok(None);
+        };
+        Ok(Some(self.providers.catalog().list(&config).await))
+    }
+
+    /// Get capability of a model, only user overrides are considered if its provider is unavailable
+    pub async fn get_capability(&self, config: &ModelConfig) -> openrouter::Capability {
+        let model: openrouter::Model = config.clone().into();
+        match self.provider_for(config).await {
+            Ok(provider) => provider.get_capability(&model),
+            Err(_) => model.capability.merge(&Default::default()),
+        }
     }
 
     pub fn process(
@@ -123,30 +196,33 @@ pub struct CompletionContext {
 }
 
 impl CompletionContext {
-    /// Creates a new completion context.
+    /// Creates a new completion context replying to `parent_id`.
+    ///
+    /// History is the branch ending at `parent_id`, which gets selected once the reply is created.
     pub async fn new(
         ctx: Arc<Context>,
         user_id: i32,
         chat_id: i32,
         model_id: i32,
+        parent_id: i32,
     ) -> Result<Self, anyhow::Error> {
         let db = &ctx.db;
 
-        let (user, chat_with_msgs, model, msg) = join!(
+        // claim the chat first, so a rejected request leaves no empty reply behind
+        let mut publisher = ctx
+            .channel
+            .clone()
+            .publish(chat_id)
+            .context("only one publisher is allow at same time")?;
+
+        let (user, chat_with_msgs, model) = join!(
             user::Entity::find_by_id(user_id).one(db),
             chat::Entity::find_by_id(chat_id)
                 .find_with_related(message::Entity)
                 .all(db),
             model::Entity::find_by_id(model_id).one(db),
-            message::ActiveModel {
-                chat_id: ActiveValue::Set(chat_id),
-                inner: ActiveValue::Set(MessageInner::default()),
-                ..Default::default()
-            }
-            .insert(db)
         );
 
-        let msg = msg?;
         let user = user?.ok_or_else(|| anyhow::anyhow!("User not found"))?;
         let (chat, msgs) = chat_with_msgs?
             .into_iter()
@@ -154,15 +230,14 @@ impl CompletionContext {
             .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
         let model = model?.ok_or_else(|| anyhow::anyhow!("Model not found"))?;
 
+        let tree: Tree = msgs.iter().map(|m| (m.id, m.parent_id)).collect();
+        let path = tree.path(parent_id);
+        anyhow::ensure!(!path.is_empty(), "Parent message not found");
+        let msgs: Vec<_> = msgs.into_iter().filter(|m| path.contains(&m.id)).collect();
+
         let mut chat = chat.into_active_model();
         chat.model_id = ActiveValue::Set(Some(model.id));
 
-        let mut publisher = ctx
-            .channel
-            .clone()
-            .publish(chat_id)
-            .context("only one publisher is allow at same time")?;
-
         let user_msg_id = msgs
             .iter()
             .filter(|m| matches!(m.inner, MessageInner::User { .. }))
@@ -170,6 +245,17 @@ impl CompletionContext {
             .context("no user message found")?
             .id;
 
+        let msg = message::ActiveModel {
+            chat_id: ActiveValue::Set(chat_id),
+            parent_id: ActiveValue::Set(Some(parent_id)),
+            inner: ActiveValue::Set(MessageInner::default()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        branch::set_leaf(db, chat_id, Some(msg.id)).await?;
+
         publisher.publish(Token::Start {
             id: msg.id,
             user_msg_id,
@@ -286,7 +372,8 @@ impl CompletionContext {
 
         let completion = self
             .ctx
-            .openrouter
+            .provider_for(&model)
+            .await?
             .complete(messages, model.into(), option)
             .await?;
 
@@ -347,6 +434,7 @@ impl CompletionContext {
         });
 
         let db = &self.ctx.db;
+        let mode = self.get_mode();
 
         if let Err(err) = self.chat.update(db).await {
             if !matches!(err, DbErr::RecordNotUpdated) {
@@ -361,6 +449,18 @@ impl CompletionContext {
             }
         }
 
+        usage::ActiveModel {
+            user_id: ActiveValue::Set(self.user.id),
+            model_id: ActiveValue::Set(Some(self.model.id)),
+            mode: ActiveValue::Set(mode),
+            price: ActiveValue::Set(cost),
+            token_count: ActiveValue::Set(token_count),
+            created_at: ActiveValue::Set(time::OffsetDateTime::now_utc().unix_timestamp()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
         Ok(())
     }
 
@@ -0,0 +1,227 @@
+//! Fit chat history into the context window of a model
+//!
+//! History is split into turns, each starting at a user message. When the history is over
+//! budget, the oldest turns are dropped and summarized. The summary is cached on the chat and
+//! only extended when more turns are dropped.
+
+use anyhow::Result;
+use protocol::{MessageInner, ModelConfig};
+use sea_orm::ActiveValue;
+
+use super::{
+    CompletionContext, Context, TokenCounter, converter::db_message_to_openrouter,
+    prompt::PromptKind,
+};
+use crate::openrouter::{self, CompletionOption, Message, ReasoningEffort};
+use crate::providers::Completion;
+
+/// Tokens reserved for the response when `max_tokens` is not set
+const RESERVED_OUTPUT: usize = 4096;
+/// Tokens reserved for the summary of dropped turns
+const SUMMARY_TOKENS: usize = 1024;
+
+struct Turn {
+    /// id of the last message in this turn
+    last_id: i32,
+    messages: Vec<Message>,
+    tokens: usize,
+}
+
+/// Tokens taken by the system prompt, tool definitions and the response
+pub fn reserved_tokens(
+    counter: &TokenCounter,
+    system_prompt: &Message,
+    option: &CompletionOption,
+) -> usize {
+    let tools: usize = option
+        .tools
+        .iter()
+        .map(|tool| {
+            counter.count(&tool.name)
+                + counter.count(&tool.description)
+                + counter.count(&tool.schema.to_string())
+        })
+        .sum();
+    let output = option
+        .max_tokens
+        .map(|x| x as usize)
+        .unwrap_or(RESERVED_OUTPUT);
+    counter.count_message(system_prompt) + tools + output
+}
+
+/// Index of the first turn to keep, newest turns are kept first and the latest one always is
+fn first_kept(tokens: &[usize], budget: usize) -> usize {
+    let mut used = 0;
+    let mut split = tokens.len();
+    while split > 0 {
+        let turn = tokens[split - 1];
+        if split < tokens.len() && used + turn > budget {
+            break;
+        }
+        used += turn;
+        split -= 1;
+    }
+    split
+}
+
+/// Load chat history as messages, summarizing the oldest turns if it doesn't fit the model
+///
+/// `reserved` is the tokens taken outside of history, see [`reserved_tokens`].
+pub async fn load_history(
+    ctx: &Context,
+    completion_ctx: &mut CompletionContext,
+    provider: &dyn Completion,
+    config: &ModelConfig,
+    reserved: usize,
+) -> Result<Vec<Message>> {
+    let counter = TokenCounter::for_model(config);
+    let mut turns: Vec<Turn> = Vec::new();
+    for m in &completion_ctx.messages {
+        let messages: Vec<_> = db_message_to_openrouter(ctx, &m.inner).await?.collect();
+        let tokens = messages.iter().map(|m| counter.count_message(m)).sum();
+        match turns.last_mut() {
+            Some(turn) if !matches!(m.inner, MessageInner::User { .. }) => {
+                turn.last_id = m.id;
+                turn.messages.extend(messages);
+                turn.tokens += tokens;
+            }
+            _ => turns.push(Turn {
+                last_id: m.id,
+                messages,
+                tokens,
+            }),
+        }
+    }
+
+    let window = config
+        .context_window
+        .or_else(|| provider.context_window(&config.model_id))
+        .or_else(|| {
+            ctx.providers
+                .catalog()
+                .context_window(config.provider, &config.model_id)
+        });
+    let Some(window) = window else {
+        return Ok(flatten(turns));
+    };
+
+    let budget = (window.max(0) as usize).saturating_sub(reserved);
+    if turns.iter().map(|t| t.tokens).sum::<usize>() <= budget {
+        return Ok(flatten(turns));
+    }
+
+    let budget = budget.saturating_sub(SUMMARY_TOKENS);
+    let tokens: Vec<_> = turns.iter().map(|t| t.tokens).collect();
+    let kept = turns.split_off(first_kept(&tokens, budget));
+    let dropped = turns;
+
+    let mut messages = Vec::new();
+    if !dropped.is_empty() {
+        log::debug!("Dropping {} turns to fit context window", dropped.len());
+        match summarize(ctx, completion_ctx, provider, config, &dropped, budget).await {
+            Ok(summary) => messages.push(Message::System(format!(
+                "Summary of earlier conversation:\n\n{}",
+                summary
+            ))),
+            Err(err) => log::warn!("Failed to summarize dropped history: {}", err),
+        }
+    }
+    messages.extend(flatten(kept));
+    Ok(messages)
+}
+
+fn flatten(turns: Vec<Turn>) -> Vec<Message> {
+    turns.into_iter().flat_map(|turn| turn.messages).collect()
+}
+
+/// Summary of dropped turns, reuse or extend the one cached on the chat
+async fn summarize(
+    ctx: &Context,
+    completion_ctx: &mut CompletionContext,
+    provider: &dyn Completion,
+    config: &ModelConfig,
+    dropped: &[Turn],
+    budget: usize,
+) -> Result<String> {
+    let until = dropped.last().map(|turn| turn.last_id).unwrap_or_default();
+    let summary = completion_ctx.chat.summary.try_as_ref().cloned().flatten();
+    let summary_until = completion_ctx
+        .chat
+        .summary_until
+        .try_as_ref()
+        .copied()
+        .flatten();
+
+    let (previous, pending) = match (summary, summary_until) {
+        // a summary of another branch doesn't apply
+        (_, Some(covered)) if !dropped.iter().any(|turn| turn.last_id == covered) => {
+            (None, dropped)
+        }
+        (Some(summary), Some(covered)) if covered == until => return Ok(summary),
+        (Some(summary), Some(covered)) if covered < until => {
+            let start = dropped.partition_point(|turn| turn.last_id <= covered);
+            (Some(summary), &dropped[start..])
+        }
+        _ => (None, dropped),
+    };
+
+    // summarizer has the same window, skip the oldest turns it cannot take
+    let tokens: Vec<_> = pending.iter().map(|t| t.tokens).collect();
+    let pending = &pending[first_kept(&tokens, budget)..];
+
+    let mut input = String::new();
+    if let Some(previous) = previous {
+        input.push_str(&format!("Previous summary:\n\n{}\n\n", previous));
+    }
+    input.push_str("Conversation:\n\n");
+    for message in pending.iter().flat_map(|turn| turn.messages.iter()) {
+        match message {
+            Message::User(text) | Message::MultipartUser { text, .. } => {
+                input.push_str(&format!("User: {}\n\n", text))
+            }
+            Message::Assistant { content, .. } if !content.is_empty() => {
+                input.push_str(&format!("Assistant: {}\n\n", content))
+            }
+            Message::ToolCall(call) => {
+                input.push_str(&format!("Tool call {}: {}\n\n", call.name, call.arguments))
+            }
+            Message::ToolResult(result) => {
+                input.push_str(&format!("Tool result: {}\n\n", result.content))
+            }
+            _ => {}
+        }
+    }
+
+    let system_prompt = ctx.prompt.render(PromptKind::Summary, completion_ctx)?;
+    let messages = vec![Message::System(system_prompt), Message::User(input)];
+
+    let option = openrouter::CompletionOption::builder()
+        .reasoning_effort(ReasoningEffort::Low)
+        .max_tokens(SUMMARY_TOKENS as i32)
+        .build();
+
+    let completion = provider
+        .complete(messages, config.clone().into(), option)
+        .await?;
+
+    completion_ctx.update_usage(completion.price as f32, completion.token as i32);
+
+    let summary = completion.response.trim().to_string();
+    completion_ctx.chat.summary = ActiveValue::Set(Some(summary.clone()));
+    completion_ctx.chat.summary_until = ActiveValue::Set(Some(until));
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_newest_turns() {
+        assert_eq!(first_kept(&[10, 10, 10], 100), 0);
+        assert_eq!(first_kept(&[10, 10, 10], 25), 1);
+        // the latest turn is kept even if it's over budget
+        assert_eq!(first_kept(&[10, 10, 50], 25), 2);
+    }
+}
@@ -4,10 +4,11 @@ mod configs;
 mod context;
 pub mod converter;
 mod deep_prompt;
+mod history;
 mod prompt;
 mod token;
 mod tools;
 
 pub use configs::Configurations;
 pub use context::{CompletionContext, Context};
-pub use token::Token;
+pub use token::{Token, TokenCounter};
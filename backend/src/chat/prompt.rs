@@ -14,6 +14,7 @@ pub enum PromptKind {
     Normal,
     Search,
     TitleGen,
+    Summary,
     Coordinator,
 }
 
@@ -23,6 +24,7 @@ impl PromptKind {
             PromptKind::Normal => "normal",
             PromptKind::Search => "search",
             PromptKind::TitleGen => "title",
+            PromptKind::Summary => "summary",
             PromptKind::Coordinator => "coordinator",
         }
     }
@@ -40,6 +42,8 @@ impl Prompt {
             include_str!("../../../prompts/title_generation.md"),
         )
         .unwrap();
+        env.add_template("summary", include_str!("../../../prompts/summary.md"))
+            .unwrap();
         env.add_template("normal", include_str!("../../../prompts/normal.md"))
             .unwrap();
         env.add_template("search", include_str!("../../../prompts/search.md"))
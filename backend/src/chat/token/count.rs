@@ -0,0 +1,146 @@
+//! Local token counts for outgoing messages
+//!
+//! Text is encoded with the BPE of the model's [`TokenizerFamily`], the encodings OpenAI models
+//! use. Models of other vendors are counted with one of them, which is close but not exact, and
+//! files are a flat guess. The provider's `usage` remains the source of truth for billing.
+
+use protocol::{AssistantChunk, MessageInner, ModelConfig, TokenizerFamily};
+use tiktoken_rs::{CoreBPE, cl100k_base_singleton, o200k_base_singleton};
+
+use crate::openrouter::Message;
+
+/// Rough token cost of an attached file or image
+const FILE_TOKENS: usize = 1024;
+/// Tokens taken by role and separators of each message
+const MESSAGE_OVERHEAD: usize = 3;
+/// Tokens priming the assistant reply
+const REPLY_OVERHEAD: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TokenCounter {
+    family: TokenizerFamily,
+}
+
+impl TokenCounter {
+    pub fn new(family: TokenizerFamily) -> Self {
+        Self { family }
+    }
+
+    /// Counter for a model, `tokenizer` in config takes precedence over detection
+    pub fn for_model(config: &ModelConfig) -> Self {
+        Self::new(
+            config
+                .tokenizer
+                .unwrap_or_else(|| detect_family(&config.model_id)),
+        )
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        self.bpe().encode_ordinary(text).len()
+    }
+
+    pub fn count_message(&self, message: &Message) -> usize {
+        let content = match message {
+            Message::System(text) | Message::User(text) => self.count(text),
+            Message::MultipartUser { text, files } => self.count(text) + files.len() * FILE_TOKENS,
+            Message::Assistant {
+                content, images, ..
+            } => self.count(content) + images.len() * FILE_TOKENS,
+            Message::ToolCall(call) => self.count(&call.name) + self.count(&call.arguments),
+            Message::ToolResult(result) => self.count(&result.content),
+        };
+        content + MESSAGE_OVERHEAD
+    }
+
+    /// Tokens of a whole request, including the reply priming
+    pub fn count_messages(&self, messages: &[Message]) -> usize {
+        messages
+            .iter()
+            .map(|m| self.count_message(m))
+            .sum::<usize>()
+            + REPLY_OVERHEAD
+    }
+
+    /// Tokens of a stored message as it would be sent again
+    ///
+    /// Reasoning, annotations and deep research progress are not resent, so they are not counted.
+    pub fn count_inner(&self, inner: &MessageInner) -> usize {
+        let content = match inner {
+            MessageInner::User { text, files } => self.count(text) + files.len() * FILE_TOKENS,
+            MessageInner::Assistant(chunks) => chunks
+                .iter()
+                .map(|chunk| match chunk {
+                    AssistantChunk::Text(text) => self.count(text),
+                    AssistantChunk::ToolCall { name, arg, .. } => {
+                        self.count(name) + self.count(arg) + MESSAGE_OVERHEAD
+                    }
+                    AssistantChunk::ToolResult { response, .. } => {
+                        self.count(response) + MESSAGE_OVERHEAD
+                    }
+                    AssistantChunk::Image(_) => FILE_TOKENS,
+                    _ => 0,
+                })
+                .sum(),
+        };
+        content + MESSAGE_OVERHEAD
+    }
+
+    fn bpe(&self) -> &'static CoreBPE {
+        match self.family {
+            TokenizerFamily::Cl100k => cl100k_base_singleton(),
+            TokenizerFamily::O200k => o200k_base_singleton(),
+        }
+    }
+}
+
+/// Tokenizer family from a model id, with or without the vendor prefix
+pub fn detect_family(model_id: &str) -> TokenizerFamily {
+    let name = model_id.rsplit('/').next().unwrap_or(model_id);
+    const O200K: &[&str] = &[
+        "gpt-4o", "gpt-4.1", "gpt-4.5", "gpt-5", "gpt-oss", "chatgpt", "o1", "o3", "o4",
+    ];
+    match O200K.iter().any(|prefix| name.starts_with(prefix)) {
+        true => TokenizerFamily::O200k,
+        false => TokenizerFamily::Cl100k,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_english() {
+        let counter = TokenCounter::new(TokenizerFamily::Cl100k);
+        // "Hello" "," " world" "!"
+        assert_eq!(counter.count("Hello, world!"), 4);
+        assert_eq!(counter.count(""), 0);
+    }
+
+    /// Counts of the OpenAI cookbook's encoding comparison
+    #[test]
+    fn count_matches_tiktoken() {
+        let cl100k = TokenCounter::new(TokenizerFamily::Cl100k);
+        let o200k = TokenCounter::new(TokenizerFamily::O200k);
+        for (text, cl100k_tokens, o200k_tokens) in [
+            ("antidisestablishmentarianism", 6, 6),
+            ("2 + 2 = 4", 7, 7),
+            ("お誕生日おめでとう", 9, 8),
+            ("tiktoken is great!", 6, 6),
+        ] {
+            assert_eq!(cl100k.count(text), cl100k_tokens, "cl100k {text}");
+            assert_eq!(o200k.count(text), o200k_tokens, "o200k {text}");
+        }
+    }
+
+    #[test]
+    fn detect_from_model_id() {
+        assert_eq!(detect_family("openai/gpt-4o-mini"), TokenizerFamily::O200k);
+        assert_eq!(detect_family("o3-mini"), TokenizerFamily::O200k);
+        assert_eq!(detect_family("openai/gpt-4-turbo"), TokenizerFamily::Cl100k);
+        assert_eq!(
+            detect_family("anthropic/claude-sonnet-4"),
+            TokenizerFamily::Cl100k
+        );
+    }
+}
@@ -1,3 +1,6 @@
+mod count;
+pub use count::TokenCounter;
+
 use super::channel::Mergeable;
 
 #[derive(Debug, Clone)]
@@ -30,6 +33,8 @@ pub enum Token {
         token: i32,
     },
     Title(String),
+    /// Model answering the completion, sent again when a fallback model takes over
+    Model(String),
     Start {
         id: i32,
         user_msg_id: i32,
@@ -82,6 +87,7 @@ impl Mergeable for Token {
             | Token::DeepStepToolResult(_)
             | Token::Complete { .. }
             | Token::Title(_)
+            | Token::Model(_)
             | Token::Start { .. }
             | Token::ToolCall { .. }
             | Token::DeepStepToolCall { .. }
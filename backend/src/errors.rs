@@ -63,6 +63,10 @@ pub enum ErrorKind {
     /// Frontend should redirect to login page.
     Unauthorized,
 
+    /// User is authenticated but lacks the role required for the action.
+    /// Frontend should hide admin features from members.
+    Forbidden,
+
     /// Session token is malformed, expired, or tampered with.
     /// Usually means the user needs to log in again.
     MalformedToken,
@@ -99,6 +103,22 @@ pub enum ErrorKind {
     /// - Tool rejected input parameters
     /// Frontend should show tool-specific error context.
     ToolCallFail,
+
+    /// User has used up a spending budget set by an admin.
+    /// Frontend should show the reason, new messages are rejected until the budget resets.
+    QuotaExceeded,
+
+    /// Too many failed logins for the username or from the client.
+    /// Frontend should show the reason, which tells how long to wait or that an admin must unlock.
+    TooManyAttempts,
+
+    /// Password is correct but has to be changed before logging in.
+    /// Frontend should ask for a new password and log in again with `new_password`.
+    PasswordChangeRequired,
+
+    /// New password doesn't meet the password policy.
+    /// Frontend should show the reason and ask for another password.
+    WeakPassword,
 }
 
 pub type JsonResult<T> = Result<Json<T>, Json<Error>>;
@@ -0,0 +1,262 @@
+//! ChatGPT `conversations.json`
+//!
+//! Each conversation is a tree in `mapping`, with `current_node` as the selected leaf. Tool calls
+//! are assistant messages sent to a `recipient` other than `all`, followed by a message from the
+//! `tool` role. Images are `asset_pointer`s to files in the export zip.
+
+use std::collections::HashMap;
+
+use protocol::AssistantChunk;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{Archive, Attachment, Body, Chunk, Conversation, Node, seconds};
+
+#[derive(Debug, Deserialize)]
+struct Export {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    create_time: Option<f64>,
+    #[serde(default)]
+    update_time: Option<f64>,
+    mapping: HashMap<String, MappingNode>,
+    #[serde(default)]
+    current_node: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MappingNode {
+    id: String,
+    #[serde(default)]
+    message: Option<Message>,
+    #[serde(default)]
+    parent: Option<String>,
+    #[serde(default)]
+    children: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    author: Author,
+    #[serde(default)]
+    create_time: Option<f64>,
+    content: Value,
+    #[serde(default)]
+    recipient: Option<String>,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct Author {
+    role: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Metadata {
+    #[serde(default)]
+    is_visually_hidden_from_conversation: bool,
+    #[serde(default)]
+    attachments: Vec<FileRef>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileRef {
+    id: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    mime_type: Option<String>,
+}
+
+pub(super) fn parse(
+    list: Vec<Value>,
+    archive: &Archive,
+    missing: &mut u32,
+) -> anyhow::Result<Vec<Conversation>> {
+    let mut conversations = Vec::new();
+    for value in list {
+        let export: Export = serde_json::from_value(value)?;
+        let created_at = export.create_time.map(seconds).unwrap_or_default();
+
+        // keep the order of `children`, it's the order alternatives were made in
+        let mut order: Vec<&MappingNode> = Vec::with_capacity(export.mapping.len());
+        let mut stack: Vec<&MappingNode> = export
+            .mapping
+            .values()
+            .filter(|x| {
+                x.parent
+                    .as_ref()
+                    .is_none_or(|x| !export.mapping.contains_key(x))
+            })
+            .collect();
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            if order.len() > export.mapping.len() {
+                break;
+            }
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .filter_map(|x| export.mapping.get(x)),
+            );
+        }
+
+        let mut nodes = Vec::with_capacity(order.len());
+        for node in order {
+            nodes.push(Node {
+                id: node.id.clone(),
+                parent: node.parent.clone(),
+                created_at: node
+                    .message
+                    .as_ref()
+                    .and_then(|x| x.create_time)
+                    .map(seconds)
+                    .unwrap_or(created_at),
+                body: match &node.message {
+                    Some(x) => body(&node.id, node.parent.as_deref(), x, archive, missing),
+                    None => None,
+                },
+            });
+        }
+
+        let conversation = Conversation {
+            title: export.title,
+            created_at,
+            updated_at: export.update_time.map(seconds).unwrap_or(created_at),
+            ..Default::default()
+        }
+        .with_nodes(nodes, export.current_node.as_deref());
+        if !conversation.is_empty() {
+            conversations.push(conversation);
+        }
+    }
+    Ok(conversations)
+}
+
+fn body(
+    id: &str,
+    parent: Option<&str>,
+    message: &Message,
+    archive: &Archive,
+    missing: &mut u32,
+) -> Option<Body> {
+    if message.metadata.is_visually_hidden_from_conversation {
+        return None;
+    }
+    let content_type = message.content["content_type"].as_str().unwrap_or_default();
+    let text = text(&message.content);
+    let recipient = message.recipient.as_deref().unwrap_or("all");
+
+    match message.author.role.as_str() {
+        "user" if content_type == "text" || content_type == "multimodal_text" => {
+            let mut files = Vec::new();
+            for pointer in images(&message.content) {
+                files.extend(file(archive, missing, pointer, None, None));
+            }
+            for attachment in &message.metadata.attachments {
+                // images are in the content already
+                if files.iter().any(|x| x.name.starts_with(&attachment.id)) {
+                    continue;
+                }
+                files.extend(file(
+                    archive,
+                    missing,
+                    &attachment.id,
+                    attachment.name.as_deref(),
+                    attachment.mime_type.as_deref(),
+                ));
+            }
+            Some(Body::User { text, files })
+        }
+        "assistant" => {
+            let chunk = match content_type {
+                "thoughts" => {
+                    let thoughts = message.content["thoughts"].as_array()?;
+                    let text = thoughts
+                        .iter()
+                        .filter_map(|x| x["content"].as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n\n");
+                    AssistantChunk::Reasoning(text)
+                }
+                _ if recipient != "all" => AssistantChunk::ToolCall {
+                    id: id.to_owned(),
+                    arg: text,
+                    name: recipient.to_owned(),
+                },
+                "text" | "multimodal_text" | "code" => AssistantChunk::Text(text),
+                _ => return None,
+            };
+            Body::assistant(vec![Chunk::Ready(chunk)])
+        }
+        "tool" => {
+            let mut list = Vec::new();
+            for pointer in images(&message.content) {
+                list.extend(file(archive, missing, pointer, None, None).map(Chunk::Image));
+            }
+            if !text.trim().is_empty() {
+                list.insert(
+                    0,
+                    Chunk::Ready(AssistantChunk::ToolResult {
+                        id: parent.unwrap_or_default().to_owned(),
+                        response: text,
+                    }),
+                );
+            }
+            Body::assistant(list)
+        }
+        _ => None,
+    }
+}
+
+/// Text parts of the content, or its `text` for code and tool output
+fn text(content: &Value) -> String {
+    match content["parts"].as_array() {
+        Some(parts) => parts
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => content["text"]
+            .as_str()
+            .or(content["result"].as_str())
+            .unwrap_or_default()
+            .to_owned(),
+    }
+}
+
+/// Ids of the images in the content, like `file-service://file-abc` or `sediment://file_abc`
+fn images(content: &Value) -> impl Iterator<Item = &str> {
+    content["parts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|x| x["content_type"] == "image_asset_pointer")
+        .filter_map(|x| x["asset_pointer"].as_str())
+        .map(|x| x.split_once("://").map_or(x, |(_, id)| id))
+}
+
+pub(super) fn file(
+    archive: &Archive,
+    missing: &mut u32,
+    id: &str,
+    name: Option<&str>,
+    mime_type: Option<&str>,
+) -> Option<Attachment> {
+    let found = archive.find(id).unwrap_or_else(|err| {
+        log::warn!("skipped attachment {}: {}", id, err);
+        None
+    });
+    let Some((file_name, data)) = found else {
+        *missing += 1;
+        return None;
+    };
+    Some(Attachment {
+        name: name.map_or(file_name, str::to_owned),
+        mime_type: mime_type.map(str::to_owned),
+        data,
+    })
+}
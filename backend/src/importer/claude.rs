@@ -0,0 +1,172 @@
+//! Claude `conversations.json`
+//!
+//! Messages are listed oldest first, newer exports link them with `parent_message_uuid`. Text
+//! extracted from attachments is included and stored as a text file, images and other uploads
+//! aren't part of the export.
+
+use protocol::AssistantChunk;
+use serde::Deserialize;
+use serde_json::Value;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+use super::{Attachment, Body, Chunk, Conversation, Node};
+
+#[derive(Debug, Deserialize)]
+struct Export {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    updated_at: Option<String>,
+    chat_messages: Vec<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    uuid: String,
+    #[serde(default)]
+    parent_message_uuid: Option<String>,
+    sender: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    content: Vec<Value>,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    attachments: Vec<FileAttachment>,
+    /// uploads without extracted text, their content isn't exported
+    #[serde(default)]
+    files: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileAttachment {
+    file_name: String,
+    #[serde(default)]
+    extracted_content: Option<String>,
+}
+
+pub(super) fn parse(list: Vec<Value>, missing: &mut u32) -> anyhow::Result<Vec<Conversation>> {
+    let mut conversations = Vec::new();
+    for value in list {
+        let export: Export = serde_json::from_value(value)?;
+        let created_at = timestamp(export.created_at.as_deref()).unwrap_or_default();
+
+        let mut previous: Option<String> = None;
+        let mut nodes = Vec::with_capacity(export.chat_messages.len());
+        for message in export.chat_messages {
+            // older exports have no links, the list is the conversation
+            let parent = message
+                .parent_message_uuid
+                .clone()
+                .or_else(|| previous.clone());
+            previous = Some(message.uuid.clone());
+            *missing += message.files.len() as u32;
+            nodes.push(Node {
+                id: message.uuid.clone(),
+                parent,
+                created_at: timestamp(message.created_at.as_deref()).unwrap_or(created_at),
+                body: body(message),
+            });
+        }
+
+        let conversation = Conversation {
+            title: export.name.filter(|x| !x.is_empty()),
+            created_at,
+            updated_at: timestamp(export.updated_at.as_deref()).unwrap_or(created_at),
+            ..Default::default()
+        }
+        .with_nodes(nodes, None);
+        if !conversation.is_empty() {
+            conversations.push(conversation);
+        }
+    }
+    Ok(conversations)
+}
+
+fn body(message: Message) -> Option<Body> {
+    match message.sender.as_str() {
+        "human" => {
+            let text = match message.text.is_empty() {
+                true => message
+                    .content
+                    .iter()
+                    .filter(|x| x["type"] == "text")
+                    .filter_map(|x| x["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                false => message.text,
+            };
+            let files = message
+                .attachments
+                .into_iter()
+                .filter_map(|x| {
+                    Some(Attachment {
+                        name: x.file_name,
+                        mime_type: Some("text/plain".to_owned()),
+                        data: x.extracted_content?.into_bytes(),
+                    })
+                })
+                .collect();
+            Some(Body::User { text, files })
+        }
+        "assistant" => {
+            let mut chunks = Vec::new();
+            let mut last_call = String::new();
+            for (i, item) in message.content.iter().enumerate() {
+                let chunk = match item["type"].as_str().unwrap_or_default() {
+                    "text" => AssistantChunk::Text(string(&item["text"])),
+                    "thinking" => AssistantChunk::Reasoning(string(&item["thinking"])),
+                    "tool_use" => {
+                        last_call = item["id"]
+                            .as_str()
+                            .map_or_else(|| format!("{}-{}", message.uuid, i), str::to_owned);
+                        AssistantChunk::ToolCall {
+                            id: last_call.clone(),
+                            arg: item["input"].to_string(),
+                            name: item["name"].as_str().unwrap_or_default().to_owned(),
+                        }
+                    }
+                    "tool_result" => AssistantChunk::ToolResult {
+                        id: item["tool_use_id"]
+                            .as_str()
+                            .map_or_else(|| last_call.clone(), str::to_owned),
+                        response: tool_result(&item["content"]),
+                    },
+                    _ => continue,
+                };
+                chunks.push(Chunk::Ready(chunk));
+            }
+            if chunks.is_empty() {
+                chunks.push(Chunk::Ready(AssistantChunk::Text(message.text)));
+            }
+            Body::assistant(chunks)
+        }
+        _ => None,
+    }
+}
+
+fn string(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_owned()
+}
+
+/// Text of a tool result, a string or a list of content blocks
+fn tool_result(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(list) => list
+            .iter()
+            .filter_map(|x| x["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn timestamp(value: Option<&str>) -> Option<i64> {
+    OffsetDateTime::parse(value?, &Rfc3339)
+        .ok()
+        .map(OffsetDateTime::unix_timestamp)
+}
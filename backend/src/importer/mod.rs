@@ -0,0 +1,633 @@
+//! Import conversations exported from other chat UIs
+//!
+//! ChatGPT and Claude `conversations.json` and OpenWebUI chat exports are told apart by their
+//! shape. The zip file of a whole export works too, ChatGPT keeps uploaded and generated images
+//! next to `conversations.json` in it. Only files a conversation refers to are decompressed, and
+//! only up to [`MAX_ENTRY_SIZE`] each and [`MAX_TOTAL_SIZE`] together.
+//!
+//! Each source is parsed into [`Conversation`]s first, which [`save`] then writes as chats owned
+//! by one user, keeping branches where the source has them. Imported chats have no model set.
+
+mod chatgpt;
+mod claude;
+mod openwebui;
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use anyhow::{Context as _, bail};
+use bytes::Bytes;
+use entity::{chat, file, message, user};
+use protocol::{AssistantChunk, FileMetadata, MessageInner, ModeKind};
+use sea_orm::{ActiveValue::Set, ColumnTrait, DbConn, EntityTrait, QueryFilter, TransactionTrait};
+use serde::Serialize;
+use serde_json::Value;
+use typeshare::typeshare;
+
+use crate::utils::{blob::BlobDB, branch};
+
+/// A chat parsed from an export
+#[derive(Debug, Default)]
+pub struct Conversation {
+    pub title: Option<String>,
+    /// unix timestamp in seconds
+    pub created_at: i64,
+    /// unix timestamp in seconds
+    pub updated_at: i64,
+    /// parents come before their children
+    pub messages: Vec<Message>,
+    /// index of the last message of the selected branch, the newest one if unset
+    pub leaf: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Message {
+    /// index into `Conversation::messages`
+    pub parent: Option<usize>,
+    /// unix timestamp in seconds
+    pub created_at: i64,
+    pub body: Body,
+}
+
+#[derive(Debug)]
+pub enum Body {
+    User {
+        text: String,
+        files: Vec<Attachment>,
+    },
+    Assistant(Vec<Chunk>),
+}
+
+impl Body {
+    /// Assistant message without empty text, `None` if nothing is left
+    fn assistant(chunks: Vec<Chunk>) -> Option<Self> {
+        let chunks: Vec<_> = chunks
+            .into_iter()
+            .filter(|x| match x {
+                Chunk::Ready(AssistantChunk::Text(text) | AssistantChunk::Reasoning(text)) => {
+                    !text.trim().is_empty()
+                }
+                _ => true,
+            })
+            .collect();
+        (!chunks.is_empty()).then_some(Body::Assistant(chunks))
+    }
+}
+
+#[derive(Debug)]
+pub enum Chunk {
+    Ready(AssistantChunk),
+    /// becomes `AssistantChunk::Image` once the file is stored
+    Image(Attachment),
+}
+
+#[derive(Debug)]
+pub struct Attachment {
+    pub name: String,
+    pub mime_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// A message as the source stores it, linked by the source's ids
+#[derive(Debug)]
+struct Node {
+    id: String,
+    parent: Option<String>,
+    created_at: i64,
+    /// `None` for messages that aren't shown, like system prompts, their children move up
+    body: Option<Body>,
+}
+
+/// Most bytes one file of a zip export may decompress to
+pub const MAX_ENTRY_SIZE: u64 = 1024 * 1024 * 256; // 256MB
+/// Most bytes the files read from a zip export may decompress to together
+pub const MAX_TOTAL_SIZE: u64 = 1024 * 1024 * 1024; // 1GB
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// Other files of a zip export, decompressed when a conversation refers to them
+#[derive(Default)]
+pub struct Archive {
+    zip: Option<RefCell<zip::ZipArchive<Box<dyn ReadSeek>>>>,
+    /// index of each entry by file name
+    names: HashMap<String, usize>,
+    /// bytes left to decompress
+    budget: Cell<u64>,
+}
+
+impl Archive {
+    fn new(zip: zip::ZipArchive<Box<dyn ReadSeek>>) -> anyhow::Result<Self> {
+        let mut zip = zip;
+        let mut names = HashMap::new();
+        for i in 0..zip.len() {
+            let entry = zip.by_index_raw(i)?;
+            if entry.is_dir() {
+                continue;
+            }
+            if let Some(name) = Path::new(entry.name()).file_name().and_then(|x| x.to_str()) {
+                names.insert(name.to_owned(), i);
+            }
+        }
+        Ok(Self {
+            zip: Some(RefCell::new(zip)),
+            names,
+            budget: Cell::new(MAX_TOTAL_SIZE),
+        })
+    }
+
+    /// The file named after the id `prefix`, exports name files like `{id}-{name}` or `{id}.png`
+    fn find(&self, prefix: &str) -> anyhow::Result<Option<(String, Vec<u8>)>> {
+        if prefix.is_empty() {
+            return Ok(None);
+        }
+        let Some((name, index)) = self
+            .names
+            .iter()
+            .filter(|(name, _)| {
+                // so `file-1` doesn't pick up `file-10`
+                name.strip_prefix(prefix)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '_', '.']))
+            })
+            .min_by_key(|(name, _)| name.len())
+        else {
+            return Ok(None);
+        };
+        Ok(Some((name.clone(), self.read(*index)?)))
+    }
+
+    fn read(&self, index: usize) -> anyhow::Result<Vec<u8>> {
+        let Some(zip) = &self.zip else {
+            bail!("not a zip file");
+        };
+        let mut zip = zip.borrow_mut();
+        let entry = zip.by_index(index)?;
+        let name = entry.name().to_owned();
+
+        // the sizes in the zip file are whatever its author wrote there
+        let limit = MAX_ENTRY_SIZE.min(self.budget.get());
+        let mut data = Vec::new();
+        entry.take(limit + 1).read_to_end(&mut data)?;
+        if data.len() as u64 > limit {
+            bail!("{} is too large once decompressed", name);
+        }
+        self.budget.set(self.budget.get() - data.len() as u64);
+        Ok(data)
+    }
+}
+
+/// What an import added
+#[derive(Debug, Default, Serialize)]
+#[typeshare]
+pub struct ImportSummary {
+    pub chats: u32,
+    pub messages: u32,
+    pub files: u32,
+    /// Attachments referenced by the export but not included in it, or too large to import
+    pub missing_files: u32,
+}
+
+/// Parse a JSON export or the zip file holding one
+pub fn parse(reader: impl Read + Seek + 'static) -> anyhow::Result<(Vec<Conversation>, u32)> {
+    let mut reader = reader;
+    let mut magic = [0u8; 4];
+    let is_zip = reader.read_exact(&mut magic).is_ok() && magic == *b"PK\x03\x04";
+    reader.rewind()?;
+
+    if !is_zip {
+        let value: Value = serde_json::from_reader(std::io::BufReader::new(reader))
+            .context("the export is neither a zip file nor JSON")?;
+        return parse_value(value, &Archive::default());
+    }
+
+    let mut archive = Archive::new(zip::ZipArchive::new(Box::new(reader) as Box<dyn ReadSeek>)?)?;
+
+    // OpenWebUI exports are a single JSON file under any name
+    let json = match archive.names.remove("conversations.json") {
+        Some(json) => json,
+        None => {
+            let name = archive
+                .names
+                .keys()
+                .find(|x| x.ends_with(".json"))
+                .cloned()
+                .context("no conversations.json in the zip file")?;
+            archive.names.remove(&name).unwrap()
+        }
+    };
+    let json = archive.read(json)?;
+    let value: Value = serde_json::from_slice(&json).context("malformed conversations.json")?;
+    drop(json);
+    parse_value(value, &archive)
+}
+
+fn parse_value(value: Value, archive: &Archive) -> anyhow::Result<(Vec<Conversation>, u32)> {
+    let list = match value {
+        Value::Array(list) => list,
+        value @ Value::Object(_) => vec![value],
+        _ => bail!("expected a list of conversations"),
+    };
+    let Some(first) = list.first() else {
+        return Ok((Vec::new(), 0));
+    };
+
+    let mut missing = 0;
+    let conversations = if first.get("mapping").is_some() {
+        chatgpt::parse(list, archive, &mut missing)?
+    } else if first.get("chat_messages").is_some() {
+        claude::parse(list, &mut missing)?
+    } else if first.get("chat").is_some() || first.get("history").is_some() {
+        openwebui::parse(list, &mut missing)?
+    } else {
+        bail!("unknown export format, expected ChatGPT, Claude or OpenWebUI");
+    };
+    Ok((conversations, missing))
+}
+
+impl Conversation {
+    /// Order `nodes` so parents come first and link them by index
+    ///
+    /// Consecutive assistant messages without alternatives, like a tool call followed by its
+    /// result, are merged into one, which is how llumen stores them.
+    fn with_nodes(mut self, nodes: Vec<Node>, current: Option<&str>) -> Self {
+        let ids: HashMap<_, _> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.id.clone(), i))
+            .collect();
+        let mut children: HashMap<Option<usize>, Vec<usize>> = HashMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            let parent = node.parent.as_ref().and_then(|x| ids.get(x)).copied();
+            // a broken link makes a root rather than losing the message
+            let parent = parent.filter(|x| *x != i);
+            children.entry(parent).or_default().push(i);
+        }
+
+        let mut nodes: Vec<_> = nodes.into_iter().map(Some).collect();
+        // index of the message each node ended up in
+        let mut placed: HashMap<usize, Option<usize>> = HashMap::new();
+        let mut stack: Vec<(usize, Option<usize>)> = children
+            .get(&None)
+            .into_iter()
+            .flatten()
+            .rev()
+            .map(|x| (*x, None))
+            .collect();
+
+        while let Some((i, parent)) = stack.pop() {
+            let Some(node) = nodes[i].take() else {
+                // visited, the source has a cycle
+                continue;
+            };
+            let siblings = node
+                .parent
+                .as_ref()
+                .and_then(|x| ids.get(x))
+                .and_then(|x| children.get(&Some(*x)))
+                .map_or(1, Vec::len);
+            let merge = siblings == 1
+                && parent.is_some_and(|x| matches!(self.messages[x].body, Body::Assistant(_)));
+
+            let index = match node.body {
+                None => parent,
+                Some(Body::Assistant(chunks)) if merge => {
+                    if let Some(Body::Assistant(prev)) = parent.map(|x| &mut self.messages[x].body)
+                    {
+                        prev.extend(chunks);
+                    }
+                    parent
+                }
+                Some(body) => {
+                    self.messages.push(Message {
+                        parent,
+                        created_at: node.created_at,
+                        body,
+                    });
+                    Some(self.messages.len() - 1)
+                }
+            };
+            placed.insert(i, index);
+
+            for child in children.get(&Some(i)).into_iter().flatten().rev() {
+                stack.push((*child, index));
+            }
+        }
+
+        self.leaf = current
+            .and_then(|x| ids.get(x))
+            .and_then(|x| placed.get(x).copied().flatten());
+        self
+    }
+
+    fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+/// Write the conversations as chats of `owner_id`
+pub async fn save(
+    conn: &DbConn,
+    blob: &BlobDB,
+    owner_id: i32,
+    conversations: Vec<Conversation>,
+) -> anyhow::Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+
+    for conversation in conversations {
+        let mut blobs = Vec::new();
+        let txn = conn.begin().await?;
+
+        let chat_id = chat::Entity::insert(chat::ActiveModel {
+            owner_id: Set(owner_id),
+            model_id: Set(None),
+            mode: Set(ModeKind::Normal),
+            title: Set(conversation.title),
+            created_at: Set(conversation.created_at),
+            updated_at: Set(conversation.updated_at),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?
+        .last_insert_id;
+
+        let mut ids: Vec<i32> = Vec::with_capacity(conversation.messages.len());
+        for message in conversation.messages {
+            let file = |attachment: &Attachment| file::ActiveModel {
+                chat_id: Set(Some(chat_id)),
+                owner_id: Set(Some(owner_id)),
+                mime_type: Set(attachment
+                    .mime_type
+                    .clone()
+                    .or_else(|| infer::get(&attachment.data).map(|x| x.mime_type().to_owned()))),
+                created_at: Set(message.created_at),
+                updated_at: Set(message.created_at),
+                ..Default::default()
+            };
+
+            let inner = match message.body {
+                Body::User { text, files } => {
+                    let mut metadata = Vec::new();
+                    for attachment in files {
+                        let id = file::Entity::insert(file(&attachment))
+                            .exec(&txn)
+                            .await?
+                            .last_insert_id;
+                        metadata.push(FileMetadata {
+                            name: attachment.name,
+                            id,
+                        });
+                        blobs.push((id, attachment.data));
+                    }
+                    MessageInner::User {
+                        text,
+                        files: metadata,
+                    }
+                }
+                Body::Assistant(chunks) => {
+                    let mut inner = Vec::new();
+                    for chunk in chunks {
+                        match chunk {
+                            Chunk::Ready(chunk) => inner.push(chunk),
+                            Chunk::Image(attachment) => {
+                                let id = file::Entity::insert(file(&attachment))
+                                    .exec(&txn)
+                                    .await?
+                                    .last_insert_id;
+                                inner.push(AssistantChunk::Image(id));
+                                blobs.push((id, attachment.data));
+                            }
+                        }
+                    }
+                    MessageInner::Assistant(inner)
+                }
+            };
+
+            let id = message::Entity::insert(message::ActiveModel {
+                chat_id: Set(chat_id),
+                parent_id: Set(message.parent.map(|x| ids[x])),
+                price: Set(0.0),
+                token_count: Set(0),
+                inner: Set(inner),
+                created_at: Set(message.created_at),
+                updated_at: Set(message.created_at),
+                ..Default::default()
+            })
+            .exec(&txn)
+            .await?
+            .last_insert_id;
+            ids.push(id);
+        }
+
+        branch::set_leaf(&txn, chat_id, conversation.leaf.map(|x| ids[x])).await?;
+
+        // blobs go first, the chat is rolled back if one fails and must not point at nothing
+        let files = blobs.len() as u32;
+        let mut written = Vec::with_capacity(blobs.len());
+        for (id, data) in blobs {
+            let size = data.len();
+            let inserted = blob
+                .insert(id, size, tokio_stream::once(Bytes::from(data)))
+                .await;
+            if let Err(err) = inserted {
+                discard(blob, &written);
+                return Err(err.into());
+            }
+            written.push(id);
+        }
+        if let Err(err) = txn.commit().await {
+            discard(blob, &written);
+            return Err(err.into());
+        }
+
+        summary.chats += 1;
+        summary.messages += ids.len() as u32;
+        summary.files += files;
+    }
+
+    Ok(summary)
+}
+
+/// Remove the blobs of a chat that wasn't saved
+fn discard(blob: &BlobDB, ids: &[i32]) {
+    for id in ids {
+        if let Err(err) = blob.delete(*id) {
+            log::warn!("cannot remove blob {} of a failed import: {}", id, err);
+        }
+    }
+}
+
+/// `backend import <username> <file>`, imports an export from the command line
+pub async fn cli(conn: &DbConn, blob: &BlobDB, args: &[String]) -> anyhow::Result<()> {
+    let [username, path] = args else {
+        bail!("usage: backend import <username> <conversations.json or export.zip>");
+    };
+    let user = user::Entity::find()
+        .filter(user::Column::Name.eq(username))
+        .one(conn)
+        .await?
+        .with_context(|| format!("no user named {}", username))?;
+
+    let path = path.clone();
+    let (conversations, missing) = tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&path).with_context(|| format!("cannot open {}", path))?;
+        parse(std::io::BufReader::new(file))
+    })
+    .await??;
+
+    let summary = save(conn, blob, user.id, conversations).await?;
+    println!(
+        "imported {} chats with {} messages and {} files for {}",
+        summary.chats, summary.messages, summary.files, user.name
+    );
+    if missing > 0 {
+        println!(
+            "{} attachments were not in the export or too large",
+            missing
+        );
+    }
+    Ok(())
+}
+
+/// Unix timestamp in seconds from seconds or milliseconds
+fn seconds(value: f64) -> i64 {
+    match value > 1e11 {
+        true => (value / 1000.0) as i64,
+        false => value as i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use serde_json::json;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    fn archive(files: &[(&str, &[u8])]) -> Archive {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        let data = writer.finish().unwrap();
+        let zip = zip::ZipArchive::new(Box::new(data) as Box<dyn ReadSeek>).unwrap();
+        Archive::new(zip).unwrap()
+    }
+
+    fn shape(conversation: &Conversation) -> Vec<(Option<usize>, &'static str, usize)> {
+        conversation
+            .messages
+            .iter()
+            .map(|x| match &x.body {
+                Body::User { files, .. } => (x.parent, "user", files.len()),
+                Body::Assistant(chunks) => (x.parent, "assistant", chunks.len()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_exports() {
+        // a regenerated answer, the first one called a tool
+        let chatgpt = json!([{
+            "title": "Weather",
+            "create_time": 1700000000.5,
+            "current_node": "a2",
+            "mapping": {
+                "root": {"id": "root", "message": null, "parent": null, "children": ["sys"]},
+                "sys": {"id": "sys", "parent": "root", "children": ["u"], "message": {
+                    "author": {"role": "system"}, "content": {"content_type": "text", "parts": [""]}}},
+                "u": {"id": "u", "parent": "sys", "children": ["call", "a2"], "message": {
+                    "author": {"role": "user"}, "content": {"content_type": "multimodal_text", "parts": [
+                        {"content_type": "image_asset_pointer", "asset_pointer": "file-service://file-1"},
+                        "weather?"]}}},
+                "call": {"id": "call", "parent": "u", "children": ["result"], "message": {
+                    "author": {"role": "assistant"}, "recipient": "browser",
+                    "content": {"content_type": "code", "text": "search(\"weather\")"}}},
+                "result": {"id": "result", "parent": "call", "children": ["a1"], "message": {
+                    "author": {"role": "tool"}, "content": {"content_type": "text", "parts": ["sunny"]}}},
+                "a1": {"id": "a1", "parent": "result", "children": [], "message": {
+                    "author": {"role": "assistant"}, "content": {"content_type": "text", "parts": ["Sunny"]}}},
+                "a2": {"id": "a2", "parent": "u", "children": [], "message": {
+                    "author": {"role": "assistant"}, "content": {"content_type": "text", "parts": ["Rainy"]}}}
+            }
+        }]);
+        let archive = archive(&[("file-1-cat.png", b"png")]);
+        let (list, missing) = parse_value(chatgpt, &archive).unwrap();
+        assert_eq!(missing, 0);
+        assert_eq!(
+            shape(&list[0]),
+            [
+                (None, "user", 1),
+                (Some(0), "assistant", 3),
+                (Some(0), "assistant", 1)
+            ]
+        );
+        assert_eq!(list[0].leaf, Some(2));
+        assert_eq!(list[0].created_at, 1700000000);
+
+        let claude = json!([{
+            "name": "Hi",
+            "created_at": "2024-05-01T12:00:00.000000Z",
+            "chat_messages": [
+                {"uuid": "1", "sender": "human", "text": "hi", "attachments": [
+                    {"file_name": "a.txt", "extracted_content": "text"}]},
+                {"uuid": "2", "sender": "assistant", "text": "", "content": [
+                    {"type": "thinking", "thinking": "greet"}, {"type": "text", "text": "hello"}]}
+            ]
+        }]);
+        let (list, _) = parse_value(claude, &Archive::default()).unwrap();
+        assert_eq!(
+            shape(&list[0]),
+            [(None, "user", 1), (Some(0), "assistant", 2)]
+        );
+        assert_eq!(list[0].created_at, 1714564800);
+
+        let openwebui = json!([{
+            "title": "Math",
+            "created_at": 1700000000,
+            "chat": {"history": {"currentId": "b", "messages": {
+                "b": {"id": "b", "parentId": "a", "role": "assistant", "timestamp": 1700000002,
+                      "content": "<details type=\"reasoning\" done=\"true\">\n<summary>Thought</summary>\n> add\n</details>\n2"},
+                "a": {"id": "a", "parentId": null, "role": "user", "timestamp": 1700000001,
+                      "content": "1+1", "files": [{"type": "image", "url": "data:image/png;base64,cG5n"}]}
+            }}}
+        }]);
+        let (list, _) = parse_value(openwebui, &Archive::default()).unwrap();
+        assert_eq!(
+            shape(&list[0]),
+            [(None, "user", 1), (Some(0), "assistant", 2)]
+        );
+        let Body::Assistant(chunks) = &list[0].messages[1].body else {
+            unreachable!()
+        };
+        assert!(matches!(&chunks[0], Chunk::Ready(AssistantChunk::Reasoning(x)) if x == "add"));
+        assert!(matches!(&chunks[1], Chunk::Ready(AssistantChunk::Text(x)) if x == "2"));
+    }
+
+    #[test]
+    fn archive_budget() {
+        let archive = archive(&[
+            ("file-1.png", b"png"),
+            ("file-2.png", b"png"),
+            ("file-30.png", b"png"),
+        ]);
+        archive.budget.set(5);
+        assert_eq!(archive.find("file-1").unwrap().unwrap().1, b"png");
+        assert!(archive.find("file-2").is_err());
+        assert!(archive.find("file-3").unwrap().is_none());
+
+        // too large files count as missing, the rest of the import goes on
+        let mut missing = 0;
+        assert!(chatgpt::file(&archive, &mut missing, "file-2", None, None).is_none());
+        assert_eq!(missing, 1);
+    }
+}
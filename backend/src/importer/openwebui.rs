@@ -0,0 +1,167 @@
+//! OpenWebUI chat export, a list of chats or a single one
+//!
+//! Each chat keeps a tree in `history.messages` with `currentId` as the selected leaf. Images are
+//! inlined as data URLs, reasoning is a `<details type="reasoning">` block in the content.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use protocol::AssistantChunk;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{Attachment, Body, Chunk, Conversation, Node, seconds};
+
+#[derive(Debug, Deserialize)]
+struct Export {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    created_at: Option<f64>,
+    #[serde(default)]
+    updated_at: Option<f64>,
+    /// missing when the chat itself was exported
+    #[serde(default)]
+    chat: Option<Chat>,
+    #[serde(default)]
+    history: Option<History>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    timestamp: Option<f64>,
+    history: History,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct History {
+    messages: HashMap<String, Message>,
+    #[serde(default)]
+    current_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Message {
+    id: String,
+    #[serde(default)]
+    parent_id: Option<String>,
+    role: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    timestamp: Option<f64>,
+    #[serde(default)]
+    files: Vec<Value>,
+}
+
+static REASONING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?s)<details type="reasoning"[^>]*>\s*(?:<summary>.*?</summary>)?(.*?)</details>"#,
+    )
+    .unwrap()
+});
+
+pub(super) fn parse(list: Vec<Value>, missing: &mut u32) -> anyhow::Result<Vec<Conversation>> {
+    let mut conversations = Vec::new();
+    for value in list {
+        let export: Export = serde_json::from_value(value)?;
+        let (title, timestamp, history) = match (export.chat, export.history) {
+            (Some(chat), _) => (export.title.or(chat.title), chat.timestamp, chat.history),
+            (None, Some(history)) => (export.title, None, history),
+            (None, None) => anyhow::bail!("chat without history"),
+        };
+        let created_at = export
+            .created_at
+            .or(timestamp)
+            .map(seconds)
+            .unwrap_or_default();
+
+        let mut messages: Vec<_> = history.messages.into_values().collect();
+        // the map has no order, alternatives are told apart by age
+        messages.sort_by(|a, b| {
+            a.timestamp
+                .unwrap_or_default()
+                .total_cmp(&b.timestamp.unwrap_or_default())
+        });
+        let nodes = messages
+            .into_iter()
+            .map(|message| Node {
+                id: message.id.clone(),
+                parent: message.parent_id.clone(),
+                created_at: message.timestamp.map(seconds).unwrap_or(created_at),
+                body: body(message, missing),
+            })
+            .collect();
+
+        let conversation = Conversation {
+            title,
+            created_at,
+            updated_at: export.updated_at.map(seconds).unwrap_or(created_at),
+            ..Default::default()
+        }
+        .with_nodes(nodes, history.current_id.as_deref());
+        if !conversation.is_empty() {
+            conversations.push(conversation);
+        }
+    }
+    Ok(conversations)
+}
+
+fn body(message: Message, missing: &mut u32) -> Option<Body> {
+    let mut files = Vec::new();
+    for file in &message.files {
+        match data_url(file["url"].as_str().unwrap_or_default()) {
+            Some((mime_type, data)) => files.push(Attachment {
+                name: file["name"].as_str().unwrap_or("image").to_owned(),
+                mime_type: Some(mime_type),
+                data,
+            }),
+            None => *missing += 1,
+        }
+    }
+
+    match message.role.as_str() {
+        "user" => Some(Body::User {
+            text: message.content,
+            files,
+        }),
+        "assistant" => {
+            let mut chunks = Vec::new();
+            let mut rest = 0;
+            for captures in REASONING.captures_iter(&message.content) {
+                let whole = captures.get(0).unwrap();
+                chunks.push(text(&message.content[rest..whole.start()]));
+                let reasoning = captures[1]
+                    .lines()
+                    .map(|x| x.strip_prefix('>').unwrap_or(x).trim_start())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                chunks.push(Chunk::Ready(AssistantChunk::Reasoning(
+                    reasoning.trim().to_owned(),
+                )));
+                rest = whole.end();
+            }
+            chunks.push(text(&message.content[rest..]));
+            chunks.extend(files.into_iter().map(Chunk::Image));
+            Body::assistant(chunks)
+        }
+        _ => None,
+    }
+}
+
+fn text(text: &str) -> Chunk {
+    Chunk::Ready(AssistantChunk::Text(text.trim().to_owned()))
+}
+
+/// Mime type and content of a `data:` URL with base64 data
+fn data_url(url: &str) -> Option<(String, Vec<u8>)> {
+    let (meta, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let mime_type = meta.strip_suffix(";base64")?;
+    Some((mime_type.to_owned(), STANDARD.decode(data).ok()?))
+}
@@ -24,14 +24,18 @@
 //!
 //! 4. **Middlewares**: Handles authentication (PASETO tokens), compression (Zstd), and logging.
 //!
-//! 5. **OpenRouter Client**: Abstracts interactions with OpenRouter's LLM API, including
-//!    streaming completions and tool calling.
+//! 5. **Providers**: OpenRouter client and native OpenAI/Anthropic/Google clients behind a
+//!    common completion trait, including streaming completions and tool calling.
+//!
+//! Running `backend import <username> <file>` imports chats exported from other UIs instead of
+//! starting the server, see the `importer` module.
 //!
 //! The MiMalloc allocator is used for better performance on memory-constrained systems.
 
 mod chat;
 mod config;
 mod errors;
+mod importer;
 mod middlewares;
 mod openrouter;
 mod providers;
@@ -42,6 +46,7 @@ mod runner;
 mod utils;
 
 use std::{
+    net::SocketAddr,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -55,6 +60,7 @@ use middlewares::cache_control::CacheControlLayer;
 use migration::MigratorTrait;
 use mimalloc::MiMalloc;
 use pasetors::{keys::SymmetricKey, version4::V4};
+use providers::{AnthropicClient, GoogleClient, OpenAIClient, Providers};
 use sea_orm::{ConnectionTrait, Database, DbConn, EntityTrait};
 use tokio::{net::TcpListener, signal};
 use tower::ServiceBuilder;
@@ -82,17 +88,26 @@ use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
 ///
 /// * `conn`: Database connection pool for all database operations via SeaORM
 /// * `key`: Encryption key for PASETO token generation/validation used in authentication
+/// * `provider_key`: Encryption key for API keys of stored providers
 /// * `hasher`: Password hasher for user authentication and security
 /// * `processor`: Main chat processing pipeline context managing LLM completions
 /// * `blob`: Blob database for storing binary data and file uploads
-/// * `user_header`: Optional HTTP header name for header-based authentication (SSO/proxy integration)
+/// * `header_auth`: Optional header-based authentication (SSO/proxy integration), configured by
+///   `TRUSTED_HEADER*` environment variables
+/// * `oidc`: Optional OpenID Connect client, configured by `OIDC_*` environment variables
+/// * `login_guard`: Failed login counters throttling password guessing
+/// * `password_policy`: Rules for new passwords, configured by `PASSWORD_*` environment variables
 pub struct AppState {
     pub conn: DbConn,
     pub key: SymmetricKey<V4>,
+    pub provider_key: SymmetricKey<V4>,
     pub hasher: Hasher,
     pub processor: Arc<Context>,
     pub blob: Arc<BlobDB>,
-    pub auth_header: Option<String>,
+    pub header_auth: Option<utils::header_auth::HeaderAuthConfig>,
+    pub oidc: Option<utils::oidc::Oidc>,
+    pub login_guard: utils::login_guard::LoginGuard,
+    pub password_policy: utils::password_policy::PasswordPolicy,
 }
 
 /// Attempts to load the OpenRouter API key from environment variables.
@@ -176,17 +191,7 @@ async fn main() {
     #[cfg(feature = "tracing")]
     let _main_span = info_span!("llumen_backend_startup").entered();
 
-    let api_key = load_api_key();
-    let api_base = var("API_BASE").unwrap_or_else(|_| {
-        var("OPENAI_API_BASE").unwrap_or("https://openrouter.ai/api".to_string())
-    });
     let database_url = var("DATABASE_URL").unwrap_or("sqlite://db.sqlite?mode=rwc".to_owned());
-    let bind_addr = var("BIND_ADDR").unwrap_or("0.0.0.0:8001".to_owned());
-    let static_dir = var("STATIC_DIR").unwrap_or(
-        option_env!("STATIC_DIR")
-            .unwrap_or("../frontend/build")
-            .to_owned(),
-    );
     let blob_url = match var("BLOB_URL") {
         Ok(x) => PathBuf::from(x),
         Err(_) => {
@@ -218,6 +223,33 @@ async fn main() {
     .await
     .expect("Failed to set pragmas");
 
+    let blob = Arc::new(
+        BlobDB::new_from_path(blob_url)
+            .await
+            .expect("Cannot open blob db"),
+    );
+
+    // `backend import <username> <file>` imports chats instead of serving
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|x| x == "import") {
+        if let Err(err) = importer::cli(&conn, &blob, &args[1..]).await {
+            eprintln!("Import failed: {:#}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let api_key = load_api_key();
+    let api_base = var("API_BASE").unwrap_or_else(|_| {
+        var("OPENAI_API_BASE").unwrap_or("https://openrouter.ai/api".to_string())
+    });
+    let bind_addr = var("BIND_ADDR").unwrap_or("0.0.0.0:8001".to_owned());
+    let static_dir = var("STATIC_DIR").unwrap_or(
+        option_env!("STATIC_DIR")
+            .unwrap_or("../frontend/build")
+            .to_owned(),
+    );
+
     let key = SymmetricKey::from(
         &Config::find_by_id("paseto_key")
             .one(&conn)
@@ -229,28 +261,47 @@ async fn main() {
     )
     .expect("Cannot parse paseto key");
 
-    let openrouter = openrouter::Openrouter::new(api_key, api_base);
-
-    let blob = Arc::new(
-        BlobDB::new_from_path(blob_url)
+    let provider_key = SymmetricKey::from(
+        &Config::find_by_id("provider_key")
+            .one(&conn)
             .await
-            .expect("Cannot open blob db"),
-    );
+            .unwrap()
+            .context("Cannot find provider key")
+            .unwrap()
+            .value,
+    )
+    .expect("Cannot parse provider key");
+
+    let mut providers = Providers::new(openrouter::Openrouter::new(api_key, api_base));
+    if let Ok(key) = var("OPENAI_API_KEY") {
+        providers = providers.with_openai(OpenAIClient::new(key));
+    }
+    if let Ok(key) = var("ANTHROPIC_API_KEY") {
+        providers = providers.with_anthropic(AnthropicClient::new(key));
+    }
+    if let Ok(key) = var("GEMINI_API_KEY").or_else(|_| var("GOOGLE_API_KEY")) {
+        providers = providers.with_google(GoogleClient::new(key));
+    }
 
     let processor = Arc::new(
-        Context::new(conn.clone(), openrouter, blob.clone())
+        Context::new(conn.clone(), providers, provider_key.clone(), blob.clone())
             .expect("Failed to create pipeline context"),
     );
 
-    let auth_header = var("TRUSTED_HEADER").ok();
+    let header_auth = utils::header_auth::HeaderAuthConfig::from_env();
+    let oidc = utils::oidc::OidcConfig::from_env().map(utils::oidc::Oidc::new);
 
     let state = Arc::new(AppState {
         conn,
         key,
+        provider_key,
         hasher: Hasher::default(),
         processor,
         blob,
-        auth_header,
+        header_auth,
+        oidc,
+        login_guard: utils::login_guard::LoginGuard::from_env(),
+        password_policy: utils::password_policy::PasswordPolicy::from_env(),
     });
 
     let mut cache_control = CacheControlLayer::new();
@@ -272,6 +323,9 @@ async fn main() {
                 .nest("/message", routes::message::routes())
                 .nest("/model", routes::model::routes())
                 .nest("/provider", routes::provider::routes())
+                .nest("/session", routes::session::routes())
+                .nest("/api_key", routes::api_key::routes())
+                .nest("/usage", routes::usage::routes())
                 .layer(middlewares::compression::ZstdCompressionLayer)
                 // only compress plain text content
                 .nest("/file", routes::file::routes())
@@ -282,6 +336,15 @@ async fn main() {
                 .nest("/auth", routes::auth::routes())
                 .layer(middlewares::logger::LoggerLayer),
         )
+        .nest(
+            "/v1",
+            routes::openai::routes()
+                .layer(middleware::from_extractor_with_state::<
+                    middlewares::auth::Middleware,
+                    _,
+                >(state.clone()))
+                .layer(middlewares::logger::LoggerLayer),
+        )
         .fallback_service(
             // side notes about artifact size:
             // 1. br sized about 1.3Mb, uncompressed sized about 4Mb
@@ -313,7 +376,8 @@ async fn main() {
     let _server_span = info_span!("server_startup", bind_addr = %bind_addr).entered();
 
     let tcp = TcpListener::bind(bind_addr).await.unwrap();
-    axum::serve(tcp, app)
+    // peer address is needed to throttle logins per client
+    axum::serve(tcp, app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
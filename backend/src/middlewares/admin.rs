@@ -0,0 +1,25 @@
+use axum::{Json, extract::FromRequestParts, http::request::Parts};
+use protocol::UserRole;
+
+use crate::errors::*;
+
+/// Reject users that are not admins, must be layered inside [`super::auth::Middleware`]
+pub struct Middleware;
+
+impl<S: Send + Sync> FromRequestParts<S> for Middleware {
+    type Rejection = Json<Error>;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<UserRole>() {
+            Some(UserRole::Admin) => Ok(Self),
+            Some(UserRole::Member) => Err(Json(Error {
+                error: ErrorKind::Forbidden,
+                reason: "admin only".to_owned(),
+            })),
+            None => Err(Json(Error {
+                error: ErrorKind::Unauthorized,
+                reason: "not authenticated".to_owned(),
+            })),
+        }
+    }
+}
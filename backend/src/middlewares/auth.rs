@@ -2,16 +2,28 @@ use std::sync::Arc;
 
 use axum::{
     Json,
-    extract::FromRequestParts,
+    extract::{FromRequestParts, OriginalUri},
     http::{header, request::Parts},
 };
 use pasetors::{Local, claims::ClaimsValidationRules, local, token::UntrustedToken, version4::V4};
 
-use crate::{AppState, errors::*};
+use crate::{
+    AppState,
+    errors::*,
+    utils::{api_key, session},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct UserId(pub i32);
 
+/// Session of the token used for the request
+#[derive(Debug, Clone, Copy)]
+pub struct SessionId(pub i32);
+
+/// API key used for the request, instead of a session
+#[derive(Debug, Clone, Copy)]
+pub struct ApiKeyId(pub i32);
+
 pub struct Middleware;
 
 impl FromRequestParts<Arc<AppState>> for Middleware {
@@ -28,6 +40,34 @@ impl FromRequestParts<Arc<AppState>> for Middleware {
             .kind(ErrorKind::Unauthorized)?;
 
         let token = token.to_str().kind(ErrorKind::MalformedToken)?;
+        let token = token.strip_prefix("Bearer ").unwrap_or(token);
+
+        if token.starts_with(api_key::KEY_PREFIX) {
+            let (key, user) = api_key::find_active(&state.conn, token)
+                .await
+                .kind(ErrorKind::Internal)?
+                .ok_or("API key is revoked or expired")
+                .kind(ErrorKind::Unauthorized)?;
+
+            // nested routers see a stripped path
+            let path = match parts.extensions.get::<OriginalUri>() {
+                Some(OriginalUri(uri)) => uri.path(),
+                None => parts.uri.path(),
+            };
+            if !api_key::allows(key.scope, path) {
+                return Err(Json(Error {
+                    error: ErrorKind::Forbidden,
+                    reason: format!("API key is not allowed to call {}", path),
+                }));
+            }
+
+            parts.extensions.insert(UserId(user.id));
+            parts.extensions.insert(ApiKeyId(key.id));
+            parts.extensions.insert(user.role);
+
+            return Ok(Self);
+        }
+
         let token = UntrustedToken::<Local, V4>::try_from(token).kind(ErrorKind::MalformedToken)?;
         let validation_rules = ClaimsValidationRules::new();
         let token = local::decrypt(&state.key, &token, &validation_rules, None, None)
@@ -43,6 +83,22 @@ impl FromRequestParts<Arc<AppState>> for Middleware {
             .ok_or("Missing claim")
             .kind(ErrorKind::MalformedToken)? as i32;
 
+        // tokens issued before sessions were tracked have no jti, they have to log in again
+        let jti = token
+            .payload_claims()
+            .and_then(|x| x.get_claim("jti"))
+            .and_then(|x| x.as_str())
+            .ok_or("Missing token id")
+            .kind(ErrorKind::MalformedToken)?;
+
+        // deleting a user deletes its sessions, so this also rejects deleted users
+        let (session, user) = session::find_active(&state.conn, jti)
+            .await
+            .kind(ErrorKind::Internal)?
+            .filter(|(session, _)| session.user_id == user_id)
+            .ok_or("session is revoked")
+            .kind(ErrorKind::Unauthorized)?;
+
         #[cfg(feature = "tracing")]
         {
             use tracing::info;
@@ -50,6 +106,8 @@ impl FromRequestParts<Arc<AppState>> for Middleware {
         }
 
         parts.extensions.insert(UserId(user_id));
+        parts.extensions.insert(SessionId(session.id));
+        parts.extensions.insert(user.role);
 
         Ok(Self)
     }
@@ -39,6 +39,23 @@ impl fmt::Display for Error {
     }
 }
 
+impl Error {
+    /// Whether the request may succeed if sent again, i.e. rate limited or upstream server errors
+    pub fn is_retryable(&self) -> bool {
+        let status = match self {
+            Error::Http(e) if e.is_timeout() || e.is_connect() => return true,
+            Error::Http(e) => e.status().map(|s| s.as_u16() as i32),
+            Error::EventSource(reqwest_eventsource::Error::Transport(_)) => return true,
+            Error::EventSource(reqwest_eventsource::Error::InvalidStatusCode(s, _)) => {
+                Some(s.as_u16() as i32)
+            }
+            Error::Api { code, .. } => *code,
+            _ => None,
+        };
+        matches!(status, Some(429 | 500..=599))
+    }
+}
+
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
@@ -12,9 +12,12 @@ mod test;
 static HTTP_REFERER: &str = "https://github.com/pinkfuwa/llumen";
 static X_TITLE: &str = "llumen";
 
+pub use error::Error;
 pub use message::{File, Image, Message, MessageToolCall, MessageToolResult};
 pub use model::{Capability, MaybeCapability, Model, ModelBuilder};
-pub use openrouter::Openrouter;
+pub use openrouter::{
+    ChatCompletion, JsonSchema, ModelCacheStatus, Openrouter, StructuredCompletion, list_models,
+};
 pub use option::{CompletionOption, ReasoningEffort, Tool};
-pub use raw::FinishReason;
-pub use stream::{StreamCompletion, StreamCompletionResp, ToolCall};
+pub use raw::{FinishReason, Model as ModelMetadata};
+pub use stream::{StreamCompletion, StreamCompletionResp, StreamResult, ToolCall, Usage};
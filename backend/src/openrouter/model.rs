@@ -1,5 +1,5 @@
-use super::option::Tool;
-use protocol::OcrEngine;
+use super::{option::Tool, raw};
+use protocol::{ModelPrice, OcrEngine};
 
 #[derive(Clone, Default)]
 pub struct Capability {
@@ -21,6 +21,78 @@ pub struct MaybeCapability {
     pub audio: Option<bool>,
 }
 
+impl MaybeCapability {
+    /// Merge user overrides with capabilities reported by upstream.
+    ///
+    /// Unknown capabilities are assumed supported, except OCR which is disabled.
+    pub fn merge(&self, upstream: &MaybeCapability) -> Capability {
+        macro_rules! merge {
+            ($v:ident) => {
+                match self.$v {
+                    Some(v) => v,
+                    None => upstream.$v.unwrap_or(true),
+                }
+            };
+        }
+        Capability {
+            image_output: merge!(image_output),
+            image_input: merge!(image_input),
+            structured_output: merge!(structured_output),
+            toolcall: merge!(toolcall),
+            ocr: match &self.ocr {
+                Some(v) => v.clone(),
+                None => upstream.ocr.clone().unwrap_or(OcrEngine::Disabled),
+            },
+            audio: merge!(audio),
+        }
+    }
+}
+
+impl From<&raw::Model> for MaybeCapability {
+    fn from(model: &raw::Model) -> Self {
+        let supports_file_modality = model
+            .architecture
+            .input_modalities
+            .contains(&raw::Modality::File);
+
+        MaybeCapability {
+            image_output: Some(
+                model
+                    .architecture
+                    .output_modalities
+                    .contains(&raw::Modality::Image),
+            ),
+            image_input: Some(
+                model
+                    .architecture
+                    .input_modalities
+                    .contains(&raw::Modality::Image),
+            ),
+            structured_output: Some(
+                model
+                    .supported_parameters
+                    .contains(&raw::SupportedParams::StructuredOutput),
+            ),
+            toolcall: Some(
+                model
+                    .supported_parameters
+                    .contains(&raw::SupportedParams::Tools),
+            ),
+            ocr: Some(if supports_file_modality {
+                OcrEngine::Native
+            } else {
+                OcrEngine::Text
+            }),
+            audio: Some(
+                model
+                    .architecture
+                    .input_modalities
+                    .contains(&raw::Modality::Audio),
+            ),
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct Model {
     pub id: String,
@@ -30,6 +102,8 @@ pub struct Model {
     pub top_p: Option<f32>,
     // capabilities override
     pub capability: MaybeCapability,
+    // price override of native providers
+    pub price: Option<ModelPrice>,
 }
 
 impl Model {
@@ -46,6 +120,7 @@ pub struct ModelBuilder {
     top_p: Option<f32>,
     tools: Vec<Tool>,
     capability: MaybeCapability,
+    price: Option<ModelPrice>,
 }
 
 impl ModelBuilder {
@@ -58,6 +133,7 @@ impl ModelBuilder {
             top_p: None,
             tools: Vec::new(),
             capability: MaybeCapability::default(),
+            price: None,
         }
     }
 
@@ -70,6 +146,7 @@ impl ModelBuilder {
             top_p: model.top_p,
             tools: Vec::new(),
             capability: model.capability.clone(),
+            price: model.price,
         }
     }
 
@@ -136,6 +213,7 @@ impl ModelBuilder {
             top_k: self.top_k,
             top_p: self.top_p,
             capability: self.capability,
+            price: self.price,
         }
     }
 }
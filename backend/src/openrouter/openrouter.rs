@@ -1,5 +1,8 @@
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, Weak};
+use std::time::Duration;
+
+use time::OffsetDateTime;
 
 use super::message::*;
 use crate::openrouter::{StreamCompletion, option::CompletionOption};
@@ -10,7 +13,15 @@ use protocol::OcrEngine;
 static HTTP_REFERER: &str = "https://github.com/pinkfuwa/llumen";
 static X_TITLE: &str = "llumen";
 
-async fn fetch_models(url: &str, api_key: &str) -> Result<Vec<raw::Model>, Error> {
+/// Interval between refreshes of model metadata
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Backoff bounds when fetching model metadata fails
+const RETRY_MIN: Duration = Duration::from_secs(5);
+const RETRY_MAX: Duration = Duration::from_secs(5 * 60);
+
+/// Fetch model metadata of an OpenRouter(compatible) endpoint
+pub async fn list_models(api_base: &str, api_key: &str) -> Result<Vec<raw::Model>, Error> {
+    let url = format!("{}/v1/models", api_base.trim_end_matches('/'));
     let client = reqwest::Client::new();
     let response = client
         .get(url)
@@ -24,13 +35,65 @@ async fn fetch_models(url: &str, api_key: &str) -> Result<Vec<raw::Model>, Error
     Ok(model.data)
 }
 
+#[derive(Default)]
+struct ModelCache {
+    models: HashMap<String, raw::Model>,
+    refreshed_at: Option<OffsetDateTime>,
+}
+
+/// Snapshot of the model metadata cache
+pub struct ModelCacheStatus {
+    /// `None` if never refreshed successfully
+    pub refreshed_at: Option<OffsetDateTime>,
+    pub count: usize,
+}
+
+/// Keep model metadata fresh until the owning client is dropped
+///
+/// Failed fetches are retried with exponential backoff, then it refreshes on `REFRESH_INTERVAL`.
+fn spawn_refresher(cache: Weak<RwLock<ModelCache>>, api_base: String, api_key: String) {
+    tokio::spawn(async move {
+        let mut backoff = RETRY_MIN;
+        loop {
+            let result = list_models(&api_base, &api_key).await;
+
+            let Some(cache) = cache.upgrade() else {
+                break;
+            };
+            let wait = match result {
+                Ok(model_list) => {
+                    log::info!("{} models available", model_list.len());
+                    let mut cache = cache.write().unwrap();
+                    cache.models = model_list.into_iter().map(|m| (m.id.clone(), m)).collect();
+                    cache.refreshed_at = Some(OffsetDateTime::now_utc());
+                    backoff = RETRY_MIN;
+                    REFRESH_INTERVAL
+                }
+                Err(err) => {
+                    log::error!("Failed to fetch models, retry in {:?}: {}", backoff, err);
+                    let wait = backoff;
+                    backoff = (backoff * 2).min(RETRY_MAX);
+                    wait
+                }
+            };
+            drop(cache);
+
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
 pub struct Openrouter {
     pub(super) api_key: String,
     pub(super) chat_completion_endpoint: String,
-    models: Arc<RwLock<HashMap<String, raw::Model>>>,
+    models: Arc<RwLock<ModelCache>>,
     pub(super) http_client: reqwest::Client,
-    // true if not openrouter
-    pub(super) compatibility_mode: bool,
+    // model metadata is only trusted from openrouter
+    detect_capability: bool,
+    // openrouter plugins(pdf parsing, web search) and image output
+    plugins: bool,
+    // ask for usage accounting in response
+    usage: bool,
 }
 
 impl Openrouter {
@@ -51,7 +114,7 @@ impl Openrouter {
         let mut plugins = Vec::new();
         let mut modalities = Vec::new();
 
-        if !self.compatibility_mode {
+        if self.plugins {
             match capability.ocr {
                 OcrEngine::Native => plugins.push(raw::Plugin::pdf_native()),
                 OcrEngine::Text => plugins.push(raw::Plugin::pdf_text()),
@@ -67,10 +130,9 @@ impl Openrouter {
             }
         }
 
-        let usage = if self.compatibility_mode {
-            None
-        } else {
-            Some(raw::UsageReq { include: true })
+        let usage = match self.usage {
+            true => Some(raw::UsageReq { include: true }),
+            false => None,
         };
 
         let reasoning = option
@@ -113,136 +175,97 @@ impl Openrouter {
             &chat_completion_endpoint
         );
 
-        let compatibility_mode = !api_base.contains("openrouter");
-        if compatibility_mode {
-            log::warn!("Custom API_BASE detected, disabling plugin support");
+        let is_openrouter = api_base.contains("openrouter");
+        if !is_openrouter {
+            log::warn!(
+                "{} is not openrouter, disabling plugin support",
+                &chat_completion_endpoint
+            );
         }
 
-        let models = Arc::new(RwLock::new(HashMap::new()));
-
-        {
-            let models = models.clone();
-            let api_key = api_key.clone();
-            let endpoint = format!("{}/v1/models", api_base.trim_end_matches('/'));
-            tokio::spawn(async move {
-                match fetch_models(&endpoint, &api_key).await {
-                    Ok(model_list) => {
-                        log::info!("{} models available", model_list.len());
-                        let map: HashMap<String, raw::Model> =
-                            model_list.into_iter().map(|m| (m.id.clone(), m)).collect();
-                        *models.write().unwrap() = map;
-                    }
-                    Err(err) => log::error!("Failed to fetch models: {}", err),
-                }
-            });
-        }
+        let models = Arc::new(RwLock::new(ModelCache::default()));
+        spawn_refresher(
+            Arc::downgrade(&models),
+            api_base.to_string(),
+            api_key.clone(),
+        );
 
         Self {
             api_key,
             chat_completion_endpoint,
             models,
             http_client: reqwest::Client::new(),
-            compatibility_mode,
+            detect_capability: is_openrouter,
+            plugins: is_openrouter,
+            usage: is_openrouter,
         }
     }
 
+    /// Override whether openrouter plugins are sent, detected from api base by default
+    pub fn with_plugins(mut self, plugins: bool) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// Override whether usage accounting is requested, detected from api base by default
+    pub fn with_usage(mut self, usage: bool) -> Self {
+        self.usage = usage;
+        self
+    }
+
     /// Get a list of available model IDs
     pub fn get_model_ids(&self) -> Vec<String> {
-        self.models.read().unwrap().keys().cloned().collect()
+        self.models.read().unwrap().models.keys().cloned().collect()
+    }
+
+    /// When model metadata was last refreshed and how many models it holds
+    pub fn cache_status(&self) -> ModelCacheStatus {
+        let cache = self.models.read().unwrap();
+        ModelCacheStatus {
+            refreshed_at: cache.refreshed_at,
+            count: cache.models.len(),
+        }
     }
 
     /// get capability of a model(consider user overrides)
     pub fn get_capability(&self, model: &Model) -> super::Capability {
-        let overrides: super::MaybeCapability = model.capability.clone().into();
-        let capability = self.get_openrouter_capability(&model.id);
-
-        macro_rules! merge {
-            ($v:ident) => {
-                match overrides.$v {
-                    Some(v) => v,
-                    None => capability.$v.unwrap_or(true),
-                }
-            };
-        }
-        super::Capability {
-            image_output: merge!(image_output),
-            image_input: merge!(image_input),
-            structured_output: merge!(structured_output),
-            toolcall: merge!(toolcall),
-            ocr: match overrides.ocr {
-                Some(v) => v,
-                None => capability.ocr.unwrap_or(OcrEngine::Disabled),
-            },
-            audio: merge!(audio),
-        }
+        model
+            .capability
+            .merge(&self.get_openrouter_capability(&model.id))
     }
 
     /// get openrouter capabilities
     fn get_openrouter_capability(&self, model_id: &str) -> super::MaybeCapability {
-        if self.compatibility_mode {
+        if !self.detect_capability {
             return super::MaybeCapability::default();
         }
 
-        let models = self.models.read().unwrap();
-        let model = models.get(model_id);
-
-        if model.is_none() {
-            return super::MaybeCapability::default();
+        let cache = self.models.read().unwrap();
+        match cache.models.get(model_id) {
+            Some(model) => model.into(),
+            None => super::MaybeCapability::default(),
         }
+    }
 
-        let model = model.unwrap();
-
-        let supports_file_modality = model
-            .architecture
-            .input_modalities
-            .contains(&raw::Modality::File);
-
-        super::MaybeCapability {
-            image_output: Some(
-                model
-                    .architecture
-                    .output_modalities
-                    .contains(&raw::Modality::Image),
-            ),
-            image_input: Some(
-                model
-                    .architecture
-                    .input_modalities
-                    .contains(&raw::Modality::Image),
-            ),
-            structured_output: Some(
-                model
-                    .supported_parameters
-                    .contains(&raw::SupportedParams::StructuredOutput),
-            ),
-            toolcall: Some(
-                model
-                    .supported_parameters
-                    .contains(&raw::SupportedParams::Tools),
-            ),
-            ocr: Some(if supports_file_modality {
-                OcrEngine::Native
-            } else {
-                OcrEngine::Text
-            }),
-            audio: Some(
-                model
-                    .architecture
-                    .input_modalities
-                    .contains(&raw::Modality::Audio),
-            ),
+    /// Context length reported by openrouter
+    pub fn get_context_length(&self, model_id: &str) -> Option<i32> {
+        if !self.detect_capability {
+            return None;
         }
+
+        let cache = self.models.read().unwrap();
+        cache.models.get(model_id)?.context_length
     }
 
     /// Check if a model supports tools (function calling)
     /// Returns None if model not found or not using OpenRouter
     pub fn supports_tools(&self, model_id: &str) -> Option<bool> {
-        if self.compatibility_mode {
+        if !self.detect_capability {
             return None;
         }
 
-        let models = self.models.read().unwrap();
-        let model = models.get(model_id)?;
+        let cache = self.models.read().unwrap();
+        let model = cache.models.get(model_id)?;
 
         Some(
             model
@@ -345,15 +368,17 @@ impl Openrouter {
         self.send_complete_request(req).await
     }
 
-    pub async fn structured<T>(
+    /// Complete with a JSON schema constraint on the response.
+    ///
+    /// The schema is only sent when the model supports structured output;
+    /// otherwise the caller is expected to parse the response leniently.
+    pub async fn complete_json(
         &self,
         messages: Vec<Message>,
         model: Model,
         option: CompletionOption,
-    ) -> Result<StructuredCompletion<T>, Error>
-    where
-        T: serde::de::DeserializeOwned + schemars::JsonSchema,
-    {
+        schema: JsonSchema,
+    ) -> Result<ChatCompletion, Error> {
         debug_assert!(
             !option.image_generation,
             "Image generation supported only on streaming"
@@ -368,33 +393,30 @@ impl Openrouter {
         let mut req = self.create_request(messages, false, model, option);
 
         if structured_output {
-            let schema = schemars::schema_for!(T);
-            let schema_json = serde_json::to_value(&schema).map_err(|e| Error::Serde(e))?;
-
             // structure need to be marked with `#[schemars(deny_unknown_fields)]`
-
             req.response_format = Some(raw::ResponseFormat {
                 r#type: "json_schema".to_string(),
                 json_schema: serde_json::json!({
-                    "name": std::any::type_name::<T>().split("::").last().unwrap(),
+                    "name": schema.name,
                     "strict": true,
-                    "schema": schema_json
+                    "schema": schema.schema
                 }),
             });
         }
 
         req.log();
 
-        let completion = self.send_complete_request(req).await?;
-        let result: T = serde_json::from_str(&completion.response).map_err(Error::Serde)?;
-        Ok(StructuredCompletion {
-            price: completion.price,
-            token: completion.token,
-            response: result,
-        })
+        self.send_complete_request(req).await
     }
 }
 
+/// JSON schema of a structured response
+#[derive(Clone)]
+pub struct JsonSchema {
+    pub name: String,
+    pub schema: serde_json::Value,
+}
+
 pub struct ChatCompletion {
     pub price: f64,
     pub token: usize,
@@ -41,6 +41,17 @@ pub struct Model {
     pub supported_parameters: Vec<SupportedParams>,
     #[serde(default)]
     pub architecture: Architecture,
+    pub context_length: Option<i32>,
+    pub pricing: Option<Pricing>,
+}
+
+/// USD per token, as decimal strings
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Pricing {
+    #[serde(default)]
+    pub prompt: String,
+    #[serde(default)]
+    pub completion: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -141,7 +141,7 @@ impl StreamCompletion {
 
         // Handle tool calls - support parallel tool calls
         if let Some(tool_calls) = delta.tool_calls {
-            let mut last_tool_token: Option<(usize, String, String)> = None;
+            let mut last_tool_token: Option<(usize, String, String, String)> = None;
 
             for call in tool_calls {
                 let index = call.index as usize;
@@ -152,8 +152,10 @@ impl StreamCompletion {
                 }
 
                 // Initialize with id if present (first chunk for this tool call)
+                let mut id_token = String::new();
                 if let Some(id) = call.id {
-                    self.toolcalls[index].id = id;
+                    self.toolcalls[index].id = id.clone();
+                    id_token = id;
                 }
 
                 let mut name_token = String::new();
@@ -172,13 +174,18 @@ impl StreamCompletion {
                 }
 
                 // Track the last non-empty token
-                if !name_token.is_empty() || !args_token.is_empty() {
-                    last_tool_token = Some((index, name_token, args_token));
+                if !id_token.is_empty() || !name_token.is_empty() || !args_token.is_empty() {
+                    last_tool_token = Some((index, id_token, name_token, args_token));
                 }
             }
 
-            if let Some((idx, name, args)) = last_tool_token {
-                return StreamCompletionResp::ToolToken { idx, name, args };
+            if let Some((idx, id, name, args)) = last_tool_token {
+                return StreamCompletionResp::ToolToken {
+                    idx,
+                    id,
+                    name,
+                    args,
+                };
             }
         }
 
@@ -368,6 +375,8 @@ pub enum StreamCompletionResp {
     ResponseToken(String),
     ToolToken {
         idx: usize,
+        /// id of the call, only set on its first token
+        id: String,
         args: String,
         name: String,
     },
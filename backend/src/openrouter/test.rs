@@ -32,6 +32,7 @@ fn test_stream_completion_resp_variants() {
     let reasoning = StreamCompletionResp::ReasoningToken("Thinking...".to_string());
     let tool_token = StreamCompletionResp::ToolToken {
         idx: 0,
+        id: "call_0".to_string(),
         args: "partial".to_string(),
         name: "test".to_string(),
     };
@@ -91,7 +92,9 @@ async fn tool_calls() {
     while let Some(result) = stream.next().await {
         match result {
             Ok(resp) => match resp {
-                crate::openrouter::StreamCompletionResp::ToolToken { idx, args, name } => {
+                crate::openrouter::StreamCompletionResp::ToolToken {
+                    idx, args, name, ..
+                } => {
                     println!("Tool token at idx {}: name={}, args={}", idx, name, args);
                 }
                 _ => {}
@@ -193,7 +196,9 @@ async fn parallel_tool_calls() {
     while let Some(result) = stream.next().await {
         match result {
             Ok(resp) => match resp {
-                crate::openrouter::StreamCompletionResp::ToolToken { idx, args, name } => {
+                crate::openrouter::StreamCompletionResp::ToolToken {
+                    idx, args, name, ..
+                } => {
                     println!("Tool token at idx {}: name={}, args={}", idx, name, args);
                 }
                 _ => {}
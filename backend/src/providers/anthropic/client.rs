@@ -1,6 +1,6 @@
 use super::error::AnthropicError;
-use super::types::*;
 use super::stream::AnthropicStream;
+use super::types::*;
 
 pub struct AnthropicClient {
     api_key: String,
@@ -33,6 +33,8 @@ impl AnthropicClient {
             }],
             max_tokens: 10,
             temperature: None,
+            top_p: None,
+            top_k: None,
             system: None,
             stream: None,
             tools: None,
@@ -52,6 +54,40 @@ impl AnthropicClient {
         Ok(response.status().is_success())
     }
 
+    pub async fn list_models(&self) -> Result<Vec<Model>, AnthropicError> {
+        let mut models = Vec::new();
+        let mut after_id: Option<String> = None;
+
+        loop {
+            let mut url = format!("{}/models?limit=1000", self.base_url);
+            if let Some(id) = &after_id {
+                url.push_str(&format!("&after_id={}", id));
+            }
+            let response = self
+                .http_client
+                .get(&url)
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", &self.api_version)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                let status = response.status().as_u16();
+                let message = response.text().await.unwrap_or_default();
+                return Err(AnthropicError::Status { status, message });
+            }
+
+            let model_list: ModelListResponse = response.json().await?;
+            models.extend(model_list.data);
+            match (model_list.has_more, model_list.last_id) {
+                (true, Some(last_id)) => after_id = Some(last_id),
+                _ => break,
+            }
+        }
+
+        Ok(models)
+    }
+
     pub async fn create_message(
         &self,
         request: MessagesRequest,
@@ -68,8 +104,9 @@ impl AnthropicClient {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(AnthropicError::InvalidResponse(error_text));
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(AnthropicError::Status { status, message });
         }
 
         let message_response: MessagesResponse = response.json().await?;
@@ -94,8 +131,9 @@ impl AnthropicClient {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(AnthropicError::InvalidResponse(error_text));
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(AnthropicError::Status { status, message });
         }
 
         Ok(AnthropicStream::new(response))
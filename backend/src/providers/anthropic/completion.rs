@@ -0,0 +1,311 @@
+use futures_util::future::BoxFuture;
+use protocol::{ModelPrice, ProviderType};
+
+use super::stream::{ContentBlockStart, ContentDelta, StreamEvent};
+use super::types::*;
+use super::{AnthropicClient, AnthropicError};
+use crate::openrouter::{
+    self, ChatCompletion, CompletionOption, Error, FinishReason, JsonSchema, Model,
+    StreamCompletionResp,
+};
+use crate::providers::completion::{
+    Attachment, BoxCompletionStream, Completion, NativeStream, StreamAccumulator, attachment, cost,
+    json_instruction, model_price,
+};
+
+/// Anthropic requires `max_tokens`, use it when caller doesn't specify one
+const DEFAULT_MAX_TOKENS: i32 = 8192;
+
+impl From<AnthropicError> for Error {
+    fn from(err: AnthropicError) -> Self {
+        match err {
+            AnthropicError::Reqwest(e) => Error::Http(e),
+            AnthropicError::SerdeJson(e) => Error::Serde(e),
+            AnthropicError::InvalidResponse(message) => Error::Api {
+                message,
+                code: None,
+            },
+            AnthropicError::Status { status, message } => Error::Api {
+                message,
+                code: Some(status as i32),
+            },
+            AnthropicError::ApiError { message, .. } => Error::Api {
+                message,
+                code: None,
+            },
+        }
+    }
+}
+
+fn user_blocks(
+    text: String,
+    files: Vec<openrouter::File>,
+    capability: &openrouter::Capability,
+) -> Vec<ContentBlock> {
+    let mut blocks = vec![ContentBlock::Text { text }];
+
+    for file in files {
+        let block = match attachment(&file, capability) {
+            Some(Attachment::Text(text)) => ContentBlock::Text { text },
+            Some(Attachment::Inline { mime_type, data }) => {
+                let source = ImageSource {
+                    source_type: "base64".to_string(),
+                    media_type: mime_type.clone(),
+                    data,
+                };
+                match mime_type.as_str() {
+                    "image/jpeg" | "image/png" | "image/gif" | "image/webp" => {
+                        ContentBlock::Image { source }
+                    }
+                    "application/pdf" => ContentBlock::Document { source },
+                    _ => continue,
+                }
+            }
+            None => continue,
+        };
+        blocks.push(ContentBlock::Text {
+            text: format!("Uploaded file: {}", file.name),
+        });
+        blocks.push(block);
+    }
+
+    blocks
+}
+
+/// Convert messages, returning system prompt and conversation separately.
+///
+/// Anthropic requires alternating roles, so consecutive messages of the same role are merged.
+fn to_messages(
+    messages: Vec<openrouter::Message>,
+    capability: &openrouter::Capability,
+) -> (Option<String>, Vec<Message>) {
+    let mut system: Vec<String> = Vec::new();
+    let mut result: Vec<Message> = Vec::new();
+
+    for message in messages {
+        let (role, blocks) = match message {
+            openrouter::Message::System(text) => {
+                system.push(text);
+                continue;
+            }
+            openrouter::Message::User(text) => ("user", vec![ContentBlock::Text { text }]),
+            openrouter::Message::MultipartUser { text, files } => {
+                ("user", user_blocks(text, files, capability))
+            }
+            openrouter::Message::Assistant { content, .. } => {
+                if content.trim().is_empty() {
+                    continue;
+                }
+                ("assistant", vec![ContentBlock::Text { text: content }])
+            }
+            openrouter::Message::ToolCall(call) => (
+                "assistant",
+                vec![ContentBlock::ToolUse {
+                    id: call.id,
+                    name: call.name,
+                    input: serde_json::from_str(&call.arguments)
+                        .unwrap_or_else(|_| serde_json::json!({})),
+                }],
+            ),
+            openrouter::Message::ToolResult(result) => (
+                "user",
+                vec![ContentBlock::ToolResult {
+                    tool_use_id: result.id,
+                    content: result.content,
+                }],
+            ),
+        };
+
+        match result.last_mut() {
+            Some(Message {
+                role: last_role,
+                content: MessageContent::Blocks(last_blocks),
+            }) if last_role == role => last_blocks.extend(blocks),
+            _ => result.push(Message {
+                role: role.to_string(),
+                content: MessageContent::Blocks(blocks),
+            }),
+        }
+    }
+
+    let system = match system.is_empty() {
+        true => None,
+        false => Some(system.join("\n\n")),
+    };
+    (system, result)
+}
+
+impl AnthropicClient {
+    fn create_request(
+        &self,
+        messages: Vec<openrouter::Message>,
+        model: Model,
+        option: CompletionOption,
+    ) -> MessagesRequest {
+        let capability = Completion::get_capability(self, &model);
+        let (system, messages) = to_messages(messages, &capability);
+
+        let tools = match option.tools.is_empty() || !capability.toolcall {
+            true => None,
+            false => Some(
+                option
+                    .tools
+                    .into_iter()
+                    .map(|tool| Tool {
+                        name: tool.name,
+                        description: Some(tool.description),
+                        input_schema: tool.schema,
+                    })
+                    .collect(),
+            ),
+        };
+
+        MessagesRequest {
+            model: model.id,
+            messages,
+            max_tokens: option.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            temperature: model.temperature,
+            top_p: model.top_p,
+            top_k: model.top_k,
+            system,
+            stream: None,
+            tools,
+        }
+    }
+
+    async fn send_complete_request(
+        &self,
+        request: MessagesRequest,
+        price: Option<ModelPrice>,
+    ) -> Result<ChatCompletion, Error> {
+        let response = self.create_message(request).await?;
+
+        let text = response
+            .content
+            .into_iter()
+            .filter_map(|content| match content {
+                ResponseContent::Text { text } => Some(text),
+                _ => None,
+            })
+            .collect();
+
+        let usage = response.usage;
+        Ok(ChatCompletion {
+            price: cost(price, usage.input_tokens as i64, usage.output_tokens as i64),
+            token: (usage.input_tokens + usage.output_tokens) as usize,
+            response: text,
+        })
+    }
+}
+
+fn handle_event(acc: &mut StreamAccumulator, event: StreamEvent) -> Vec<StreamCompletionResp> {
+    let resp = match event {
+        // output tokens in `message_delta` are cumulative
+        StreamEvent::MessageStart { message } => acc.usage(message.usage.input_tokens as i64, 0),
+        StreamEvent::ContentBlockStart { content_block, .. } => match content_block {
+            ContentBlockStart::Text { text } => acc.text(text),
+            ContentBlockStart::Thinking { thinking } => acc.reasoning(thinking),
+            ContentBlockStart::ToolUse { id, name, .. } => {
+                let idx = acc.tool_count();
+                acc.tool_start(idx, id, name)
+            }
+            ContentBlockStart::Unknown => return vec![],
+        },
+        StreamEvent::ContentBlockDelta { delta, .. } => match delta {
+            ContentDelta::TextDelta { text } => acc.text(text),
+            ContentDelta::ThinkingDelta { thinking } => acc.reasoning(thinking),
+            // tool_use blocks are streamed one after another
+            ContentDelta::InputJsonDelta { partial_json } => {
+                let idx = acc.tool_count().saturating_sub(1);
+                acc.tool_args(idx, partial_json)
+            }
+            ContentDelta::Unknown => return vec![],
+        },
+        StreamEvent::MessageDelta { delta, usage } => {
+            if delta.stop_reason.as_deref() == Some("max_tokens") {
+                acc.stop(FinishReason::Length);
+            }
+            acc.usage(0, usage.output_tokens as i64)
+        }
+        // error events are reported by `AnthropicStream`
+        StreamEvent::ContentBlockStop { .. }
+        | StreamEvent::MessageStop
+        | StreamEvent::Ping
+        | StreamEvent::Error { .. } => return vec![],
+    };
+    vec![resp]
+}
+
+impl Completion for AnthropicClient {
+    fn stream(
+        &self,
+        model: Model,
+        messages: Vec<openrouter::Message>,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<BoxCompletionStream, Error>> {
+        Box::pin(async move {
+            let price = model_price(ProviderType::Anthropic, &model);
+            let request = self.create_request(messages, model, option);
+            let stream = self.create_message_stream(request).await?;
+            Ok(Box::new(NativeStream::new(stream, handle_event, price)) as BoxCompletionStream)
+        })
+    }
+
+    fn complete(
+        &self,
+        messages: Vec<openrouter::Message>,
+        model: Model,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>> {
+        Box::pin(async move {
+            let price = model_price(ProviderType::Anthropic, &model);
+            let request = self.create_request(messages, model, option);
+            self.send_complete_request(request, price).await
+        })
+    }
+
+    fn complete_json(
+        &self,
+        mut messages: Vec<openrouter::Message>,
+        model: Model,
+        option: CompletionOption,
+        schema: JsonSchema,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>> {
+        Box::pin(async move {
+            messages.push(openrouter::Message::System(json_instruction(&schema)));
+            let price = model_price(ProviderType::Anthropic, &model);
+            let request = self.create_request(messages, model, option);
+            self.send_complete_request(request, price).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_consecutive_roles() {
+        let messages = vec![
+            openrouter::Message::System("be helpful".to_string()),
+            openrouter::Message::User("search rust".to_string()),
+            openrouter::Message::ToolCall(openrouter::MessageToolCall {
+                id: "toolu_1".to_string(),
+                name: "web_search_tool".to_string(),
+                arguments: "{\"query\":\"rust\"}".to_string(),
+            }),
+            openrouter::Message::ToolResult(openrouter::MessageToolResult {
+                id: "toolu_1".to_string(),
+                content: "rust-lang.org".to_string(),
+            }),
+            openrouter::Message::User("thanks".to_string()),
+        ];
+
+        let (system, messages) = to_messages(messages, &Default::default());
+
+        assert_eq!(system.as_deref(), Some("be helpful"));
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2].role, "user");
+        assert!(matches!(&messages[2].content, MessageContent::Blocks(b) if b.len() == 2));
+    }
+}
@@ -5,7 +5,15 @@ pub enum AnthropicError {
     Reqwest(reqwest::Error),
     SerdeJson(serde_json::Error),
     InvalidResponse(String),
-    ApiError { message: String, error_type: Option<String> },
+    /// Non-success HTTP status with the response body
+    Status {
+        status: u16,
+        message: String,
+    },
+    ApiError {
+        message: String,
+        error_type: Option<String>,
+    },
 }
 
 impl fmt::Display for AnthropicError {
@@ -14,7 +22,11 @@ impl fmt::Display for AnthropicError {
             AnthropicError::Reqwest(e) => write!(f, "Request error: {}", e),
             AnthropicError::SerdeJson(e) => write!(f, "JSON error: {}", e),
             AnthropicError::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
-            AnthropicError::ApiError { message, error_type } => {
+            AnthropicError::Status { status, message } => write!(f, "HTTP {}: {}", status, message),
+            AnthropicError::ApiError {
+                message,
+                error_type,
+            } => {
                 write!(f, "API error: {} (type: {:?})", message, error_type)
             }
         }
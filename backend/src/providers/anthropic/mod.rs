@@ -1,8 +1,9 @@
 pub mod client;
-pub mod types;
-pub mod stream;
+mod completion;
 pub mod error;
+pub mod stream;
+pub mod types;
 
 pub use client::AnthropicClient;
-pub use types::*;
 pub use error::AnthropicError;
+pub use types::*;
@@ -1,7 +1,7 @@
 use super::error::AnthropicError;
 use bytes::Bytes;
-use futures_util::stream::Stream;
 use futures_util::StreamExt;
+use futures_util::stream::Stream;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -9,13 +9,36 @@ use std::task::{Context, Poll};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum StreamEvent {
-    MessageStart { message: MessageStart },
-    ContentBlockStart { index: i32, content_block: ContentBlockStart },
-    ContentBlockDelta { index: i32, delta: ContentDelta },
-    ContentBlockStop { index: i32 },
-    MessageDelta { delta: MessageDelta, usage: DeltaUsage },
+    MessageStart {
+        message: MessageStart,
+    },
+    ContentBlockStart {
+        index: i32,
+        content_block: ContentBlockStart,
+    },
+    ContentBlockDelta {
+        index: i32,
+        delta: ContentDelta,
+    },
+    ContentBlockStop {
+        index: i32,
+    },
+    MessageDelta {
+        delta: MessageDelta,
+        usage: DeltaUsage,
+    },
     MessageStop,
     Ping,
+    Error {
+        error: ErrorDetail,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,15 +63,35 @@ pub struct Usage {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ContentBlockStart {
-    Text { text: String },
-    ToolUse { id: String, name: String, input: serde_json::Value },
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    Thinking {
+        thinking: String,
+    },
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ContentDelta {
-    TextDelta { text: String },
-    InputJsonDelta { partial_json: String },
+    TextDelta {
+        text: String,
+    },
+    InputJsonDelta {
+        partial_json: String,
+    },
+    ThinkingDelta {
+        thinking: String,
+    },
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,15 +123,15 @@ impl AnthropicStream {
             return None;
         }
 
-        if let Some(data) = line.strip_prefix("data: ") {
-            match serde_json::from_str::<StreamEvent>(data) {
-                Ok(event) => Some(Ok(event)),
-                Err(e) => Some(Err(AnthropicError::SerdeJson(e))),
-            }
-        } else if line.starts_with("event: ") {
-            None
-        } else {
-            None
+        // `event:` lines repeat the type in the data
+        let data = line.strip_prefix("data: ")?;
+        match serde_json::from_str::<StreamEvent>(data) {
+            Ok(StreamEvent::Error { error }) => Some(Err(AnthropicError::ApiError {
+                message: error.message,
+                error_type: Some(error.error_type),
+            })),
+            Ok(event) => Some(Ok(event)),
+            Err(e) => Some(Err(AnthropicError::SerdeJson(e))),
         }
     }
 }
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use super::{ModelInfo, ProviderConfig, ProviderType, default_models, find_known};
+
+/// How long a model listing is trusted before calling the provider again
+const CATALOG_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Model listings of providers, cached per endpoint
+///
+/// Only successful listings are cached, a failing provider is retried on the next call.
+#[derive(Default)]
+pub struct ModelCatalog {
+    entries: RwLock<HashMap<u64, (Instant, Vec<ModelInfo>)>>,
+}
+
+impl ModelCatalog {
+    /// List models of a provider, fall back to the built-in table if the call fails
+    ///
+    /// Error of the failed call is returned alongside the fallback.
+    pub async fn list(&self, config: &ProviderConfig) -> (Vec<ModelInfo>, Option<String>) {
+        let key = cache_key(config);
+
+        if let Some((fetched_at, models)) = self.entries.read().unwrap().get(&key)
+            && fetched_at.elapsed() < CATALOG_TTL
+        {
+            return (models.clone(), None);
+        }
+
+        match config.list_models().await {
+            Ok(models) => {
+                let mut entries = self.entries.write().unwrap();
+                // keys of rotated or deleted endpoints would stay forever otherwise
+                entries.retain(|_, (fetched_at, _)| fetched_at.elapsed() < CATALOG_TTL);
+                entries.insert(key, (Instant::now(), models.clone()));
+                (models, None)
+            }
+            Err(err) => {
+                log::warn!(
+                    "Failed to list models of {:?}, using built-in list: {}",
+                    config.provider_type,
+                    err
+                );
+                (config.get_default_models(), Some(err.to_string()))
+            }
+        }
+    }
+
+    /// Context window of a model from cached listings, then from the built-in table
+    pub fn context_window(&self, provider_type: ProviderType, model_id: &str) -> Option<i32> {
+        let cached = self
+            .entries
+            .read()
+            .unwrap()
+            .values()
+            .flat_map(|(_, models)| models.iter())
+            .find(|model| model.id == model_id)
+            .and_then(|model| model.context_window);
+
+        cached.or_else(|| find_known(&default_models(provider_type), model_id)?.context_window)
+    }
+}
+
+/// Endpoints are told apart by type, base url and api key, the key itself is not kept
+fn cache_key(config: &ProviderConfig) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    config.provider_type.hash(&mut hasher);
+    config.base_url.hash(&mut hasher);
+    config.api_key.hash(&mut hasher);
+    hasher.finish()
+}
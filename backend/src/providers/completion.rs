@@ -0,0 +1,505 @@
+//! Provider-agnostic completion interface
+//!
+//! The chat pipeline speaks in `openrouter` types (messages, options, stream tokens).
+//! Every backend implements [`Completion`] by translating them to its own wire format,
+//! so configurations don't care which upstream actually serves the model.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    sync::{Arc, RwLock},
+    task::{self, Poll, ready},
+};
+
+use futures_util::{StreamExt, future::BoxFuture};
+use protocol::{ModelPrice, OcrEngine, ProviderType};
+use serde::de::DeserializeOwned;
+use tokio_stream::Stream;
+
+use crate::openrouter::{
+    self, ChatCompletion, CompletionOption, Error, File, FinishReason, JsonSchema, Message, Model,
+    StreamCompletionResp, StreamResult, StructuredCompletion, ToolCall, Usage,
+};
+
+use super::{
+    AnthropicClient, GoogleClient, ModelCatalog, OpenAIClient, default_models, find_known,
+};
+
+/// A streaming completion, yields tokens and can be finalized into a [`StreamResult`].
+pub trait CompletionStream:
+    Stream<Item = Result<StreamCompletionResp, Error>> + Send + Unpin
+{
+    fn get_result(self: Box<Self>) -> StreamResult;
+}
+
+pub type BoxCompletionStream = Box<dyn CompletionStream>;
+
+pub trait Completion: Send + Sync {
+    fn stream(
+        &self,
+        model: Model,
+        messages: Vec<Message>,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<BoxCompletionStream, Error>>;
+
+    fn complete(
+        &self,
+        messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>>;
+
+    /// Complete with response constrained(or instructed) to match the schema
+    fn complete_json(
+        &self,
+        messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+        schema: JsonSchema,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>>;
+
+    /// get capability of a model(consider user overrides)
+    fn get_capability(&self, model: &Model) -> openrouter::Capability {
+        model.capability.merge(&Default::default())
+    }
+
+    /// Context window of a model reported by the provider
+    fn context_window(&self, _model_id: &str) -> Option<i32> {
+        None
+    }
+}
+
+impl dyn Completion {
+    pub async fn structured<T>(
+        &self,
+        messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+    ) -> Result<StructuredCompletion<T>, Error>
+    where
+        T: DeserializeOwned + schemars::JsonSchema,
+    {
+        let schema = serde_json::to_value(schemars::schema_for!(T))?;
+        let schema = JsonSchema {
+            name: std::any::type_name::<T>()
+                .split("::")
+                .last()
+                .unwrap()
+                .to_string(),
+            schema,
+        };
+
+        let completion = self.complete_json(messages, model, option, schema).await?;
+        let response: T = serde_json::from_str(strip_code_fence(&completion.response))?;
+        Ok(StructuredCompletion {
+            price: completion.price,
+            token: completion.token,
+            response,
+        })
+    }
+}
+
+/// Models without native structured output tend to wrap json in markdown fence
+fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    match text.strip_prefix("```") {
+        Some(inner) => {
+            let inner = inner.trim_start_matches("json");
+            inner.strip_suffix("```").unwrap_or(inner).trim()
+        }
+        None => text,
+    }
+}
+
+/// Price of a model served by a native provider
+///
+/// The price from the model config wins, then the one of the longest matching built-in model.
+pub(super) fn model_price(provider: ProviderType, model: &Model) -> Option<ModelPrice> {
+    model.price.or_else(|| {
+        let defaults = default_models(provider);
+        let pricing = find_known(&defaults, &model.id)?.pricing.as_ref()?;
+        Some(ModelPrice {
+            input: pricing.input_cost_per_1m,
+            output: pricing.output_cost_per_1m,
+        })
+    })
+}
+
+/// Cost in USD of the tokens, free if the price is unknown
+pub(super) fn cost(price: Option<ModelPrice>, input: i64, output: i64) -> f64 {
+    price.map_or(0.0, |price| {
+        (input as f64 * price.input as f64 + output as f64 * price.output as f64) / 1_000_000.0
+    })
+}
+
+/// Uploaded file in a form native providers understand
+pub(super) enum Attachment {
+    /// base64 encoded data the provider takes inline
+    Inline {
+        mime_type: String,
+        data: String,
+    },
+    Text(String),
+}
+
+/// Classify an uploaded file, `None` if the model cannot take it
+pub(super) fn attachment(file: &File, capability: &openrouter::Capability) -> Option<Attachment> {
+    let data = &file.data;
+    let supported = if infer::is_image(data) {
+        capability.image_input
+    } else if infer::is_audio(data) {
+        capability.audio
+    } else if infer::archive::is_pdf(data) {
+        capability.ocr != OcrEngine::Disabled
+    } else {
+        let content = String::from_utf8_lossy(data);
+        return Some(Attachment::Text(format!(
+            "Uploaded file: {}\n\n<content>{}</content>",
+            file.name, content
+        )));
+    };
+
+    supported.then(|| Attachment::Inline {
+        mime_type: infer::get(data)
+            .map(|kind| kind.mime_type().to_string())
+            .unwrap_or_default(),
+        data: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, data),
+    })
+}
+
+/// System prompt suffix for providers without json schema support
+pub(super) fn json_instruction(schema: &JsonSchema) -> String {
+    format!(
+        "Respond with a single JSON object matching the following JSON schema, without any other text.\n{}",
+        schema.schema
+    )
+}
+
+impl Completion for openrouter::Openrouter {
+    fn stream(
+        &self,
+        model: Model,
+        messages: Vec<Message>,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<BoxCompletionStream, Error>> {
+        Box::pin(async move {
+            let stream = openrouter::Openrouter::stream(self, model, messages, option).await?;
+            Ok(Box::new(stream) as BoxCompletionStream)
+        })
+    }
+
+    fn complete(
+        &self,
+        messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>> {
+        Box::pin(openrouter::Openrouter::complete(
+            self, messages, model, option,
+        ))
+    }
+
+    fn complete_json(
+        &self,
+        messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+        schema: JsonSchema,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>> {
+        Box::pin(openrouter::Openrouter::complete_json(
+            self, messages, model, option, schema,
+        ))
+    }
+
+    fn get_capability(&self, model: &Model) -> openrouter::Capability {
+        openrouter::Openrouter::get_capability(self, model)
+    }
+
+    fn context_window(&self, model_id: &str) -> Option<i32> {
+        self.get_context_length(model_id)
+    }
+}
+
+impl CompletionStream for openrouter::StreamCompletion {
+    fn get_result(self: Box<Self>) -> StreamResult {
+        openrouter::StreamCompletion::get_result(*self)
+    }
+}
+
+/// Accumulates stream deltas of native providers into a [`StreamResult`]
+#[derive(Default)]
+pub(super) struct StreamAccumulator {
+    responses: Vec<StreamCompletionResp>,
+    toolcalls: Vec<ToolCall>,
+    usage: Usage,
+    stop_reason: Option<FinishReason>,
+    price: Option<ModelPrice>,
+}
+
+impl StreamAccumulator {
+    fn push(&mut self, resp: StreamCompletionResp) -> StreamCompletionResp {
+        self.responses.push(resp.clone());
+        resp
+    }
+
+    pub fn text(&mut self, text: String) -> StreamCompletionResp {
+        self.push(StreamCompletionResp::ResponseToken(text))
+    }
+
+    pub fn reasoning(&mut self, text: String) -> StreamCompletionResp {
+        self.push(StreamCompletionResp::ReasoningToken(text))
+    }
+
+    pub fn tool_start(&mut self, idx: usize, id: String, name: String) -> StreamCompletionResp {
+        if self.toolcalls.len() <= idx {
+            self.toolcalls.resize(idx + 1, ToolCall::default());
+        }
+        self.toolcalls[idx].id = id.clone();
+        self.toolcalls[idx].name.push_str(&name);
+        StreamCompletionResp::ToolToken {
+            idx,
+            id,
+            args: String::new(),
+            name,
+        }
+    }
+
+    pub fn tool_args(&mut self, idx: usize, args: String) -> StreamCompletionResp {
+        if self.toolcalls.len() <= idx {
+            self.toolcalls.resize(idx + 1, ToolCall::default());
+        }
+        self.toolcalls[idx].args.push_str(&args);
+        StreamCompletionResp::ToolToken {
+            idx,
+            id: String::new(),
+            args,
+            name: String::new(),
+        }
+    }
+
+    pub fn tool_count(&self) -> usize {
+        self.toolcalls.len()
+    }
+
+    pub fn usage(&mut self, input: i64, output: i64) -> StreamCompletionResp {
+        let token = input + output;
+        let price = cost(self.price, input, output);
+        self.usage.token += token;
+        self.usage.cost += price;
+        StreamCompletionResp::Usage {
+            price,
+            token: token.max(0) as usize,
+        }
+    }
+
+    pub fn stop(&mut self, reason: FinishReason) {
+        self.stop_reason = Some(reason);
+    }
+
+    pub fn finish(self) -> StreamResult {
+        let stop_reason = match self.toolcalls.is_empty() {
+            true => self.stop_reason.unwrap_or(FinishReason::Stop),
+            false => FinishReason::ToolCalls,
+        };
+        StreamResult {
+            toolcalls: self.toolcalls,
+            usage: self.usage,
+            stop_reason,
+            responses: self
+                .responses
+                .into_iter()
+                .filter(|x| !x.is_empty())
+                .collect(),
+            annotations: None,
+            reasoning_details: None,
+            image: Vec::new(),
+        }
+    }
+}
+
+/// Adapts a provider event stream into [`CompletionStream`]
+///
+/// `handler` turns each provider event into zero or more tokens, usage is priced with `price`.
+pub(super) struct NativeStream<S, T> {
+    inner: S,
+    handler: fn(&mut StreamAccumulator, T) -> Vec<StreamCompletionResp>,
+    acc: StreamAccumulator,
+    pending: VecDeque<StreamCompletionResp>,
+}
+
+impl<S, T> NativeStream<S, T> {
+    pub fn new(
+        inner: S,
+        handler: fn(&mut StreamAccumulator, T) -> Vec<StreamCompletionResp>,
+        price: Option<ModelPrice>,
+    ) -> Self {
+        Self {
+            inner,
+            handler,
+            acc: StreamAccumulator {
+                price,
+                ..Default::default()
+            },
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<S, T, E> Stream for NativeStream<S, T>
+where
+    S: Stream<Item = Result<T, E>> + Unpin,
+    E: Into<Error>,
+{
+    type Item = Result<StreamCompletionResp, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(resp) = self.pending.pop_front() {
+                return Poll::Ready(Some(Ok(resp)));
+            }
+            match ready!(self.inner.poll_next_unpin(cx)) {
+                Some(Ok(event)) => {
+                    let this = &mut *self;
+                    let resps = (this.handler)(&mut this.acc, event);
+                    this.pending.extend(resps);
+                }
+                Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl<S, T, E> CompletionStream for NativeStream<S, T>
+where
+    S: Stream<Item = Result<T, E>> + Send + Unpin,
+    E: Into<Error>,
+{
+    fn get_result(self: Box<Self>) -> StreamResult {
+        self.acc.finish()
+    }
+}
+
+/// Completion backends available to the chat pipeline
+///
+/// OpenRouter(or any OpenAI-compatible endpoint from `API_BASE`) is always available,
+/// native clients are only available when their API key is configured.
+///
+/// Clients of providers stored in database are created lazily and cached by id.
+/// Model listings of any provider are cached in `catalog`.
+pub struct Providers {
+    openrouter: Arc<openrouter::Openrouter>,
+    openai: Option<Arc<OpenAIClient>>,
+    anthropic: Option<Arc<AnthropicClient>>,
+    google: Option<Arc<GoogleClient>>,
+    stored: RwLock<HashMap<i32, Arc<dyn Completion>>>,
+    catalog: ModelCatalog,
+}
+
+impl Providers {
+    pub fn new(openrouter: openrouter::Openrouter) -> Self {
+        Self {
+            openrouter: Arc::new(openrouter),
+            openai: None,
+            anthropic: None,
+            google: None,
+            stored: RwLock::new(HashMap::new()),
+            catalog: ModelCatalog::default(),
+        }
+    }
+
+    pub fn with_openai(mut self, client: OpenAIClient) -> Self {
+        self.openai = Some(Arc::new(client));
+        self
+    }
+
+    pub fn with_anthropic(mut self, client: AnthropicClient) -> Self {
+        self.anthropic = Some(Arc::new(client));
+        self
+    }
+
+    pub fn with_google(mut self, client: GoogleClient) -> Self {
+        self.google = Some(Arc::new(client));
+        self
+    }
+
+    pub fn openrouter(&self) -> &openrouter::Openrouter {
+        &self.openrouter
+    }
+
+    pub fn catalog(&self) -> &ModelCatalog {
+        &self.catalog
+    }
+
+    /// Get the backend of a provider, fail if it's not configured
+    pub fn get(&self, provider: ProviderType) -> anyhow::Result<Arc<dyn Completion>> {
+        let backend: Option<Arc<dyn Completion>> = match provider {
+            ProviderType::OpenRouter => Some(self.openrouter.clone()),
+            ProviderType::OpenAI => self.openai.clone().map(|x| x as _),
+            ProviderType::Anthropic => self.anthropic.clone().map(|x| x as _),
+            ProviderType::Google => self.google.clone().map(|x| x as _),
+        };
+        backend.ok_or_else(|| anyhow::anyhow!("provider {:?} is not configured", provider))
+    }
+
+    pub fn get_stored(&self, id: i32) -> Option<Arc<dyn Completion>> {
+        self.stored.read().unwrap().get(&id).cloned()
+    }
+
+    pub fn insert_stored(&self, id: i32, backend: Arc<dyn Completion>) {
+        self.stored.write().unwrap().insert(id, backend);
+    }
+
+    /// Drop cached client of a stored provider, called after it's updated or deleted
+    pub fn invalidate(&self, id: i32) {
+        self.stored.write().unwrap().remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_fence() {
+        assert_eq!(strip_code_fence("{\"a\":1}"), "{\"a\":1}");
+        assert_eq!(strip_code_fence("```json\n{\"a\":1}\n```"), "{\"a\":1}");
+        assert_eq!(strip_code_fence("  ```\n{}\n```  "), "{}");
+    }
+
+    #[test]
+    fn accumulator_tool_calls() {
+        let mut acc = StreamAccumulator::default();
+        acc.text("calling".to_string());
+        acc.tool_start(0, "call_0".to_string(), "web_search_tool".to_string());
+        acc.tool_args(0, "{\"query\":".to_string());
+        acc.tool_args(0, "\"rust\"}".to_string());
+        acc.stop(FinishReason::Stop);
+
+        let result = acc.finish();
+        assert!(matches!(result.stop_reason, FinishReason::ToolCalls));
+        assert_eq!(result.toolcalls.len(), 1);
+        assert_eq!(result.toolcalls[0].args, "{\"query\":\"rust\"}");
+        assert_eq!(result.get_text(), "calling");
+    }
+
+    #[test]
+    fn price_usage() {
+        let model = Model::builder("gpt-4o-2024-08-06").build();
+        let price = model_price(ProviderType::OpenAI, &model);
+        assert_eq!(cost(price, 1_000_000, 100_000), 3.5);
+
+        let mut acc = StreamAccumulator {
+            price,
+            ..Default::default()
+        };
+        acc.usage(2_000, 0);
+        acc.usage(0, 1_000);
+        let usage = acc.finish().usage;
+        assert_eq!(usage.token, 3_000);
+        assert!((usage.cost - 0.015).abs() < 1e-9);
+
+        let unknown = Model::builder("my-finetune").build();
+        assert_eq!(model_price(ProviderType::OpenAI, &unknown), None);
+    }
+}
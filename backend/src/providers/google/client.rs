@@ -1,6 +1,6 @@
 use super::error::GoogleError;
-use super::types::*;
 use super::stream::GoogleStream;
+use super::types::*;
 
 pub struct GoogleClient {
     api_key: String,
@@ -30,12 +30,16 @@ impl GoogleClient {
     }
 
     pub async fn list_models(&self) -> Result<Vec<Model>, GoogleError> {
-        let url = format!("{}/models?key={}", self.base_url, self.api_key);
+        let url = format!(
+            "{}/models?pageSize=1000&key={}",
+            self.base_url, self.api_key
+        );
         let response = self.http_client.get(&url).send().await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(GoogleError::InvalidResponse(error_text));
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(GoogleError::Status { status, message });
         }
 
         let model_list: ModelListResponse = response.json().await?;
@@ -52,16 +56,12 @@ impl GoogleClient {
             self.base_url, model, self.api_key
         );
 
-        let response = self
-            .http_client
-            .post(&url)
-            .json(&request)
-            .send()
-            .await?;
+        let response = self.http_client.post(&url).json(&request).send().await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(GoogleError::InvalidResponse(error_text));
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(GoogleError::Status { status, message });
         }
 
         let content_response: GenerateContentResponse = response.json().await?;
@@ -74,20 +74,16 @@ impl GoogleClient {
         request: GenerateContentRequest,
     ) -> Result<GoogleStream, GoogleError> {
         let url = format!(
-            "{}/models/{}:streamGenerateContent?key={}",
+            "{}/models/{}:streamGenerateContent?alt=sse&key={}",
             self.base_url, model, self.api_key
         );
 
-        let response = self
-            .http_client
-            .post(&url)
-            .json(&request)
-            .send()
-            .await?;
+        let response = self.http_client.post(&url).json(&request).send().await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(GoogleError::InvalidResponse(error_text));
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(GoogleError::Status { status, message });
         }
 
         Ok(GoogleStream::new(response))
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use futures_util::future::BoxFuture;
+use protocol::ProviderType;
+
+use super::types::*;
+use super::{GoogleClient, GoogleError};
+use crate::openrouter::{
+    self, ChatCompletion, CompletionOption, Error, FinishReason, JsonSchema, Message, Model,
+    StreamCompletionResp,
+};
+use crate::providers::completion::{
+    Attachment, BoxCompletionStream, Completion, NativeStream, StreamAccumulator, attachment, cost,
+    json_instruction, model_price,
+};
+
+impl From<GoogleError> for Error {
+    fn from(err: GoogleError) -> Self {
+        match err {
+            GoogleError::Reqwest(e) => Error::Http(e),
+            GoogleError::SerdeJson(e) => Error::Serde(e),
+            GoogleError::InvalidResponse(message) => Error::Api {
+                message,
+                code: None,
+            },
+            GoogleError::Status { status, message } => Error::Api {
+                message,
+                code: Some(status as i32),
+            },
+            GoogleError::ApiError { message, code } => Error::Api { message, code },
+        }
+    }
+}
+
+fn text_part(text: String) -> Part {
+    Part::Text {
+        text,
+        thought: false,
+    }
+}
+
+fn user_parts(
+    text: String,
+    files: Vec<openrouter::File>,
+    capability: &openrouter::Capability,
+) -> Vec<Part> {
+    let mut parts = vec![text_part(text)];
+
+    for file in files {
+        let part = match attachment(&file, capability) {
+            Some(Attachment::Text(text)) => text_part(text),
+            Some(Attachment::Inline { mime_type, data }) => Part::InlineData {
+                inline_data: InlineData { mime_type, data },
+            },
+            None => continue,
+        };
+        parts.push(text_part(format!("Uploaded file: {}", file.name)));
+        parts.push(part);
+    }
+
+    parts
+}
+
+/// Convert messages, returning system instruction and contents separately.
+///
+/// Gemini doesn't have tool call ids, function responses are matched by name instead.
+fn to_contents(
+    messages: Vec<Message>,
+    capability: &openrouter::Capability,
+) -> (Option<Content>, Vec<Content>) {
+    let mut system = Vec::new();
+    let mut contents: Vec<Content> = Vec::new();
+    let mut tool_names = HashMap::new();
+
+    for message in messages {
+        let (role, parts) = match message {
+            Message::System(text) => {
+                system.push(text_part(text));
+                continue;
+            }
+            Message::User(text) => ("user", vec![text_part(text)]),
+            Message::MultipartUser { text, files } => ("user", user_parts(text, files, capability)),
+            Message::Assistant { content, .. } => {
+                if content.is_empty() {
+                    continue;
+                }
+                ("model", vec![text_part(content)])
+            }
+            Message::ToolCall(call) => {
+                tool_names.insert(call.id, call.name.clone());
+                let function_call = FunctionCall {
+                    name: call.name,
+                    args: serde_json::from_str(&call.arguments)
+                        .unwrap_or_else(|_| serde_json::json!({})),
+                };
+                ("model", vec![Part::FunctionCall { function_call }])
+            }
+            Message::ToolResult(result) => {
+                let function_response = FunctionResponse {
+                    name: tool_names.get(&result.id).cloned().unwrap_or_default(),
+                    response: serde_json::json!({ "content": result.content }),
+                };
+                ("user", vec![Part::FunctionResponse { function_response }])
+            }
+        };
+
+        match contents.last_mut() {
+            Some(last) if last.role == role => last.parts.extend(parts),
+            _ => contents.push(Content {
+                role: role.to_string(),
+                parts,
+            }),
+        }
+    }
+
+    let system = match system.is_empty() {
+        true => None,
+        false => Some(Content {
+            role: String::new(),
+            parts: system,
+        }),
+    };
+    (system, contents)
+}
+
+impl GoogleClient {
+    fn create_request(
+        &self,
+        messages: Vec<Message>,
+        model: &Model,
+        option: CompletionOption,
+    ) -> GenerateContentRequest {
+        let capability = Completion::get_capability(self, model);
+        let (system_instruction, contents) = to_contents(messages, &capability);
+
+        let tools = match option.tools.is_empty() || !capability.toolcall {
+            true => None,
+            false => Some(vec![Tool {
+                function_declarations: option
+                    .tools
+                    .into_iter()
+                    .map(|tool| FunctionDeclaration {
+                        name: tool.name,
+                        description: Some(tool.description),
+                        parameters: tool.schema,
+                    })
+                    .collect(),
+            }]),
+        };
+
+        GenerateContentRequest {
+            contents,
+            system_instruction,
+            generation_config: Some(GenerationConfig {
+                temperature: model.temperature,
+                top_p: model.top_p,
+                top_k: model.top_k,
+                max_output_tokens: option.max_tokens,
+                response_mime_type: None,
+            }),
+            safety_settings: None,
+            tools,
+        }
+    }
+
+    async fn send_complete_request(
+        &self,
+        model: &Model,
+        request: GenerateContentRequest,
+    ) -> Result<ChatCompletion, Error> {
+        let response = self.generate_content(&model.id, request).await?;
+
+        let candidate = response
+            .candidates
+            .into_iter()
+            .next()
+            .ok_or(Error::MalformedResponse(
+                "No candidates in completion response",
+            ))?;
+
+        let text = candidate
+            .content
+            .parts
+            .into_iter()
+            .filter_map(|part| match part {
+                Part::Text {
+                    text,
+                    thought: false,
+                } => Some(text),
+                _ => None,
+            })
+            .collect();
+
+        let usage = response.usage_metadata.unwrap_or_default();
+        Ok(ChatCompletion {
+            price: cost(
+                model_price(ProviderType::Google, model),
+                usage.prompt_token_count as i64,
+                (usage.total_token_count - usage.prompt_token_count) as i64,
+            ),
+            token: usage.total_token_count as usize,
+            response: text,
+        })
+    }
+}
+
+fn handle_response(
+    acc: &mut StreamAccumulator,
+    response: GenerateContentResponse,
+) -> Vec<StreamCompletionResp> {
+    let mut resps = Vec::new();
+    let mut finished = false;
+
+    for candidate in response.candidates {
+        for part in candidate.content.parts {
+            match part {
+                Part::Text {
+                    text,
+                    thought: true,
+                } => resps.push(acc.reasoning(text)),
+                Part::Text { text, .. } => resps.push(acc.text(text)),
+                Part::FunctionCall { function_call } => {
+                    let idx = acc.tool_count();
+                    let id = format!("call_{:016x}", fastrand::u64(..));
+                    resps.push(acc.tool_start(idx, id, function_call.name));
+                    resps.push(acc.tool_args(idx, function_call.args.to_string()));
+                }
+                _ => {}
+            }
+        }
+        match candidate.finish_reason.as_deref() {
+            Some("MAX_TOKENS") => acc.stop(FinishReason::Length),
+            Some("STOP") => acc.stop(FinishReason::Stop),
+            Some(_) => acc.stop(FinishReason::Error),
+            None => continue,
+        }
+        finished = true;
+    }
+
+    // usage metadata in every chunk is cumulative, only count the last one
+    // output includes thinking tokens, which aren't in `candidates_token_count`
+    if finished && let Some(usage) = response.usage_metadata {
+        resps.push(acc.usage(
+            usage.prompt_token_count as i64,
+            (usage.total_token_count - usage.prompt_token_count) as i64,
+        ));
+    }
+
+    resps
+}
+
+impl Completion for GoogleClient {
+    fn stream(
+        &self,
+        model: Model,
+        messages: Vec<Message>,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<BoxCompletionStream, Error>> {
+        Box::pin(async move {
+            let request = self.create_request(messages, &model, option);
+            let stream = self.generate_content_stream(&model.id, request).await?;
+            let price = model_price(ProviderType::Google, &model);
+            Ok(Box::new(NativeStream::new(stream, handle_response, price)) as BoxCompletionStream)
+        })
+    }
+
+    fn complete(
+        &self,
+        messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>> {
+        Box::pin(async move {
+            let request = self.create_request(messages, &model, option);
+            self.send_complete_request(&model, request).await
+        })
+    }
+
+    fn complete_json(
+        &self,
+        mut messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+        schema: JsonSchema,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>> {
+        Box::pin(async move {
+            messages.push(Message::System(json_instruction(&schema)));
+            let mut request = self.create_request(messages, &model, option);
+            if let Some(config) = request.generation_config.as_mut() {
+                config.response_mime_type = Some("application/json".to_string());
+            }
+            self.send_complete_request(&model, request).await
+        })
+    }
+}
@@ -5,7 +5,15 @@ pub enum GoogleError {
     Reqwest(reqwest::Error),
     SerdeJson(serde_json::Error),
     InvalidResponse(String),
-    ApiError { message: String, code: Option<i32> },
+    /// Non-success HTTP status with the response body
+    Status {
+        status: u16,
+        message: String,
+    },
+    ApiError {
+        message: String,
+        code: Option<i32>,
+    },
 }
 
 impl fmt::Display for GoogleError {
@@ -14,6 +22,7 @@ impl fmt::Display for GoogleError {
             GoogleError::Reqwest(e) => write!(f, "Request error: {}", e),
             GoogleError::SerdeJson(e) => write!(f, "JSON error: {}", e),
             GoogleError::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
+            GoogleError::Status { status, message } => write!(f, "HTTP {}: {}", status, message),
             GoogleError::ApiError { message, code } => {
                 write!(f, "API error: {} (code: {:?})", message, code)
             }
@@ -1,8 +1,8 @@
 use super::error::GoogleError;
 use super::types::GenerateContentResponse;
 use bytes::Bytes;
-use futures_util::stream::Stream;
 use futures_util::StreamExt;
+use futures_util::stream::Stream;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
@@ -19,14 +19,14 @@ impl GoogleStream {
         }
     }
 
+    /// parse a server-sent event line (`alt=sse`)
     fn parse_chunk(&mut self, chunk: &str) -> Option<Result<GenerateContentResponse, GoogleError>> {
-        if chunk.is_empty() || chunk.trim() == "[" || chunk.trim() == "]" {
+        let data = chunk.trim().strip_prefix("data:")?.trim();
+        if data.is_empty() {
             return None;
         }
 
-        let cleaned = chunk.trim().trim_end_matches(',');
-
-        match serde_json::from_str::<GenerateContentResponse>(cleaned) {
+        match serde_json::from_str::<GenerateContentResponse>(data) {
             Ok(response) => Some(Ok(response)),
             Err(e) => Some(Err(GoogleError::SerdeJson(e))),
         }
@@ -1,21 +1,25 @@
-pub mod openai;
 pub mod anthropic;
+pub mod catalog;
+pub mod completion;
 pub mod google;
+pub mod openai;
+
+pub use anthropic::AnthropicClient;
+pub use google::GoogleClient;
+pub use openai::OpenAIClient;
 
-pub use openai::{OpenAIClient, OpenAIError};
-pub use anthropic::{AnthropicClient, AnthropicError};
-pub use google::{GoogleClient, GoogleError};
+pub use catalog::ModelCatalog;
+pub use completion::{BoxCompletionStream, Completion, Providers};
+pub use protocol::ProviderType;
+
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum ProviderType {
-    OpenAI,
-    Anthropic,
-    Google,
-    OpenRouter,
-}
+use crate::openrouter::{self, MaybeCapability, Openrouter};
+
+const OPENROUTER_API_BASE: &str = "https://openrouter.ai/api";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
@@ -23,9 +27,16 @@ pub struct ProviderConfig {
     pub api_key: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub base_url: Option<String>,
+    /// Send openrouter plugins, only used by OpenRouter(compatible) endpoint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugins: Option<bool>,
+    /// Request usage accounting, only used by OpenRouter(compatible) endpoint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[typeshare]
 pub struct ModelInfo {
     pub id: String,
     pub display_name: String,
@@ -38,6 +49,7 @@ pub struct ModelInfo {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[typeshare]
 pub struct ModelCapabilities {
     pub text: bool,
     pub vision: bool,
@@ -47,12 +59,60 @@ pub struct ModelCapabilities {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[typeshare]
 pub struct ModelPricing {
     pub input_cost_per_1m: f32,
     pub output_cost_per_1m: f32,
 }
 
 impl ProviderConfig {
+    fn openai_client(&self) -> OpenAIClient {
+        let client = OpenAIClient::new(self.api_key.clone());
+        match self.base_url.clone() {
+            Some(url) => client.with_base_url(url),
+            None => client,
+        }
+    }
+
+    fn anthropic_client(&self) -> AnthropicClient {
+        let client = AnthropicClient::new(self.api_key.clone());
+        match self.base_url.clone() {
+            Some(url) => client.with_base_url(url),
+            None => client,
+        }
+    }
+
+    fn google_client(&self) -> GoogleClient {
+        let client = GoogleClient::new(self.api_key.clone());
+        match self.base_url.clone() {
+            Some(url) => client.with_base_url(url),
+            None => client,
+        }
+    }
+
+    fn openrouter_base(&self) -> &str {
+        self.base_url.as_deref().unwrap_or(OPENROUTER_API_BASE)
+    }
+
+    /// Create a completion backend for this provider
+    pub fn build(&self) -> Arc<dyn Completion> {
+        match self.provider_type {
+            ProviderType::OpenAI => Arc::new(self.openai_client()),
+            ProviderType::Anthropic => Arc::new(self.anthropic_client()),
+            ProviderType::Google => Arc::new(self.google_client()),
+            ProviderType::OpenRouter => {
+                let mut client = Openrouter::new(&self.api_key, self.openrouter_base());
+                if let Some(plugins) = self.plugins {
+                    client = client.with_plugins(plugins);
+                }
+                if let Some(usage) = self.usage {
+                    client = client.with_usage(usage);
+                }
+                Arc::new(client)
+            }
+        }
+    }
+
     pub async fn test_connection(&self) -> anyhow::Result<bool> {
         match self.provider_type {
             ProviderType::OpenAI => {
@@ -67,53 +127,37 @@ impl ProviderConfig {
                 let client = GoogleClient::new(self.api_key.clone());
                 Ok(client.test_connection().await?)
             }
-            ProviderType::OpenRouter => {
-                Ok(true)
-            }
+            ProviderType::OpenRouter => Ok(true),
         }
     }
 
-    pub fn get_default_models(&self) -> Vec<ModelInfo> {
-        match self.provider_type {
-            ProviderType::OpenAI => vec![
-                ModelInfo {
-                    id: "gpt-4o".to_string(),
-                    display_name: "GPT-4o".to_string(),
-                    provider: ProviderType::OpenAI,
-                    capabilities: ModelCapabilities {
-                        text: true,
-                        vision: true,
-                        image_gen: false,
-                        files: true,
-                        audio: false,
-                    },
-                    pricing: Some(ModelPricing {
-                        input_cost_per_1m: 2.5,
-                        output_cost_per_1m: 10.0,
-                    }),
-                    context_window: Some(128000),
-                },
-                ModelInfo {
-                    id: "gpt-4o-mini".to_string(),
-                    display_name: "GPT-4o Mini".to_string(),
-                    provider: ProviderType::OpenAI,
-                    capabilities: ModelCapabilities {
-                        text: true,
-                        vision: true,
-                        image_gen: false,
-                        files: true,
-                        audio: false,
+    /// Fetch models from the model listing endpoint of the provider
+    ///
+    /// Pricing and context window of known models are filled from the built-in table
+    /// when the provider doesn't report them.
+    pub async fn list_models(&self) -> anyhow::Result<Vec<ModelInfo>> {
+        let defaults = self.get_default_models();
+
+        let models: Vec<ModelInfo> = match self.provider_type {
+            ProviderType::OpenAI => self
+                .openai_client()
+                .list_models()
+                .await?
+                .into_iter()
+                .filter_map(|model| openai_model(model.id))
+                .collect(),
+            ProviderType::Anthropic => self
+                .anthropic_client()
+                .list_models()
+                .await?
+                .into_iter()
+                .map(|model| ModelInfo {
+                    display_name: match model.display_name.is_empty() {
+                        true => model.id.clone(),
+                        false => model.display_name,
                     },
-                    pricing: Some(ModelPricing {
-                        input_cost_per_1m: 0.15,
-                        output_cost_per_1m: 0.6,
-                    }),
-                    context_window: Some(128000),
-                },
-                ModelInfo {
-                    id: "gpt-4-turbo".to_string(),
-                    display_name: "GPT-4 Turbo".to_string(),
-                    provider: ProviderType::OpenAI,
+                    id: model.id,
+                    provider: ProviderType::Anthropic,
                     capabilities: ModelCapabilities {
                         text: true,
                         vision: true,
@@ -121,131 +165,360 @@ impl ProviderConfig {
                         files: true,
                         audio: false,
                     },
-                    pricing: Some(ModelPricing {
-                        input_cost_per_1m: 10.0,
-                        output_cost_per_1m: 30.0,
-                    }),
-                    context_window: Some(128000),
-                },
-                ModelInfo {
-                    id: "dall-e-3".to_string(),
-                    display_name: "DALL-E 3".to_string(),
-                    provider: ProviderType::OpenAI,
-                    capabilities: ModelCapabilities {
-                        text: false,
-                        vision: false,
-                        image_gen: true,
-                        files: false,
-                        audio: false,
-                    },
                     pricing: None,
                     context_window: None,
+                })
+                .collect(),
+            ProviderType::Google => self
+                .google_client()
+                .list_models()
+                .await?
+                .into_iter()
+                .filter_map(google_model)
+                .collect(),
+            ProviderType::OpenRouter => {
+                openrouter::list_models(self.openrouter_base(), &self.api_key)
+                    .await?
+                    .into_iter()
+                    .map(openrouter_model)
+                    .collect()
+            }
+        };
+
+        Ok(models
+            .into_iter()
+            .map(|model| fill_known(model, &defaults))
+            .collect())
+    }
+
+    pub fn get_default_models(&self) -> Vec<ModelInfo> {
+        default_models(self.provider_type)
+    }
+}
+
+/// Built-in table of well-known models, used when the provider cannot be reached
+pub fn default_models(provider_type: ProviderType) -> Vec<ModelInfo> {
+    match provider_type {
+        ProviderType::OpenAI => vec![
+            ModelInfo {
+                id: "gpt-4o".to_string(),
+                display_name: "GPT-4o".to_string(),
+                provider: ProviderType::OpenAI,
+                capabilities: ModelCapabilities {
+                    text: true,
+                    vision: true,
+                    image_gen: false,
+                    files: true,
+                    audio: false,
                 },
-            ],
-            ProviderType::Anthropic => vec![
-                ModelInfo {
-                    id: "claude-sonnet-4-20250514".to_string(),
-                    display_name: "Claude Sonnet 4".to_string(),
-                    provider: ProviderType::Anthropic,
-                    capabilities: ModelCapabilities {
-                        text: true,
-                        vision: true,
-                        image_gen: false,
-                        files: true,
-                        audio: false,
-                    },
-                    pricing: Some(ModelPricing {
-                        input_cost_per_1m: 3.0,
-                        output_cost_per_1m: 15.0,
-                    }),
-                    context_window: Some(200000),
+                pricing: Some(ModelPricing {
+                    input_cost_per_1m: 2.5,
+                    output_cost_per_1m: 10.0,
+                }),
+                context_window: Some(128000),
+            },
+            ModelInfo {
+                id: "gpt-4o-mini".to_string(),
+                display_name: "GPT-4o Mini".to_string(),
+                provider: ProviderType::OpenAI,
+                capabilities: ModelCapabilities {
+                    text: true,
+                    vision: true,
+                    image_gen: false,
+                    files: true,
+                    audio: false,
                 },
-                ModelInfo {
-                    id: "claude-opus-4-20250514".to_string(),
-                    display_name: "Claude Opus 4".to_string(),
-                    provider: ProviderType::Anthropic,
-                    capabilities: ModelCapabilities {
-                        text: true,
-                        vision: true,
-                        image_gen: false,
-                        files: true,
-                        audio: false,
-                    },
-                    pricing: Some(ModelPricing {
-                        input_cost_per_1m: 15.0,
-                        output_cost_per_1m: 75.0,
-                    }),
-                    context_window: Some(200000),
+                pricing: Some(ModelPricing {
+                    input_cost_per_1m: 0.15,
+                    output_cost_per_1m: 0.6,
+                }),
+                context_window: Some(128000),
+            },
+            ModelInfo {
+                id: "gpt-4-turbo".to_string(),
+                display_name: "GPT-4 Turbo".to_string(),
+                provider: ProviderType::OpenAI,
+                capabilities: ModelCapabilities {
+                    text: true,
+                    vision: true,
+                    image_gen: false,
+                    files: true,
+                    audio: false,
                 },
-                ModelInfo {
-                    id: "claude-haiku-4-20250514".to_string(),
-                    display_name: "Claude Haiku 4".to_string(),
-                    provider: ProviderType::Anthropic,
-                    capabilities: ModelCapabilities {
-                        text: true,
-                        vision: true,
-                        image_gen: false,
-                        files: true,
-                        audio: false,
-                    },
-                    pricing: Some(ModelPricing {
-                        input_cost_per_1m: 0.4,
-                        output_cost_per_1m: 2.0,
-                    }),
-                    context_window: Some(200000),
+                pricing: Some(ModelPricing {
+                    input_cost_per_1m: 10.0,
+                    output_cost_per_1m: 30.0,
+                }),
+                context_window: Some(128000),
+            },
+            ModelInfo {
+                id: "dall-e-3".to_string(),
+                display_name: "DALL-E 3".to_string(),
+                provider: ProviderType::OpenAI,
+                capabilities: ModelCapabilities {
+                    text: false,
+                    vision: false,
+                    image_gen: true,
+                    files: false,
+                    audio: false,
                 },
-            ],
-            ProviderType::Google => vec![
-                ModelInfo {
-                    id: "gemini-1.5-pro".to_string(),
-                    display_name: "Gemini 1.5 Pro".to_string(),
-                    provider: ProviderType::Google,
-                    capabilities: ModelCapabilities {
-                        text: true,
-                        vision: true,
-                        image_gen: false,
-                        files: true,
-                        audio: true,
-                    },
-                    pricing: Some(ModelPricing {
-                        input_cost_per_1m: 1.25,
-                        output_cost_per_1m: 5.0,
-                    }),
-                    context_window: Some(2097152),
+                pricing: None,
+                context_window: None,
+            },
+        ],
+        ProviderType::Anthropic => vec![
+            ModelInfo {
+                id: "claude-sonnet-4-20250514".to_string(),
+                display_name: "Claude Sonnet 4".to_string(),
+                provider: ProviderType::Anthropic,
+                capabilities: ModelCapabilities {
+                    text: true,
+                    vision: true,
+                    image_gen: false,
+                    files: true,
+                    audio: false,
                 },
-                ModelInfo {
-                    id: "gemini-1.5-flash".to_string(),
-                    display_name: "Gemini 1.5 Flash".to_string(),
-                    provider: ProviderType::Google,
-                    capabilities: ModelCapabilities {
-                        text: true,
-                        vision: true,
-                        image_gen: false,
-                        files: true,
-                        audio: true,
-                    },
-                    pricing: Some(ModelPricing {
-                        input_cost_per_1m: 0.075,
-                        output_cost_per_1m: 0.3,
-                    }),
-                    context_window: Some(1048576),
+                pricing: Some(ModelPricing {
+                    input_cost_per_1m: 3.0,
+                    output_cost_per_1m: 15.0,
+                }),
+                context_window: Some(200000),
+            },
+            ModelInfo {
+                id: "claude-opus-4-20250514".to_string(),
+                display_name: "Claude Opus 4".to_string(),
+                provider: ProviderType::Anthropic,
+                capabilities: ModelCapabilities {
+                    text: true,
+                    vision: true,
+                    image_gen: false,
+                    files: true,
+                    audio: false,
                 },
-                ModelInfo {
-                    id: "imagen-3".to_string(),
-                    display_name: "Imagen 3".to_string(),
-                    provider: ProviderType::Google,
-                    capabilities: ModelCapabilities {
-                        text: false,
-                        vision: false,
-                        image_gen: true,
-                        files: false,
-                        audio: false,
-                    },
-                    pricing: None,
-                    context_window: None,
+                pricing: Some(ModelPricing {
+                    input_cost_per_1m: 15.0,
+                    output_cost_per_1m: 75.0,
+                }),
+                context_window: Some(200000),
+            },
+            ModelInfo {
+                id: "claude-haiku-4-20250514".to_string(),
+                display_name: "Claude Haiku 4".to_string(),
+                provider: ProviderType::Anthropic,
+                capabilities: ModelCapabilities {
+                    text: true,
+                    vision: true,
+                    image_gen: false,
+                    files: true,
+                    audio: false,
+                },
+                pricing: Some(ModelPricing {
+                    input_cost_per_1m: 0.4,
+                    output_cost_per_1m: 2.0,
+                }),
+                context_window: Some(200000),
+            },
+        ],
+        ProviderType::Google => vec![
+            ModelInfo {
+                id: "gemini-1.5-pro".to_string(),
+                display_name: "Gemini 1.5 Pro".to_string(),
+                provider: ProviderType::Google,
+                capabilities: ModelCapabilities {
+                    text: true,
+                    vision: true,
+                    image_gen: false,
+                    files: true,
+                    audio: true,
+                },
+                pricing: Some(ModelPricing {
+                    input_cost_per_1m: 1.25,
+                    output_cost_per_1m: 5.0,
+                }),
+                context_window: Some(2097152),
+            },
+            ModelInfo {
+                id: "gemini-1.5-flash".to_string(),
+                display_name: "Gemini 1.5 Flash".to_string(),
+                provider: ProviderType::Google,
+                capabilities: ModelCapabilities {
+                    text: true,
+                    vision: true,
+                    image_gen: false,
+                    files: true,
+                    audio: true,
                 },
-            ],
-            ProviderType::OpenRouter => vec![],
+                pricing: Some(ModelPricing {
+                    input_cost_per_1m: 0.075,
+                    output_cost_per_1m: 0.3,
+                }),
+                context_window: Some(1048576),
+            },
+            ModelInfo {
+                id: "imagen-3".to_string(),
+                display_name: "Imagen 3".to_string(),
+                provider: ProviderType::Google,
+                capabilities: ModelCapabilities {
+                    text: false,
+                    vision: false,
+                    image_gen: true,
+                    files: false,
+                    audio: false,
+                },
+                pricing: None,
+                context_window: None,
+            },
+        ],
+        ProviderType::OpenRouter => vec![],
+    }
+}
+
+/// Map an OpenAI model, skipping ones not usable for chat(embedding, moderation, speech)
+fn openai_model(id: String) -> Option<ModelInfo> {
+    const NON_CHAT: [&str; 6] = [
+        "embedding",
+        "moderation",
+        "whisper",
+        "tts",
+        "davinci",
+        "babbage",
+    ];
+    if NON_CHAT.iter().any(|x| id.contains(x)) {
+        return None;
+    }
+
+    let image_gen = id.starts_with("dall-e") || id.starts_with("gpt-image");
+    let vision = !image_gen
+        && [
+            "gpt-4o",
+            "gpt-4.1",
+            "gpt-4-turbo",
+            "gpt-5",
+            "o1",
+            "o3",
+            "o4",
+        ]
+        .iter()
+        .any(|x| id.starts_with(x));
+
+    Some(ModelInfo {
+        display_name: id.clone(),
+        capabilities: ModelCapabilities {
+            text: !image_gen,
+            vision,
+            image_gen,
+            files: vision,
+            audio: id.contains("audio"),
+        },
+        id,
+        provider: ProviderType::OpenAI,
+        pricing: None,
+        context_window: None,
+    })
+}
+
+/// Map a Gemini model, skipping ones that cannot generate content(embedding, aqa)
+fn google_model(model: google::Model) -> Option<ModelInfo> {
+    let methods = &model.supported_generation_methods;
+    let generate = methods.iter().any(|x| x == "generateContent");
+    let predict = methods.iter().any(|x| x == "predict");
+    if !generate && !predict {
+        return None;
+    }
+
+    let id = model
+        .name
+        .strip_prefix("models/")
+        .unwrap_or(&model.name)
+        .to_string();
+    let gemini = id.starts_with("gemini");
+
+    Some(ModelInfo {
+        capabilities: ModelCapabilities {
+            text: generate,
+            vision: gemini,
+            image_gen: id.contains("image"),
+            files: gemini,
+            audio: gemini,
+        },
+        id,
+        display_name: model.display_name,
+        provider: ProviderType::Google,
+        pricing: None,
+        context_window: model.input_token_limit,
+    })
+}
+
+fn openrouter_model(model: openrouter::ModelMetadata) -> ModelInfo {
+    let capability = MaybeCapability::from(&model);
+
+    // openrouter prices are USD per token
+    let pricing = model.pricing.and_then(|pricing| {
+        Some(ModelPricing {
+            input_cost_per_1m: pricing.prompt.parse::<f32>().ok()? * 1_000_000.0,
+            output_cost_per_1m: pricing.completion.parse::<f32>().ok()? * 1_000_000.0,
+        })
+    });
+
+    ModelInfo {
+        display_name: model.name.unwrap_or_else(|| model.id.clone()),
+        id: model.id,
+        provider: ProviderType::OpenRouter,
+        capabilities: ModelCapabilities {
+            text: true,
+            vision: capability.image_input.unwrap_or_default(),
+            image_gen: capability.image_output.unwrap_or_default(),
+            files: capability.ocr == Some(protocol::OcrEngine::Native),
+            audio: capability.audio.unwrap_or_default(),
+        },
+        pricing,
+        context_window: model.context_length,
+    }
+}
+
+/// Find the longest matching built-in model
+///
+/// Dated snapshots(e.g. `gpt-4o-2024-08-06`) share the details of their alias.
+pub fn find_known<'a>(defaults: &'a [ModelInfo], model_id: &str) -> Option<&'a ModelInfo> {
+    defaults
+        .iter()
+        .filter(|known| model_id.starts_with(&known.id))
+        .max_by_key(|known| known.id.len())
+}
+
+/// Fill missing pricing and context window from the built-in table
+fn fill_known(mut model: ModelInfo, defaults: &[ModelInfo]) -> ModelInfo {
+    if let Some(known) = find_known(defaults, &model.id) {
+        if model.pricing.is_none() {
+            model.pricing = known.pricing.clone();
+        }
+        if model.context_window.is_none() {
+            model.context_window = known.context_window;
         }
     }
+    model
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_dated_snapshot() {
+        let config = ProviderConfig {
+            provider_type: ProviderType::OpenAI,
+            api_key: String::new(),
+            base_url: None,
+            plugins: None,
+            usage: None,
+        };
+        let defaults = config.get_default_models();
+
+        let model = openai_model("gpt-4o-mini-2024-07-18".to_string()).unwrap();
+        let model = fill_known(model, &defaults);
+        assert_eq!(model.context_window, Some(128000));
+        assert_eq!(model.pricing.unwrap().input_cost_per_1m, 0.15);
+
+        assert!(openai_model("text-embedding-3-small".to_string()).is_none());
+    }
 }
@@ -1,6 +1,6 @@
 use super::error::OpenAIError;
-use super::types::*;
 use super::stream::OpenAIStream;
+use super::types::*;
 
 pub struct OpenAIClient {
     api_key: String,
@@ -44,8 +44,9 @@ impl OpenAIClient {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(OpenAIError::InvalidResponse(error_text));
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(OpenAIError::Status { status, message });
         }
 
         let model_list: ModelListResponse = response.json().await?;
@@ -66,8 +67,9 @@ impl OpenAIClient {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(OpenAIError::InvalidResponse(error_text));
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(OpenAIError::Status { status, message });
         }
 
         let completion: ChatCompletionResponse = response.json().await?;
@@ -90,8 +92,9 @@ impl OpenAIClient {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(OpenAIError::InvalidResponse(error_text));
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(OpenAIError::Status { status, message });
         }
 
         Ok(OpenAIStream::new(response))
@@ -111,8 +114,9 @@ impl OpenAIClient {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(OpenAIError::InvalidResponse(error_text));
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(OpenAIError::Status { status, message });
         }
 
         let image_response: ImageGenerationResponse = response.json().await?;
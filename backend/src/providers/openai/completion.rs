@@ -0,0 +1,293 @@
+use futures_util::future::BoxFuture;
+use protocol::{ModelPrice, ProviderType};
+
+use super::stream::StreamChunk;
+use super::types::*;
+use super::{OpenAIClient, OpenAIError};
+use crate::openrouter::{
+    self, ChatCompletion, CompletionOption, Error, FinishReason, JsonSchema, Message, Model,
+    StreamCompletionResp,
+};
+use crate::providers::completion::{
+    Attachment, BoxCompletionStream, Completion, NativeStream, StreamAccumulator, attachment, cost,
+    json_instruction, model_price,
+};
+
+impl From<OpenAIError> for Error {
+    fn from(err: OpenAIError) -> Self {
+        match err {
+            OpenAIError::Reqwest(e) => Error::Http(e),
+            OpenAIError::SerdeJson(e) => Error::Serde(e),
+            OpenAIError::InvalidResponse(message) => Error::Api {
+                message,
+                code: None,
+            },
+            OpenAIError::Status { status, message } => Error::Api {
+                message,
+                code: Some(status as i32),
+            },
+            OpenAIError::ApiError { message, .. } => Error::Api {
+                message,
+                code: None,
+            },
+        }
+    }
+}
+
+fn text_message(role: &str, text: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: MessageContent::Text(text),
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+fn user_parts(
+    text: String,
+    files: Vec<openrouter::File>,
+    capability: &openrouter::Capability,
+) -> Vec<ContentPart> {
+    let mut parts = vec![ContentPart::Text { text }];
+
+    for file in files {
+        let part = match attachment(&file, capability) {
+            Some(Attachment::Text(text)) => ContentPart::Text { text },
+            Some(Attachment::Inline { mime_type, data }) => match mime_type.as_str() {
+                "audio/mpeg" | "audio/x-wav" | "audio/wav" => ContentPart::InputAudio {
+                    input_audio: InputAudio {
+                        format: match mime_type.as_str() {
+                            "audio/mpeg" => "mp3".to_string(),
+                            _ => "wav".to_string(),
+                        },
+                        data,
+                    },
+                },
+                mime if mime.starts_with("image/") => ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: format!("data:{};base64,{}", mime, data),
+                        detail: None,
+                    },
+                },
+                "application/pdf" => ContentPart::File {
+                    file: FileData {
+                        filename: file.name.clone(),
+                        file_data: format!("data:{};base64,{}", mime_type, data),
+                    },
+                },
+                _ => continue,
+            },
+            None => continue,
+        };
+        parts.push(ContentPart::Text {
+            text: format!("Uploaded file: {}", file.name),
+        });
+        parts.push(part);
+    }
+
+    parts
+}
+
+fn to_chat_messages(
+    messages: Vec<Message>,
+    capability: &openrouter::Capability,
+) -> Vec<ChatMessage> {
+    messages
+        .into_iter()
+        .map(|message| match message {
+            Message::System(text) => text_message("system", text),
+            Message::User(text) => text_message("user", text),
+            Message::Assistant { content, .. } => text_message("assistant", content),
+            Message::MultipartUser { text, files } => ChatMessage {
+                role: "user".to_string(),
+                content: MessageContent::Parts(user_parts(text, files, capability)),
+                tool_calls: None,
+                tool_call_id: None,
+            },
+            Message::ToolCall(call) => ChatMessage {
+                tool_calls: Some(vec![ToolCall {
+                    id: call.id,
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: call.name,
+                        arguments: call.arguments,
+                    },
+                }]),
+                ..text_message("assistant", String::new())
+            },
+            Message::ToolResult(result) => ChatMessage {
+                tool_call_id: Some(result.id),
+                ..text_message("tool", result.content)
+            },
+        })
+        .collect()
+}
+
+impl OpenAIClient {
+    fn create_request(
+        &self,
+        messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+    ) -> ChatCompletionRequest {
+        let capability = Completion::get_capability(self, &model);
+
+        let tools = match option.tools.is_empty() || !capability.toolcall {
+            true => None,
+            false => Some(
+                option
+                    .tools
+                    .into_iter()
+                    .map(|tool| Tool {
+                        tool_type: "function".to_string(),
+                        function: FunctionDefinition {
+                            name: tool.name,
+                            description: Some(tool.description),
+                            parameters: tool.schema,
+                        },
+                    })
+                    .collect(),
+            ),
+        };
+
+        ChatCompletionRequest {
+            messages: to_chat_messages(messages, &capability),
+            model: model.id,
+            temperature: model.temperature,
+            top_p: model.top_p,
+            max_tokens: option.max_tokens,
+            stream: None,
+            stream_options: None,
+            tools,
+            response_format: None,
+            reasoning_effort: match option.reasoning_effort {
+                openrouter::ReasoningEffort::None | openrouter::ReasoningEffort::Auto => None,
+                effort => effort.to_value(),
+            },
+        }
+    }
+
+    async fn send_complete_request(
+        &self,
+        request: ChatCompletionRequest,
+        price: Option<ModelPrice>,
+    ) -> Result<ChatCompletion, Error> {
+        let response = self.chat_completion(request).await?;
+
+        let choice = response
+            .choices
+            .into_iter()
+            .next()
+            .ok_or(Error::MalformedResponse(
+                "No choices in completion response",
+            ))?;
+
+        let usage = response.usage.unwrap_or_default();
+        Ok(ChatCompletion {
+            price: cost(
+                price,
+                usage.prompt_tokens as i64,
+                usage.completion_tokens as i64,
+            ),
+            token: usage.total_tokens as usize,
+            response: choice.message.content.unwrap_or_default(),
+        })
+    }
+}
+
+fn handle_chunk(acc: &mut StreamAccumulator, chunk: StreamChunk) -> Vec<StreamCompletionResp> {
+    let mut resps = Vec::new();
+
+    if let Some(usage) = chunk.usage {
+        resps.push(acc.usage(usage.prompt_tokens as i64, usage.completion_tokens as i64));
+    }
+
+    for choice in chunk.choices {
+        let delta = choice.delta;
+        if let Some(content) = delta.content {
+            resps.push(acc.text(content));
+        }
+        for call in delta.tool_calls.unwrap_or_default() {
+            let idx = call.index as usize;
+            let function = call.function.unwrap_or(super::stream::FunctionDelta {
+                name: None,
+                arguments: None,
+            });
+            if let Some(id) = call.id {
+                resps.push(acc.tool_start(idx, id, function.name.unwrap_or_default()));
+            }
+            if let Some(args) = function.arguments {
+                resps.push(acc.tool_args(idx, args));
+            }
+        }
+        match choice.finish_reason.as_deref() {
+            Some("length") => acc.stop(FinishReason::Length),
+            Some("content_filter") => acc.stop(FinishReason::Error),
+            Some(_) => acc.stop(FinishReason::Stop),
+            None => {}
+        }
+    }
+
+    resps
+}
+
+impl Completion for OpenAIClient {
+    fn stream(
+        &self,
+        model: Model,
+        messages: Vec<Message>,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<BoxCompletionStream, Error>> {
+        Box::pin(async move {
+            let price = model_price(ProviderType::OpenAI, &model);
+            let mut request = self.create_request(messages, model, option);
+            request.stream_options = Some(StreamOptions {
+                include_usage: true,
+            });
+            let stream = self.chat_completion_stream(request).await?;
+            Ok(Box::new(NativeStream::new(stream, handle_chunk, price)) as BoxCompletionStream)
+        })
+    }
+
+    fn complete(
+        &self,
+        messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>> {
+        Box::pin(async move {
+            let price = model_price(ProviderType::OpenAI, &model);
+            let request = self.create_request(messages, model, option);
+            self.send_complete_request(request, price).await
+        })
+    }
+
+    fn complete_json(
+        &self,
+        mut messages: Vec<Message>,
+        model: Model,
+        option: CompletionOption,
+        schema: JsonSchema,
+    ) -> BoxFuture<'_, Result<ChatCompletion, Error>> {
+        Box::pin(async move {
+            let price = model_price(ProviderType::OpenAI, &model);
+            let structured_output = Completion::get_capability(self, &model).structured_output;
+            if !structured_output {
+                messages.push(Message::System(json_instruction(&schema)));
+            }
+
+            let mut request = self.create_request(messages, model, option);
+            if structured_output {
+                request.response_format = Some(serde_json::json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": schema.name,
+                        "strict": true,
+                        "schema": schema.schema
+                    }
+                }));
+            }
+            self.send_complete_request(request, price).await
+        })
+    }
+}
@@ -5,7 +5,15 @@ pub enum OpenAIError {
     Reqwest(reqwest::Error),
     SerdeJson(serde_json::Error),
     InvalidResponse(String),
-    ApiError { message: String, code: Option<String> },
+    /// Non-success HTTP status with the response body
+    Status {
+        status: u16,
+        message: String,
+    },
+    ApiError {
+        message: String,
+        code: Option<String>,
+    },
 }
 
 impl fmt::Display for OpenAIError {
@@ -14,6 +22,7 @@ impl fmt::Display for OpenAIError {
             OpenAIError::Reqwest(e) => write!(f, "Request error: {}", e),
             OpenAIError::SerdeJson(e) => write!(f, "JSON error: {}", e),
             OpenAIError::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
+            OpenAIError::Status { status, message } => write!(f, "HTTP {}: {}", status, message),
             OpenAIError::ApiError { message, code } => {
                 write!(f, "API error: {} (code: {:?})", message, code)
             }
@@ -1,7 +1,7 @@
 use super::error::OpenAIError;
 use bytes::Bytes;
-use futures_util::stream::Stream;
 use futures_util::StreamExt;
+use futures_util::stream::Stream;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -12,7 +12,11 @@ pub struct StreamChunk {
     pub object: String,
     pub created: i64,
     pub model: String,
+    #[serde(default)]
     pub choices: Vec<StreamChoice>,
+    /// only present in last chunk when `stream_options.include_usage` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<super::types::Usage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
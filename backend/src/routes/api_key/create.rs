@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::api_key;
+use protocol::ApiKeyScope;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId, utils};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ApiKeyCreateReq {
+    pub name: String,
+    /// Defaults to `full`
+    #[serde(default)]
+    pub scope: ApiKeyScope,
+    /// unix timestamp in seconds, the key never expires if omitted
+    #[typeshare(serialized_as = "Option<number>")]
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ApiKeyCreateResp {
+    pub id: i32,
+    /// The key, it cannot be read again
+    pub key: String,
+}
+
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Json(req): Json<ApiKeyCreateReq>,
+) -> JsonResult<ApiKeyCreateResp> {
+    let now = entity::patch::now();
+    if req.expires_at.is_some_and(|x| x <= now) {
+        return Err(Json(Error {
+            error: ErrorKind::MalformedRequest,
+            reason: "expires_at is in the past".to_owned(),
+        }));
+    }
+
+    let (key, prefix, hash) = utils::api_key::generate().kind(ErrorKind::Internal)?;
+    let model = api_key::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(req.name),
+        prefix: Set(prefix),
+        hash: Set(hash),
+        scope: Set(req.scope),
+        created_at: Set(now),
+        expires_at: Set(req.expires_at),
+        ..Default::default()
+    }
+    .insert(&app.conn)
+    .await
+    .kind(ErrorKind::Internal)?;
+
+    Ok(Json(ApiKeyCreateResp { id: model.id, key }))
+}
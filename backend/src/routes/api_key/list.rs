@@ -0,0 +1,99 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{Extension, Json, extract::State};
+use entity::{api_key, prelude::*, usage};
+use protocol::ApiKeyScope;
+use sea_orm::{QueryOrder, QuerySelect, prelude::*};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ApiKeyListReq {}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ApiKeyListResp {
+    pub list: Vec<ApiKeyList>,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ApiKeyList {
+    pub id: i32,
+    pub name: String,
+    /// Start of the key, like `llm_AbCd1234`
+    pub prefix: String,
+    pub scope: ApiKeyScope,
+    /// unix timestamp in seconds
+    #[typeshare(serialized_as = "number")]
+    pub created_at: i64,
+    /// unix timestamp in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[typeshare(serialized_as = "Option<number>")]
+    pub expires_at: Option<i64>,
+    /// unix timestamp in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[typeshare(serialized_as = "Option<number>")]
+    pub last_used_at: Option<i64>,
+    /// Cost of completions requested with this key
+    pub cost: f32,
+    #[typeshare(serialized_as = "number")]
+    pub token: i64,
+}
+
+/// Usable API keys of the current user, newest first
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Json(_): Json<ApiKeyListReq>,
+) -> JsonResult<ApiKeyListResp> {
+    let spent: HashMap<i32, (Option<f64>, Option<i64>)> = Usage::find()
+        .filter(usage::Column::UserId.eq(user_id))
+        .filter(usage::Column::ApiKeyId.is_not_null())
+        .select_only()
+        .column(usage::Column::ApiKeyId)
+        .column_as(usage::Column::Price.sum(), "cost")
+        .column_as(usage::Column::TokenCount.sum(), "token")
+        .group_by(usage::Column::ApiKeyId)
+        .into_tuple::<(i32, Option<f64>, Option<i64>)>()
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .into_iter()
+        .map(|(id, cost, token)| (id, (cost, token)))
+        .collect();
+
+    let list = ApiKey::find()
+        .filter(api_key::Column::UserId.eq(user_id))
+        .filter(api_key::Column::RevokedAt.is_null())
+        .filter(
+            api_key::Column::ExpiresAt
+                .is_null()
+                .or(api_key::Column::ExpiresAt.gt(entity::patch::now())),
+        )
+        .order_by_desc(api_key::Column::Id)
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .into_iter()
+        .map(|x| {
+            let (cost, token) = spent.get(&x.id).copied().unwrap_or_default();
+            ApiKeyList {
+                id: x.id,
+                name: x.name,
+                prefix: x.prefix,
+                scope: x.scope,
+                created_at: x.created_at,
+                expires_at: x.expires_at,
+                last_used_at: x.last_used_at,
+                cost: cost.unwrap_or_default() as f32,
+                token: token.unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    Ok(Json(ApiKeyListResp { list }))
+}
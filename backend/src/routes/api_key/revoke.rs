@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::{api_key, prelude::*};
+use sea_orm::prelude::*;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ApiKeyRevokeReq {
+    pub id: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ApiKeyRevokeResp {}
+
+/// Revoke an API key of the current user, it stops working immediately
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Json(req): Json<ApiKeyRevokeReq>,
+) -> JsonResult<ApiKeyRevokeResp> {
+    let res = ApiKey::update_many()
+        .col_expr(
+            api_key::Column::RevokedAt,
+            Expr::value(entity::patch::now()),
+        )
+        .filter(api_key::Column::Id.eq(req.id))
+        .filter(api_key::Column::UserId.eq(user_id))
+        .filter(api_key::Column::RevokedAt.is_null())
+        .exec(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    if res.rows_affected == 0 {
+        return Err(Json(Error {
+            error: ErrorKind::ResourceNotFound,
+            reason: "API key not found".to_owned(),
+        }));
+    }
+
+    Ok(Json(ApiKeyRevokeResp {}))
+}
@@ -2,12 +2,13 @@ use std::sync::Arc;
 
 use axum::{Json, extract::State, http::HeaderMap};
 use entity::{prelude::*, user};
-use sea_orm::prelude::*;
+use protocol::UserGroups;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, IntoActiveModel, prelude::*};
 use serde::Serialize;
 use typeshare::typeshare;
 
 use super::helper;
-use crate::{AppState, errors::*};
+use crate::{AppState, errors::*, utils::header_auth::HeaderAuthConfig};
 
 #[derive(Debug, Clone, Serialize)]
 #[typeshare]
@@ -20,28 +21,100 @@ pub async fn route(
     State(app): State<Arc<AppState>>,
     headers: HeaderMap,
 ) -> JsonResult<HeaderAuthResp> {
-    let header = app.auth_header.as_deref();
-    let username = header.and_then(|x| headers.get(x).and_then(|x| x.to_str().ok()));
+    let Some(config) = &app.header_auth else {
+        return Ok(Json(HeaderAuthResp {
+            token: None,
+            exp: None,
+        }));
+    };
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|x| x.to_str().ok())
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(str::to_owned)
+    };
 
-    if username.is_none() {
+    let Some(username) = header(&config.header) else {
         return Ok(Json(HeaderAuthResp {
             token: None,
             exp: None,
         }));
-    }
+    };
+    let display_name = config.display_name_header.as_deref().and_then(header);
+    let email = config.email_header.as_deref().and_then(header);
 
     let model = User::find()
-        .filter(user::Column::Name.eq(username.unwrap()))
+        .filter(user::Column::Name.eq(&username))
         .one(&app.conn)
         .await
-        .kind(ErrorKind::Internal)?
-        .ok_or("User not found")
-        .kind(ErrorKind::ResourceNotFound)?;
+        .kind(ErrorKind::Internal)?;
+
+    let model = match model {
+        Some(model) => update_profile(&app, model, display_name, email).await?,
+        None if config.provision => provision(&app, config, username, display_name, email).await?,
+        None => {
+            return Err(Json(Error {
+                error: ErrorKind::ResourceNotFound,
+                reason: "User not found".to_owned(),
+            }));
+        }
+    };
 
-    let helper::Token { token, exp } = helper::new_token(&app, model.id)?;
+    let helper::Token { token, exp } =
+        helper::new_token(&app, model.id, helper::user_agent(&headers)).await?;
 
     Ok(Json(HeaderAuthResp {
         token: Some(token),
         exp: Some(exp),
     }))
 }
+
+/// Keep display name and email in sync with the proxy, only the headers that are sent
+async fn update_profile(
+    app: &AppState,
+    model: user::Model,
+    display_name: Option<String>,
+    email: Option<String>,
+) -> Result<user::Model, AppError> {
+    let display_name = display_name.filter(|x| model.display_name.as_ref() != Some(x));
+    let email = email.filter(|x| model.email.as_ref() != Some(x));
+    if display_name.is_none() && email.is_none() {
+        return Ok(model);
+    }
+
+    let mut active_model = model.into_active_model();
+    if let Some(display_name) = display_name {
+        active_model.display_name = Set(Some(display_name));
+    }
+    if let Some(email) = email {
+        active_model.email = Set(Some(email));
+    }
+    active_model
+        .update(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)
+}
+
+async fn provision(
+    app: &AppState,
+    config: &HeaderAuthConfig,
+    username: String,
+    display_name: Option<String>,
+    email: Option<String>,
+) -> Result<user::Model, AppError> {
+    log::info!("provisioning user {} from trusted header", username);
+    user::ActiveModel {
+        name: Set(username),
+        password: Set(app.hasher.hash_unusable()),
+        role: Set(config.default_role),
+        groups: Set(UserGroups(config.default_groups.clone())),
+        display_name: Set(display_name),
+        email: Set(email),
+        ..Default::default()
+    }
+    .insert(&app.conn)
+    .await
+    .kind(ErrorKind::Internal)
+}
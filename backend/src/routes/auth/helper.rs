@@ -1,21 +1,35 @@
 use crate::{
     AppState,
     errors::{AppError, ErrorKind, WithKind},
+    utils::session,
 };
 use pasetors::{claims::Claims, local};
 use std::time::Duration;
 
+const TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
 pub struct Token {
     pub token: String,
     pub exp: String,
 }
-pub fn new_token(app: &AppState, user_id: i32) -> Result<Token, AppError> {
+
+/// Issue a token backed by a new session
+pub async fn new_token(
+    app: &AppState,
+    user_id: i32,
+    user_agent: Option<String>,
+) -> Result<Token, AppError> {
     let mut claim = Claims::new().kind(ErrorKind::Internal)?;
 
-    let expiration = Duration::from_secs(60 * 60 * 24 * 7);
     claim
-        .set_expires_in(&expiration)
+        .set_expires_in(&TOKEN_LIFETIME)
+        .kind(ErrorKind::Internal)?;
+
+    let expires_at = entity::patch::now() + TOKEN_LIFETIME.as_secs() as i64;
+    let jti = session::create(&app.conn, user_id, user_agent, expires_at)
+        .await
         .kind(ErrorKind::Internal)?;
+    claim.token_identifier(&jti).kind(ErrorKind::Internal)?;
 
     // safety:
     // "uid" is not reserve
@@ -29,3 +43,11 @@ pub fn new_token(app: &AppState, user_id: i32) -> Result<Token, AppError> {
 
     Ok(Token { token, exp })
 }
+
+/// `User-Agent` of a request, kept on the session to tell devices apart
+pub fn user_agent(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.chars().take(256).collect())
+}
@@ -1,19 +1,30 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
-use axum::{Json, extract::State};
+use axum::{
+    Extension, Json,
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
+};
 use entity::{prelude::*, user};
-use sea_orm::prelude::*;
+use protocol::UserRole;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, IntoActiveModel, prelude::*};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
 use super::helper;
-use crate::{AppState, errors::*};
+use crate::{
+    AppState,
+    errors::*,
+    utils::{api_key, login_guard::Account, session},
+};
 
 #[derive(Debug, Deserialize)]
 #[typeshare]
 pub struct LoginReq {
     pub username: String,
     pub password: String,
+    /// Required when login fails with `password_change_required`
+    pub new_password: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -25,24 +36,80 @@ pub struct LoginResp {
 
 pub async fn route(
     State(app): State<Arc<AppState>>,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
     Json(req): Json<LoginReq>,
 ) -> JsonResult<LoginResp> {
+    let guard = &app.login_guard;
+    let ip = guard.client_ip(&headers, peer.map(|Extension(ConnectInfo(addr))| addr));
+
     let model = User::find()
-        .filter(user::Column::Name.eq(req.username))
+        .filter(user::Column::Name.eq(&req.username))
         .one(&app.conn)
         .await
-        .kind(ErrorKind::Internal)?
-        .ok_or("")
-        .kind(ErrorKind::LoginFail)?;
+        .kind(ErrorKind::Internal)?;
+    // unknown usernames are only throttled by IP, admins are never locked
+    let account = model.as_ref().map(|model| Account {
+        name: &req.username,
+        admin: model.role == UserRole::Admin,
+    });
+
+    guard.check(ip, account).kind(ErrorKind::TooManyAttempts)?;
 
-    if !app.hasher.verify_password(&model.password, &req.password) {
+    let verified = match &model {
+        Some(model) => app.hasher.verify_password(&model.password, &req.password),
+        None => {
+            app.hasher.verify_nobody(&req.password);
+            false
+        }
+    };
+
+    let Some(model) = model.filter(|_| verified) else {
+        guard.fail(ip, account);
         return Err(Json(Error {
             error: ErrorKind::LoginFail,
             reason: "".to_owned(),
         }));
-    }
+    };
+    guard.succeed(ip, account);
+
+    let model = match (model.must_change_password, req.new_password) {
+        (false, _) => model,
+        (true, None) => {
+            return Err(Json(Error {
+                error: ErrorKind::PasswordChangeRequired,
+                reason: "password must be changed before logging in".to_owned(),
+            }));
+        }
+        (true, Some(new_password)) => {
+            if new_password == req.password {
+                return Err(Json(Error {
+                    error: ErrorKind::WeakPassword,
+                    reason: "new password must differ from the current one".to_owned(),
+                }));
+            }
+            app.password_policy
+                .check(&model.name, &new_password)
+                .kind(ErrorKind::WeakPassword)?;
+            let mut active_model = model.into_active_model();
+            active_model.password = Set(app.hasher.hash_password(&new_password));
+            active_model.must_change_password = Set(false);
+            let model = active_model
+                .update(&app.conn)
+                .await
+                .kind(ErrorKind::Internal)?;
+            session::revoke_all(&app.conn, model.id, None)
+                .await
+                .kind(ErrorKind::Internal)?;
+            api_key::revoke_all(&app.conn, model.id, None)
+                .await
+                .kind(ErrorKind::Internal)?;
+            model
+        }
+    };
 
-    let helper::Token { token, exp } = helper::new_token(&app, model.id)?;
+    let helper::Token { token, exp } =
+        helper::new_token(&app, model.id, helper::user_agent(&headers)).await?;
 
     Ok(Json(LoginResp { token, exp }))
 }
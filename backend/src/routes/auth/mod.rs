@@ -1,12 +1,16 @@
 use std::sync::Arc;
 
-use axum::{Router, routing::post};
+use axum::{
+    Router,
+    routing::{get, post},
+};
 
 use crate::AppState;
 
 mod header_auth;
 mod helper;
 mod login;
+mod oidc;
 mod renew;
 
 pub fn routes() -> Router<Arc<AppState>> {
@@ -14,4 +18,7 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/login", post(login::route))
         .route("/renew", post(renew::route))
         .route("/header", post(header_auth::route))
+        .route("/oidc", post(oidc::info))
+        .route("/oidc/start", get(oidc::start))
+        .route("/oidc/callback", get(oidc::callback))
 }
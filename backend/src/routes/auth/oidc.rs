@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{
+        HeaderMap,
+        header::{COOKIE, SET_COOKIE},
+    },
+    response::{IntoResponse, Redirect, Response},
+};
+use entity::{prelude::*, user};
+use protocol::UserGroups;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, IntoActiveModel, prelude::*};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::helper;
+use crate::{
+    AppState,
+    errors::*,
+    utils::oidc::{LOGIN_COOKIE, Oidc, OidcIdentity},
+};
+
+/// Where the frontend picks up the result, in the fragment so it never reaches a server log
+const LOGIN_PAGE: &str = "/login";
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct OidcInfoReq {}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct OidcInfoResp {
+    pub enabled: bool,
+}
+
+pub async fn info(
+    State(app): State<Arc<AppState>>,
+    Json(_): Json<OidcInfoReq>,
+) -> JsonResult<OidcInfoResp> {
+    Ok(Json(OidcInfoResp {
+        enabled: app.oidc.is_some(),
+    }))
+}
+
+/// Redirect the browser to the issuer, the login is kept in a cookie for the callback
+pub async fn start(State(app): State<Arc<AppState>>) -> Response {
+    let Some(oidc) = &app.oidc else {
+        return fail("OpenID Connect is not configured").into_response();
+    };
+    match oidc.authorize(&app.key).await {
+        Ok(login) => (
+            [(SET_COOKIE, oidc.cookie(&login.cookie))],
+            Redirect::to(&login.url),
+        )
+            .into_response(),
+        Err(err) => {
+            log::warn!("Cannot start OpenID Connect login: {:#}", err);
+            fail("Cannot reach the OpenID Connect issuer").into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Issuer redirects back here, a token is handed to the frontend in the URL fragment
+pub async fn callback(
+    State(app): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<CallbackQuery>,
+) -> Response {
+    let Some(oidc) = &app.oidc else {
+        return fail("OpenID Connect is not configured").into_response();
+    };
+    // the login is over either way
+    let clear = [(SET_COOKIE, oidc.cookie(""))];
+    (clear, finish(&app, oidc, &headers, query).await).into_response()
+}
+
+async fn finish(
+    app: &AppState,
+    oidc: &Oidc,
+    headers: &HeaderMap,
+    query: CallbackQuery,
+) -> Redirect {
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        let reason = query
+            .error_description
+            .or(query.error)
+            .unwrap_or("missing code".to_owned());
+        return fail(&reason);
+    };
+
+    let identity = match oidc
+        .exchange(&app.key, &code, &state, login_cookie(headers))
+        .await
+    {
+        Ok(identity) => identity,
+        Err(err) => {
+            log::warn!("OpenID Connect login failed: {:#}", err);
+            return fail(&err.to_string());
+        }
+    };
+
+    let user = match provision(app, identity).await {
+        Ok(user) => user,
+        Err(Json(err)) => return fail(&err.reason),
+    };
+
+    match helper::new_token(app, user.id, helper::user_agent(headers)).await {
+        Ok(helper::Token { token, exp }) => Redirect::to(&format!(
+            "{}#token={}&exp={}",
+            LOGIN_PAGE,
+            urlencoding::encode(&token),
+            urlencoding::encode(&exp)
+        )),
+        Err(Json(err)) => fail(&err.reason),
+    }
+}
+
+/// Find the user of an identity, create it on first login
+///
+/// Users are matched by subject, an existing local user of the same name is never taken over.
+async fn provision(app: &AppState, identity: OidcIdentity) -> Result<user::Model, AppError> {
+    let OidcIdentity {
+        subject,
+        username,
+        role,
+        groups,
+    } = identity;
+
+    let existing = User::find()
+        .filter(user::Column::OidcSubject.eq(&subject))
+        .one(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    if let Some(existing) = existing {
+        if role.is_none() && groups.is_none() {
+            return Ok(existing);
+        }
+        let mut active_model = existing.into_active_model();
+        if let Some(role) = role {
+            active_model.role = Set(role);
+        }
+        if let Some(groups) = groups {
+            active_model.groups = Set(UserGroups(groups));
+        }
+        return active_model
+            .update(&app.conn)
+            .await
+            .kind(ErrorKind::Internal);
+    }
+
+    let taken = User::find()
+        .filter(user::Column::Name.eq(&username))
+        .one(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .is_some();
+    if taken {
+        return Err(Json(Error {
+            error: ErrorKind::LoginFail,
+            reason: format!("username {} is taken by another account", username),
+        }));
+    }
+
+    log::info!("provisioning user {} from OpenID Connect", username);
+    user::ActiveModel {
+        name: Set(username),
+        password: Set(app.hasher.hash_unusable()),
+        role: Set(role.unwrap_or_default()),
+        groups: Set(UserGroups(groups.unwrap_or_default())),
+        oidc_subject: Set(Some(subject)),
+        ..Default::default()
+    }
+    .insert(&app.conn)
+    .await
+    .kind(ErrorKind::Internal)
+}
+
+/// Sealed login set by [`start`]
+fn login_cookie(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .flat_map(|x| x.split(';'))
+        .filter_map(|x| x.trim().split_once('='))
+        .find(|(name, _)| *name == LOGIN_COOKIE)
+        .map(|(_, value)| value)
+}
+
+fn fail(reason: &str) -> Redirect {
+    Redirect::to(&format!(
+        "{}#error={}",
+        LOGIN_PAGE,
+        urlencoding::encode(reason)
+    ))
+}
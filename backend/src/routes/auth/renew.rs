@@ -1,17 +1,12 @@
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 
 use axum::{Json, extract::State};
-use pasetors::{
-    Local,
-    claims::{Claims, ClaimsValidationRules},
-    local,
-    token::UntrustedToken,
-    version4::V4,
-};
+use pasetors::{Local, claims::ClaimsValidationRules, local, token::UntrustedToken, version4::V4};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
-use crate::{AppState, errors::*};
+use super::helper;
+use crate::{AppState, errors::*, utils::session};
 
 #[derive(Debug, Clone, Deserialize)]
 #[typeshare]
@@ -26,6 +21,7 @@ pub struct RenewResp {
     pub exp: String,
 }
 
+/// Swap a valid token for a new one, the old session is revoked
 pub async fn route(
     State(app): State<Arc<AppState>>,
     Json(RenewReq { token }): Json<RenewReq>,
@@ -34,30 +30,23 @@ pub async fn route(
 
     let token = local::decrypt(&app.key, &token, &ClaimsValidationRules::new(), None, None)
         .kind(ErrorKind::MalformedRequest)?;
-    let claim = token
+    let jti = token
         .payload_claims()
-        .map(|x| x.get_claim("uid").map(|x| x.as_u64()))
-        .flatten()
-        .flatten();
-
-    let user_id = claim
-        .ok_or("Cannot get user id")
+        .and_then(|x| x.get_claim("jti"))
+        .and_then(|x| x.as_str())
+        .ok_or("Cannot get token id")
         .kind(ErrorKind::MalformedRequest)?;
-    let mut claim = Claims::new().kind(ErrorKind::Internal)?;
-    let expiration = Duration::from_secs(60 * 60 * 24 * 7);
-    claim
-        .set_expires_in(&expiration)
-        .kind(ErrorKind::Internal)?;
 
-    // safety:
-    // "uid" is not reserve
-    claim.add_additional("uid", user_id).unwrap();
+    let (old, _) = session::find_active(&app.conn, jti)
+        .await
+        .kind(ErrorKind::Internal)?
+        .ok_or("session is revoked")
+        .kind(ErrorKind::Unauthorized)?;
 
-    // safety:
-    // "exp" must exists
-    let exp = claim.get_claim("exp").unwrap().as_str().unwrap().to_owned();
-
-    let token = local::encrypt(&app.key, &claim, None, None).kind(ErrorKind::Internal)?;
+    let helper::Token { token, exp } = helper::new_token(&app, old.user_id, old.user_agent).await?;
+    session::revoke(&app.conn, old.id)
+        .await
+        .kind(ErrorKind::Internal)?;
 
     Ok(Json(RenewResp { token, exp }))
 }
@@ -1,8 +1,8 @@
 use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
-use entity::{chat, prelude::*};
-use sea_orm::{ActiveValue::Set, EntityTrait};
+use entity::chat;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
@@ -32,17 +32,17 @@ pub async fn route(
         info!(user_id = user_id, mode = ?req.mode, "creating chat");
     }
 
-    let chat_id = Chat::insert(chat::ActiveModel {
+    let chat_id = chat::ActiveModel {
         owner_id: Set(user_id),
         model_id: Set(Some(req.model_id)),
         title: Set(None),
         mode: Set(req.mode.into()),
         ..Default::default()
-    })
-    .exec(&app.conn)
+    }
+    .insert(&app.conn)
     .await
     .kind(ErrorKind::Internal)?
-    .last_insert_id;
+    .id;
 
     Ok(Json(ChatCreateResp { id: chat_id }))
 }
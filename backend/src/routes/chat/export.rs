@@ -0,0 +1,170 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::{HeaderMap, HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use entity::{chat, file, message};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::Deserialize;
+use typeshare::typeshare;
+
+use crate::{
+    AppState,
+    errors::*,
+    middlewares::auth::UserId,
+    utils::{
+        branch::Tree,
+        export::{self, ChatExport, EXPORT_VERSION, ExportFile, ExportMessage},
+    },
+};
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[typeshare]
+#[serde(rename_all = "snake_case")]
+pub enum ChatExportFormat {
+    /// The selected branch, files are referenced by name
+    Markdown,
+    /// The selected branch as a single page with files inlined
+    Html,
+    /// Every branch and file, see `ChatExport`
+    Json,
+}
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ChatExportReq {
+    pub id: i32,
+    pub format: ChatExportFormat,
+}
+
+/// Download a chat as a file
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Json(req): Json<ChatExportReq>,
+) -> Result<Response, AppError> {
+    let chat = chat::Entity::find_by_id(req.id)
+        .filter(chat::Column::OwnerId.eq(user_id))
+        .one(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .ok_or(Json(Error {
+            error: ErrorKind::ResourceNotFound,
+            reason: "".to_owned(),
+        }))?;
+
+    let messages = message::Entity::find()
+        .filter(message::Column::ChatId.eq(chat.id))
+        .order_by_asc(message::Column::Id)
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    let tree: Tree = messages.iter().map(|x| (x.id, x.parent_id)).collect();
+    let by_id: HashMap<_, _> = messages.iter().map(|x| (x.id, &x.inner)).collect();
+    let branch: Vec<_> = tree
+        .selected(&chat)
+        .into_iter()
+        .filter_map(|id| by_id.get(&id).copied())
+        .collect();
+
+    let title = chat
+        .title
+        .clone()
+        .filter(|x| !x.trim().is_empty())
+        .unwrap_or_else(|| format!("Chat {}", chat.id));
+    let (body, content_type, extension) = match req.format {
+        ChatExportFormat::Markdown => (
+            export::markdown(&title, branch),
+            "text/markdown; charset=utf-8",
+            "md",
+        ),
+        ChatExportFormat::Html => {
+            let files = load_files(&app, user_id, export::file_ids(branch.iter().copied())).await?;
+            (
+                export::html(&title, branch, &files),
+                "text/html; charset=utf-8",
+                "html",
+            )
+        }
+        ChatExportFormat::Json => {
+            let files =
+                load_files(&app, user_id, export::file_ids(by_id.values().copied())).await?;
+            let mut files: Vec<_> = files
+                .into_iter()
+                .map(|(id, file)| ExportFile {
+                    id,
+                    mime_type: file.mime_type,
+                    data: STANDARD.encode(file.data),
+                })
+                .collect();
+            files.sort_by_key(|x| x.id);
+
+            let export = ChatExport {
+                version: EXPORT_VERSION,
+                title: chat.title,
+                mode: chat.mode.into(),
+                leaf_id: tree.leaf(chat.leaf_id),
+                created_at: chat.created_at,
+                updated_at: chat.updated_at,
+                messages: messages
+                    .into_iter()
+                    .map(|x| ExportMessage {
+                        id: x.id,
+                        parent_id: x.parent_id,
+                        inner: x.inner,
+                        token_count: x.token_count,
+                        price: x.price,
+                        created_at: x.created_at,
+                    })
+                    .collect(),
+                files,
+            };
+            (
+                serde_json::to_string_pretty(&export).kind(ErrorKind::Internal)?,
+                "application/json",
+                "json",
+            )
+        }
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    let filename = format!("attachment; filename=\"chat-{}.{}\"", chat.id, extension);
+    if let Ok(value) = HeaderValue::from_str(&filename) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+
+    Ok((headers, body).into_response())
+}
+
+/// Content of the user's files among `ids`, missing ones are skipped
+async fn load_files(
+    app: &AppState,
+    user_id: i32,
+    ids: Vec<i32>,
+) -> Result<HashMap<i32, export::File>, AppError> {
+    let models = file::Entity::find()
+        .filter(file::Column::Id.is_in(ids))
+        .filter(file::Column::OwnerId.eq(user_id))
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    let mut files = HashMap::new();
+    for model in models {
+        let Some(data) = app.blob.get_vectored(model.id).await else {
+            continue;
+        };
+        let mime_type = model
+            .mime_type
+            .or_else(|| infer::get(&data).map(|x| x.mime_type().to_owned()))
+            .unwrap_or_else(|| "application/octet-stream".to_owned());
+        files.insert(model.id, export::File { mime_type, data });
+    }
+    Ok(files)
+}
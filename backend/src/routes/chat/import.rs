@@ -0,0 +1,61 @@
+use std::{
+    io::{BufReader, Seek},
+    sync::Arc,
+};
+
+use axum::{
+    Extension, Json,
+    extract::{Multipart, State},
+};
+
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    AppState,
+    errors::*,
+    importer::{self, ImportSummary},
+    middlewares::auth::UserId,
+};
+
+const FILE_FIELD: &str = "file";
+
+/// Import chats from a ChatGPT, Claude or OpenWebUI export, see [`importer`]
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    mut multipart: Multipart,
+) -> JsonResult<ImportSummary> {
+    let mut field = multipart
+        .next_field()
+        .await
+        .kind(ErrorKind::MalformedRequest)?
+        .filter(|x| x.name() == Some(FILE_FIELD))
+        .ok_or(Json(Error {
+            error: ErrorKind::MalformedRequest,
+            reason: "missing file field".to_owned(),
+        }))?;
+
+    // spooled to disk instead of memory, exports with images get large
+    let file = tempfile::tempfile().kind(ErrorKind::Internal)?;
+    let mut file = tokio::fs::File::from_std(file);
+    while let Some(chunk) = field.chunk().await.kind(ErrorKind::MalformedRequest)? {
+        file.write_all(&chunk).await.kind(ErrorKind::Internal)?;
+    }
+    file.flush().await.kind(ErrorKind::Internal)?;
+    let mut file = file.into_std().await;
+
+    let (conversations, missing) = tokio::task::spawn_blocking(move || {
+        file.rewind()?;
+        importer::parse(BufReader::new(file))
+    })
+    .await
+    .kind(ErrorKind::Internal)?
+    .kind(ErrorKind::MalformedRequest)?;
+
+    let mut summary = importer::save(&app.conn, &app.blob, user_id, conversations)
+        .await
+        .kind(ErrorKind::Internal)?;
+    summary.missing_files = missing;
+
+    Ok(Json(summary))
+}
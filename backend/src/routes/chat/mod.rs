@@ -1,23 +1,35 @@
 mod create;
 mod delete;
+mod export;
 mod halt;
+mod import;
 mod paginate;
 mod read;
+mod search;
 mod sse;
 mod write;
 
 use std::sync::Arc;
 
-use axum::{Router, routing::post};
+use axum::{Router, extract::DefaultBodyLimit, routing::post};
 
 use crate::AppState;
 
+/// Exports with images get large, ChatGPT keeps every image ever uploaded or generated
+const MAX_IMPORT_SIZE: usize = 1024 * 1024 * 512; // 512MB
+
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/sse", post(sse::route))
         .route("/delete", post(delete::route))
         .route("/paginate", post(paginate::route))
         .route("/read", post(read::route))
+        .route("/search", post(search::route))
+        .route("/export", post(export::route))
+        .route(
+            "/import",
+            post(import::route).layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)),
+        )
         .route("/create", post(create::route))
         .route("/halt", post(halt::route))
         .route("/write", post(write::route))
@@ -25,6 +25,8 @@ pub struct ChatPaginateReqLimit {
     pub id: Option<i32>,
     pub order: ChatPaginateReqOrder,
     pub limit: Option<u32>,
+    /// Default to id
+    pub sort: Option<ChatPaginateReqSort>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -46,6 +48,16 @@ pub enum ChatPaginateReqOrder {
     Lt,
 }
 
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+#[typeshare]
+#[serde(rename_all = "snake_case")]
+pub enum ChatPaginateReqSort {
+    #[default]
+    Id,
+    /// last activity, `id` is still the cursor
+    Activity,
+}
+
 #[derive(Debug, Serialize)]
 #[typeshare]
 pub struct ChatPaginateResp {
@@ -60,6 +72,12 @@ pub struct ChatPaginateRespList {
     pub model_id: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    /// unix timestamp in seconds
+    #[typeshare(serialized_as = "number")]
+    pub created_at: i64,
+    /// unix timestamp in seconds of the last activity
+    #[typeshare(serialized_as = "number")]
+    pub updated_at: i64,
 }
 
 pub async fn route(
@@ -77,17 +95,51 @@ pub async fn route(
                         .map(|x| x.min(MAX_PAGINATE_LIMIT))
                         .unwrap_or(MAX_PAGINATE_LIMIT) as u64,
                 );
-            let q = match (limit.order, limit.id) {
-                (ChatPaginateReqOrder::Gt, None) => q.order_by_asc(chat::Column::Id),
-                (ChatPaginateReqOrder::Gt, Some(id)) => q
-                    .filter(chat::Column::Id.gt(id))
-                    .order_by_asc(chat::Column::Id),
-                (ChatPaginateReqOrder::Lt, None) => q.order_by_desc(chat::Column::Id),
-                (ChatPaginateReqOrder::Lt, Some(id)) => q
-                    .filter(chat::Column::Id.lt(id))
-                    .order_by_desc(chat::Column::Id),
-            };
-            q
+            match limit.sort.unwrap_or_default() {
+                ChatPaginateReqSort::Id => match (limit.order, limit.id) {
+                    (ChatPaginateReqOrder::Gt, None) => q.order_by_asc(chat::Column::Id),
+                    (ChatPaginateReqOrder::Gt, Some(id)) => q
+                        .filter(chat::Column::Id.gt(id))
+                        .order_by_asc(chat::Column::Id),
+                    (ChatPaginateReqOrder::Lt, None) => q.order_by_desc(chat::Column::Id),
+                    (ChatPaginateReqOrder::Lt, Some(id)) => q
+                        .filter(chat::Column::Id.lt(id))
+                        .order_by_desc(chat::Column::Id),
+                },
+                ChatPaginateReqSort::Activity => {
+                    let cursor = match limit.id {
+                        Some(id) => Chat::find_by_id(id)
+                            .filter(chat::Column::OwnerId.eq(user_id))
+                            .one(&app.conn)
+                            .await
+                            .kind(ErrorKind::Internal)?
+                            .map(|x| (x.updated_at, id)),
+                        None => None,
+                    };
+                    // order by (updated_at, id), so chats active in the same second stay apart
+                    let key = Expr::tuple([
+                        Expr::col(chat::Column::UpdatedAt).into(),
+                        Expr::col(chat::Column::Id).into(),
+                    ]);
+                    let q = match (&limit.order, cursor) {
+                        (ChatPaginateReqOrder::Gt, Some((updated_at, id))) => {
+                            q.filter(key.gt(Expr::tuple([updated_at.into(), id.into()])))
+                        }
+                        (ChatPaginateReqOrder::Lt, Some((updated_at, id))) => {
+                            q.filter(key.lt(Expr::tuple([updated_at.into(), id.into()])))
+                        }
+                        (_, None) => q,
+                    };
+                    match limit.order {
+                        ChatPaginateReqOrder::Gt => q
+                            .order_by_asc(chat::Column::UpdatedAt)
+                            .order_by_asc(chat::Column::Id),
+                        ChatPaginateReqOrder::Lt => q
+                            .order_by_desc(chat::Column::UpdatedAt)
+                            .order_by_desc(chat::Column::Id),
+                    }
+                }
+            }
         }
         ChatPaginateReq::Range(range) => Chat::find()
             .filter(chat::Column::OwnerId.eq(user_id))
@@ -105,6 +157,8 @@ pub async fn route(
             id: x.id,
             model_id: x.model_id,
             title: x.title,
+            created_at: x.created_at,
+            updated_at: x.updated_at,
         })
         .collect();
     Ok(Json(ChatPaginateResp { list }))
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use sea_orm::{DbBackend, FromQueryResult, Statement};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId};
+
+const DEFAULT_LIMIT: u32 = 20;
+const MAX_LIMIT: u32 = 100;
+
+/// Messages and titles in one list, best match first; bm25 is lower for better matches
+const SEARCH: &str = "
+SELECT * FROM (
+    SELECT chat.id AS chat_id, message.id AS message_id, chat.title AS title,
+        snippet(message_fts, 0, '<mark>', '</mark>', '…', 16) AS snippet,
+        bm25(message_fts) AS rank
+    FROM message_fts
+    JOIN message ON message.id = message_fts.rowid
+    JOIN chat ON chat.id = message.chat_id
+    WHERE message_fts MATCH $1 AND chat.owner_id = $2
+    UNION ALL
+    SELECT chat.id, NULL, chat.title,
+        snippet(chat_fts, 0, '<mark>', '</mark>', '…', 16),
+        bm25(chat_fts)
+    FROM chat_fts
+    JOIN chat ON chat.id = chat_fts.rowid
+    WHERE chat_fts MATCH $1 AND chat.owner_id = $2
+)
+ORDER BY rank
+LIMIT $3";
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ChatSearchReq {
+    /// Words to look for, the last one also matches as a prefix
+    pub query: String,
+    /// Default to 20, at most 100
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ChatSearchResp {
+    pub list: Vec<ChatSearchHit>,
+}
+
+#[derive(Debug, Serialize, FromQueryResult)]
+#[typeshare]
+pub struct ChatSearchHit {
+    pub chat_id: i32,
+    /// null when the title matched
+    pub message_id: Option<i32>,
+    pub title: Option<String>,
+    /// Text around the match, matched words are wrapped in `<mark>` and `</mark>`
+    ///
+    /// The rest is raw text and has to be escaped before rendering as HTML.
+    pub snippet: String,
+}
+
+/// Search user messages, replies and titles of the caller's chats
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Json(req): Json<ChatSearchReq>,
+) -> JsonResult<ChatSearchResp> {
+    let Some(query) = fts_query(&req.query) else {
+        return Ok(Json(ChatSearchResp { list: Vec::new() }));
+    };
+    let limit = req.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    let list = ChatSearchHit::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        SEARCH,
+        [query.into(), user_id.into(), limit.into()],
+    ))
+    .all(&app.conn)
+    .await
+    .kind(ErrorKind::Internal)?;
+
+    Ok(Json(ChatSearchResp { list }))
+}
+
+/// Quote every word so user input is never parsed as FTS5 syntax
+fn fts_query(input: &str) -> Option<String> {
+    let words: Vec<_> = input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(words.join(" ") + "*")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_query() {
+        assert_eq!(fts_query("  "), None);
+        assert_eq!(fts_query("rust"), Some("\"rust\"*".to_owned()));
+        assert_eq!(
+            fts_query("say \"hi\" OR"),
+            Some("\"say\" \"\"\"hi\"\"\" \"OR\"*".to_owned())
+        );
+    }
+}
@@ -20,6 +20,7 @@ use crate::{
     chat::{Cursor, Token},
     errors::*,
     middlewares::auth::UserId,
+    utils::branch::Tree,
 };
 
 #[derive(Debug, Deserialize)]
@@ -73,6 +74,8 @@ pub struct SseReq {
 /// - `ToolResult(SseRespToolResult)` / `DeepStepToolResult(SseRespToolResult)`: tool outputs.
 /// - `Start(SseStart)`: indicates the beginning of processing for a new assistant message.
 /// - `Title(String)`: an updated or generated title for the chat.
+/// - `Model(String)`: id of the model answering, sent before its answer starts and again when a
+///   fallback model takes over.
 /// - `Error(String)`: an error message to surface to the client.
 ///
 /// Important: the client should treat text-bearing variants (`Token`, `Reasoning`,
@@ -90,6 +93,7 @@ pub enum SseResp {
     ToolResult(SseRespToolResult),
     Complete(SseRespMessageComplete),
     Title(String),
+    Model(String),
     Error(String),
     Start(SseStart),
     DeepPlan(String),
@@ -162,9 +166,13 @@ pub async fn route(
         }));
     }
 
-    // last non-empty message
+    // last non-empty message of the selected branch
+    let branch = Tree::load(&app.conn, req.id)
+        .await
+        .kind(ErrorKind::Internal)?
+        .selected(&res);
     let last_msg = Message::find()
-        .filter(entity::message::Column::ChatId.eq(req.id))
+        .filter(entity::message::Column::Id.is_in(branch))
         .order_by_desc(entity::message::Column::Id)
         .limit(2)
         .all(&app.conn)
@@ -212,6 +220,7 @@ pub async fn route(
             Token::ToolResult(content) => SseResp::ToolResult(SseRespToolResult { content }),
             Token::Error(content) => SseResp::Error(content),
             Token::Title(title) => SseResp::Title(title),
+            Token::Model(model_id) => SseResp::Model(model_id),
             Token::Start { id, user_msg_id } => SseResp::Start(SseStart {
                 id,
                 user_msg_id,
@@ -2,8 +2,7 @@ use std::sync::Arc;
 
 use axum::Json;
 use axum::extract::{Extension, Multipart, State};
-use entity::file::Entity as File;
-use sea_orm::{ActiveValue::Set, EntityTrait};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set};
 use serde::Serialize;
 use typeshare::typeshare;
 
@@ -75,16 +74,16 @@ pub async fn route(
 
     let mime_type = content_field.content_type().map(|c| c.to_string());
 
-    let file_id = File::insert(entity::file::ActiveModel {
+    let file_id = entity::file::ActiveModel {
         chat_id: Set(Some(chat_id)),
         owner_id: Set(Some(user_id)),
         mime_type: Set(mime_type),
         ..Default::default()
-    })
-    .exec(&app.conn)
+    }
+    .insert(&app.conn)
     .await
     .kind(ErrorKind::Internal)?
-    .last_insert_id;
+    .id;
 
     app.blob
         .insert_with_error(file_id, size.max(0) as usize, content_field)
@@ -1,16 +1,18 @@
 use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
+use entity::prelude::*;
 use protocol::{FileMetadata, MessageInner};
-use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
+use super::helper;
 use crate::{
     AppState,
-    errors::{ErrorKind, JsonResult, WithKind},
+    errors::{Error, ErrorKind, JsonResult, WithKind},
     middlewares::auth::UserId,
-    utils::chat::ChatMode,
+    utils::{branch::Tree, chat::ChatMode},
 };
 
 #[derive(Debug, Deserialize)]
@@ -20,6 +22,15 @@ pub struct MessageCreateReqFile {
     pub name: String,
 }
 
+impl From<MessageCreateReqFile> for FileMetadata {
+    fn from(f: MessageCreateReqFile) -> Self {
+        FileMetadata {
+            name: f.name,
+            id: f.id,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[typeshare]
 pub struct MessageCreateReq {
@@ -37,22 +48,34 @@ pub struct MessageCreateResp {
     pub user_id: i32,
 }
 
+/// Append a user message to the selected branch and reply to it
 pub async fn route(
     State(app): State<Arc<AppState>>,
     Extension(UserId(user_id)): Extension<UserId>,
     Json(req): Json<MessageCreateReq>,
 ) -> JsonResult<MessageCreateResp> {
-    let files = req
-        .files
-        .into_iter()
-        .map(|f| FileMetadata {
-            name: f.name,
-            id: f.id,
-        })
-        .collect::<Vec<_>>();
+    helper::check_model(&app, user_id, req.model_id).await?;
+
+    let chat = Chat::find_by_id(req.chat_id)
+        .one(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .filter(|chat| chat.owner_id == user_id)
+        .ok_or_else(|| {
+            Json(Error {
+                error: ErrorKind::ResourceNotFound,
+                reason: "chat not found".to_owned(),
+            })
+        })?;
+    let tree = Tree::load(&app.conn, chat.id)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    let files = req.files.into_iter().map(Into::into).collect::<Vec<_>>();
 
     let user_msg = entity::message::ActiveModel {
         chat_id: Set(req.chat_id),
+        parent_id: Set(tree.leaf(chat.leaf_id)),
         inner: Set(MessageInner::User {
             text: req.text,
             files,
@@ -63,27 +86,15 @@ pub async fn route(
     .await
     .raw_kind(ErrorKind::Internal)?;
 
-    let mut completion_ctx = app
-        .processor
-        .get_completion_context(user_id, req.chat_id, req.model_id)
-        .await
-        .kind(ErrorKind::ResourceNotFound)?;
-
-    let id = completion_ctx.get_message_id();
-
-    let closure = async move {
-        completion_ctx.set_mode(req.mode.into());
-
-        app.processor.clone().process(completion_ctx).await?;
-
-        Ok::<(), anyhow::Error>(())
-    };
-
-    tokio::spawn(async move {
-        if let Err(e) = closure.await {
-            log::error!("Failed to process message: {:?}", e);
-        }
-    });
+    let id = helper::spawn_completion(
+        app,
+        user_id,
+        req.chat_id,
+        req.model_id,
+        user_msg.id,
+        req.mode,
+    )
+    .await?;
 
     Ok(Json(MessageCreateResp {
         user_id: user_msg.id,
@@ -1,12 +1,18 @@
 use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
-use entity::{chat, message};
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use entity::message;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, TransactionTrait};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
-use crate::{AppState, errors::*, middlewares::auth::UserId};
+use super::helper;
+use crate::{
+    AppState,
+    errors::*,
+    middlewares::auth::UserId,
+    utils::branch::{self, Tree},
+};
 
 #[derive(Debug, Deserialize)]
 #[typeshare]
@@ -20,36 +26,42 @@ pub struct Resp {
     deleted: bool,
 }
 
+/// Delete a message and every reply after it, other branches are kept
 pub async fn route(
     State(app): State<Arc<AppState>>,
     Extension(UserId(user_id)): Extension<UserId>,
     Json(req): Json<MessageDeleteReq>,
 ) -> JsonResult<Resp> {
-    let (message, chat) = message::Entity::find_by_id(req.id)
-        .find_also_related(chat::Entity)
-        .one(&app.conn)
-        .await
-        .raw_kind(ErrorKind::Internal)?
-        .ok_or_else(|| {
-            Json(Error {
-                error: ErrorKind::ResourceNotFound,
-                reason: "message not found".to_owned(),
-            })
-        })?;
-
-    if chat.map(|m| m.owner_id) != Some(user_id) {
-        return Err(Json(Error {
-            error: ErrorKind::ResourceNotFound,
-            reason: "message not found".to_owned(),
-        }));
-    }
+    let (message, chat) = helper::owned_message(&app, user_id, req.id).await?;
+
+    let txn = app.conn.begin().await.kind(ErrorKind::Internal)?;
+
+    let tree = Tree::load(&txn, chat.id).await.kind(ErrorKind::Internal)?;
+    let subtree = tree.subtree(message.id);
 
     let result = message::Entity::delete_many()
-        .filter(message::Column::Id.gte(req.id))
-        .filter(message::Column::ChatId.eq(message.chat_id))
-        .exec(&app.conn)
+        .filter(message::Column::Id.is_in(subtree.iter().copied()))
+        .filter(message::Column::ChatId.eq(chat.id))
+        .exec(&txn)
         .await
-        .raw_kind(ErrorKind::Internal)?;
+        .kind(ErrorKind::Internal)?;
+
+    // fall back to a sibling, or the parent if it was the only one
+    let selected = tree.leaf(chat.leaf_id);
+    if selected.is_none_or(|leaf| subtree.contains(&leaf)) {
+        let leaf = tree
+            .siblings(message.id)
+            .iter()
+            .rev()
+            .find(|id| **id != message.id)
+            .map(|id| tree.newest_leaf(*id))
+            .or(message.parent_id);
+        branch::set_leaf(&txn, chat.id, leaf)
+            .await
+            .kind(ErrorKind::Internal)?;
+    }
+
+    txn.commit().await.kind(ErrorKind::Internal)?;
 
     Ok(Json(Resp {
         deleted: result.rows_affected > 0,
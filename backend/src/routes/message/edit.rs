@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use protocol::MessageInner;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+use serde::Deserialize;
+use typeshare::typeshare;
+
+use super::{
+    create::{MessageCreateReqFile, MessageCreateResp},
+    helper,
+};
+use crate::{AppState, errors::*, middlewares::auth::UserId, utils::chat::ChatMode};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct MessageEditReq {
+    /// User message to edit
+    pub id: i32,
+    pub model_id: i32,
+    pub mode: ChatMode,
+    pub text: String,
+    pub files: Vec<MessageCreateReqFile>,
+}
+
+/// Add the edited user message as a sibling and reply to it, the original branch is kept
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Json(req): Json<MessageEditReq>,
+) -> JsonResult<MessageCreateResp> {
+    let (message, chat) = helper::owned_message(&app, user_id, req.id).await?;
+    if !matches!(message.inner, MessageInner::User { .. }) {
+        return Err(Json(Error {
+            error: ErrorKind::MalformedRequest,
+            reason: "only user messages can be edited".to_owned(),
+        }));
+    }
+
+    helper::check_model(&app, user_id, req.model_id).await?;
+
+    let files = req.files.into_iter().map(Into::into).collect::<Vec<_>>();
+
+    let user_msg = entity::message::ActiveModel {
+        chat_id: Set(chat.id),
+        parent_id: Set(message.parent_id),
+        inner: Set(MessageInner::User {
+            text: req.text,
+            files,
+        }),
+        ..Default::default()
+    }
+    .insert(&app.conn)
+    .await
+    .kind(ErrorKind::Internal)?;
+
+    let id = helper::spawn_completion(app, user_id, chat.id, req.model_id, user_msg.id, req.mode)
+        .await?;
+
+    Ok(Json(MessageCreateResp {
+        user_id: user_msg.id,
+        id,
+    }))
+}
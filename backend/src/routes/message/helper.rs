@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use axum::Json;
+use entity::{chat, message, prelude::*};
+use sea_orm::EntityTrait;
+
+use crate::{
+    AppState,
+    errors::*,
+    utils::{budget, chat::ChatMode, model::find_accessible},
+};
+
+/// Message and its chat, if the chat belongs to the user
+pub async fn owned_message(
+    app: &AppState,
+    user_id: i32,
+    id: i32,
+) -> Result<(message::Model, chat::Model), AppError> {
+    let res = Message::find_by_id(id)
+        .find_also_related(Chat)
+        .one(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+    match res {
+        Some((message, Some(chat))) if chat.owner_id == user_id => Ok((message, chat)),
+        _ => Err(Json(Error {
+            error: ErrorKind::ResourceNotFound,
+            reason: "message not found".to_owned(),
+        })),
+    }
+}
+
+/// Reject users over budget and models they cannot access
+pub async fn check_model(app: &AppState, user_id: i32, model_id: i32) -> Result<(), AppError> {
+    if let Some(reason) = budget::exceeded(&app.conn, user_id)
+        .await
+        .kind(ErrorKind::Internal)?
+    {
+        return Err(Json(Error {
+            error: ErrorKind::QuotaExceeded,
+            reason,
+        }));
+    }
+
+    if find_accessible(&app.conn, user_id, model_id)
+        .await
+        .kind(ErrorKind::Internal)?
+        .is_none()
+    {
+        return Err(Json(Error {
+            error: ErrorKind::ResourceNotFound,
+            reason: "model not found".to_owned(),
+        }));
+    }
+
+    Ok(())
+}
+
+/// Reply to `parent_id` in background, returns id of the reply
+pub async fn spawn_completion(
+    app: Arc<AppState>,
+    user_id: i32,
+    chat_id: i32,
+    model_id: i32,
+    parent_id: i32,
+    mode: ChatMode,
+) -> Result<i32, AppError> {
+    let mut completion_ctx = app
+        .processor
+        .get_completion_context(user_id, chat_id, model_id, parent_id)
+        .await
+        .kind(ErrorKind::ResourceNotFound)?;
+
+    let id = completion_ctx.get_message_id();
+
+    let closure = async move {
+        completion_ctx.set_mode(mode.into());
+
+        app.processor.clone().process(completion_ctx).await?;
+
+        Ok::<(), anyhow::Error>(())
+    };
+
+    tokio::spawn(async move {
+        if let Err(e) = closure.await {
+            log::error!("Failed to process message: {:?}", e);
+        }
+    });
+
+    Ok(id)
+}
@@ -1,6 +1,10 @@
 mod create;
 mod delete;
+mod edit;
+mod helper;
 mod paginate;
+mod regenerate;
+mod select;
 
 use std::sync::Arc;
 
@@ -12,5 +16,8 @@ pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/create", post(create::route))
         .route("/delete", post(delete::route))
+        .route("/edit", post(edit::route))
         .route("/paginate", post(paginate::route))
+        .route("/regenerate", post(regenerate::route))
+        .route("/select", post(select::route))
 }
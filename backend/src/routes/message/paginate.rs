@@ -1,15 +1,23 @@
 use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
-use entity::{message, prelude::*};
+use entity::{chat, message, prelude::*};
 use migration::ExprTrait;
-use protocol::{AssistantChunk, MessageInner};
+use protocol::{AssistantChunk, MessageInner, ModelConfig, TokenizerFamily};
 use sea_orm::{QueryOrder, QuerySelect, prelude::*};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
-use crate::{AppState, config::MAX_PAGINATE_LIMIT, errors::*, middlewares::auth::UserId};
+use crate::{
+    AppState,
+    chat::TokenCounter,
+    config::MAX_PAGINATE_LIMIT,
+    errors::*,
+    middlewares::auth::UserId,
+    utils::{branch::Tree, model::ModelChecker},
+};
 
+/// Only messages of the selected branch are returned, see `/api/message/select`
 #[derive(Debug, Deserialize)]
 #[typeshare]
 #[serde(tag = "t", content = "c", rename_all = "snake_case")]
@@ -53,6 +61,10 @@ pub enum MessagePaginateReqOrder {
 #[typeshare]
 pub struct MessagePaginateResp {
     pub list: Vec<MessagePaginateRespList>,
+    /// Estimated tokens of the selected branch if sent to its model again
+    ///
+    /// Only counted on the first page, a `limit` request without `id`.
+    pub chat_token_count: Option<i32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -62,6 +74,15 @@ pub struct MessagePaginateRespList {
     pub token_count: i32,
     pub price: f32,
     pub inner: MessageInner,
+    pub parent_id: Option<i32>,
+    /// Alternatives of this message including itself, oldest first
+    pub siblings: Vec<i32>,
+    /// unix timestamp in seconds
+    #[typeshare(serialized_as = "number")]
+    pub created_at: i64,
+    /// unix timestamp in seconds
+    #[typeshare(serialized_as = "number")]
+    pub updated_at: i64,
 }
 
 pub async fn route(
@@ -69,18 +90,18 @@ pub async fn route(
     Extension(UserId(user_id)): Extension<UserId>,
     Json(req): Json<MessagePaginateReq>,
 ) -> JsonResult<MessagePaginateResp> {
-    let q = match req {
+    let (q, chat, first_page) = match req {
         MessagePaginateReq::Limit(limit) => {
             let res = Chat::find_by_id(limit.chat_id)
                 .one(&app.conn)
                 .await
                 .kind(ErrorKind::Internal)?;
-            if res.is_none_or(|x| x.owner_id != user_id) {
+            let Some(chat) = res.filter(|x| x.owner_id == user_id) else {
                 return Err(Json(Error {
                     error: ErrorKind::ResourceNotFound,
                     reason: "".to_owned(),
                 }));
-            }
+            };
 
             let q = Message::find()
                 .filter(message::Column::ChatId.eq(limit.chat_id))
@@ -95,29 +116,44 @@ pub async fn route(
                     .filter(message::Column::Id.lt(id))
                     .order_by_desc(message::Column::Id),
             };
-            q
+            (q, chat, limit.id.is_none())
         }
         MessagePaginateReq::Range(range) => {
             let res = Chat::find_by_id(range.chat_id)
                 .one(&app.conn)
                 .await
                 .kind(ErrorKind::Internal)?;
-            if res.is_none_or(|x| x.owner_id != user_id) {
+            let Some(chat) = res.filter(|x| x.owner_id == user_id) else {
                 return Err(Json(Error {
                     error: ErrorKind::ResourceNotFound,
                     reason: "".to_owned(),
                 }));
-            }
+            };
 
             let q = Message::find()
                 .filter(message::Column::ChatId.eq(range.chat_id))
                 .limit(MAX_PAGINATE_LIMIT as u64)
                 .filter(message::Column::Id.gt(range.lower).lt(range.upper));
-            q
+            (q, chat, false)
         }
     };
 
-    let msgs = q.all(&app.conn).await.kind(ErrorKind::Internal)?;
+    let tree = Tree::load(&app.conn, chat.id)
+        .await
+        .kind(ErrorKind::Internal)?;
+    let branch = tree.selected(&chat);
+
+    // tokenizing the whole branch is slow, later pages reuse the first count
+    let chat_token_count = match first_page {
+        true => Some(chat_token_count(&app, &branch, &chat).await?),
+        false => None,
+    };
+
+    let msgs = q
+        .filter(message::Column::Id.is_in(branch))
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
 
     let list = msgs
         .into_iter()
@@ -138,9 +174,47 @@ pub async fn route(
                 token_count: msg.token_count,
                 price: msg.price,
                 inner,
+                parent_id: msg.parent_id,
+                siblings: tree.siblings(msg.id).to_vec(),
+                created_at: msg.created_at,
+                updated_at: msg.updated_at,
             })
         })
         .collect::<Vec<_>>();
 
-    Ok(Json(MessagePaginateResp { list }))
+    Ok(Json(MessagePaginateResp {
+        list,
+        chat_token_count,
+    }))
+}
+
+async fn chat_token_count(
+    app: &AppState,
+    branch: &[i32],
+    chat: &chat::Model,
+) -> Result<i32, AppError> {
+    let config = match chat.model_id {
+        Some(model_id) => Model::find_by_id(model_id)
+            .one(&app.conn)
+            .await
+            .kind(ErrorKind::Internal)?
+            .and_then(|m| <ModelConfig as ModelChecker>::from_toml(&m.config).ok()),
+        None => None,
+    };
+    let counter = config
+        .as_ref()
+        .map(TokenCounter::for_model)
+        .unwrap_or(TokenCounter::new(TokenizerFamily::Cl100k));
+
+    let inners: Vec<MessageInner> = Message::find()
+        .filter(message::Column::Id.is_in(branch.iter().copied()))
+        .select_only()
+        .column(message::Column::Inner)
+        .into_tuple()
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    let count: usize = inners.iter().map(|inner| counter.count_inner(inner)).sum();
+    Ok(count as i32)
 }
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use protocol::MessageInner;
+use serde::Deserialize;
+use typeshare::typeshare;
+
+use super::{create::MessageCreateResp, helper};
+use crate::{AppState, errors::*, middlewares::auth::UserId, utils::chat::ChatMode};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct MessageRegenerateReq {
+    /// Assistant message to regenerate
+    pub id: i32,
+    /// Default to the model of the chat
+    pub model_id: Option<i32>,
+    /// Default to the mode of the chat
+    pub mode: Option<ChatMode>,
+}
+
+/// Reply again to the user message before an assistant message, the old reply is kept as an
+/// alternative
+///
+/// History is the branch up to that user message, which isn't inserted again.
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Json(req): Json<MessageRegenerateReq>,
+) -> JsonResult<MessageCreateResp> {
+    let (message, chat) = helper::owned_message(&app, user_id, req.id).await?;
+    let (MessageInner::Assistant(_), Some(parent_id)) = (&message.inner, message.parent_id) else {
+        return Err(Json(Error {
+            error: ErrorKind::MalformedRequest,
+            reason: "only replies can be regenerated".to_owned(),
+        }));
+    };
+    let Some(model_id) = req.model_id.or(chat.model_id) else {
+        return Err(Json(Error {
+            error: ErrorKind::MalformedRequest,
+            reason: "select a model first".to_owned(),
+        }));
+    };
+
+    helper::check_model(&app, user_id, model_id).await?;
+
+    let mode = req.mode.unwrap_or(chat.mode.into());
+    let id = helper::spawn_completion(app, user_id, chat.id, model_id, parent_id, mode).await?;
+
+    Ok(Json(MessageCreateResp {
+        user_id: parent_id,
+        id,
+    }))
+}
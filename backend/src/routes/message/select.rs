@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::helper;
+use crate::{
+    AppState,
+    errors::*,
+    middlewares::auth::UserId,
+    utils::branch::{self, Tree},
+};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct MessageSelectReq {
+    /// Message to show, usually a sibling of a message in the current branch
+    pub id: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct MessageSelectResp {
+    /// Last message of the newly selected branch
+    pub leaf_id: i32,
+}
+
+/// Switch to the branch going through a message, following its newest replies
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Json(req): Json<MessageSelectReq>,
+) -> JsonResult<MessageSelectResp> {
+    let (_, chat) = helper::owned_message(&app, user_id, req.id).await?;
+    if app.processor.is_streaming(chat.id) {
+        return Err(Json(Error {
+            error: ErrorKind::MalformedRequest,
+            reason: "cannot switch branches while a reply is being generated".to_owned(),
+        }));
+    }
+
+    let tree = Tree::load(&app.conn, chat.id)
+        .await
+        .kind(ErrorKind::Internal)?;
+    let leaf_id = tree.newest_leaf(req.id);
+    branch::set_leaf(&app.conn, chat.id, Some(leaf_id))
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    Ok(Json(MessageSelectResp { leaf_id }))
+}
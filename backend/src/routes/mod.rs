@@ -1,7 +1,11 @@
+pub mod api_key;
 pub mod auth;
 pub mod chat;
 pub mod file;
 pub mod message;
 pub mod model;
+pub mod openai;
 pub mod provider;
+pub mod session;
+pub mod usage;
 pub mod user;
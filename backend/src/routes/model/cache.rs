@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ModelCacheReq {}
+
+/// State of the OpenRouter model metadata cache
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ModelCacheResp {
+    /// RFC 3339, omitted if never refreshed
+    pub refreshed_at: Option<String>,
+    pub count: i32,
+}
+
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(_)): Extension<UserId>,
+    Json(_): Json<ModelCacheReq>,
+) -> JsonResult<ModelCacheResp> {
+    let status = app.processor.model_cache_status();
+
+    let refreshed_at = status
+        .refreshed_at
+        .map(|x| x.format(&Rfc3339))
+        .transpose()
+        .kind(ErrorKind::Internal)?;
+
+    Ok(Json(ModelCacheResp {
+        refreshed_at,
+        count: status.count as i32,
+    }))
+}
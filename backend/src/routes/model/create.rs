@@ -2,19 +2,19 @@ use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
 use entity::{model, prelude::*};
-use protocol::{ModelConfig, OcrEngine};
+use protocol::{ModelAccess, ModelConfig, OcrEngine};
 use sea_orm::{ActiveValue::Set, EntityTrait};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
-use crate::{
-    AppState, errors::*, middlewares::auth::UserId, openrouter, utils::model::ModelChecker,
-};
+use crate::{AppState, errors::*, middlewares::auth::UserId, utils::model::ModelChecker};
 
 #[derive(Debug, Deserialize)]
 #[typeshare]
 pub struct ModelCreateReq {
     pub config: String,
+    /// Default to everyone
+    pub access: Option<ModelAccess>,
 }
 
 #[derive(Debug, Serialize)]
@@ -37,11 +37,11 @@ pub async fn route(
 
     match <ModelConfig as ModelChecker>::from_toml(&raw_config) {
         Ok(config) => {
-            let model: openrouter::Model = config.clone().into();
-            let caps = app.processor.get_capability(&model);
+            let caps = app.processor.get_capability(&config).await;
 
             let id = Model::insert(model::ActiveModel {
                 config: Set(raw_config),
+                access: Set(req.access.unwrap_or_default()),
                 ..Default::default()
             })
             .exec(&app.conn)
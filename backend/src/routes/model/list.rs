@@ -1,14 +1,17 @@
 use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
-use entity::model;
+use entity::{model, prelude::*};
 use protocol::{ModelConfig, OcrEngine};
 use sea_orm::EntityTrait;
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
 use crate::{
-    AppState, errors::*, middlewares::auth::UserId, openrouter, utils::model::ModelChecker,
+    AppState,
+    errors::*,
+    middlewares::auth::UserId,
+    utils::model::{ModelChecker, can_access},
 };
 
 #[derive(Debug, Serialize)]
@@ -34,33 +37,35 @@ pub struct ModelListReq {}
 
 pub async fn route(
     State(app): State<Arc<AppState>>,
-    Extension(UserId(_)): Extension<UserId>,
+    Extension(UserId(user_id)): Extension<UserId>,
     Json(_): Json<ModelListReq>,
 ) -> JsonResult<ModelListResp> {
+    let user = User::find_by_id(user_id)
+        .one(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .ok_or("")
+        .kind(ErrorKind::ResourceNotFound)?;
     let models = model::Entity::find()
         .all(&app.conn)
         .await
         .kind(ErrorKind::Internal)?;
 
-    let list = models
-        .into_iter()
-        .filter_map(|m| {
-            let config =
-                <ModelConfig as ModelChecker>::from_toml(&m.config).expect("corruptted database");
-
-            let model: openrouter::Model = config.clone().into();
+    let mut list = Vec::with_capacity(models.len());
+    for m in models.into_iter().filter(|m| can_access(&m.access, &user)) {
+        let config =
+            <ModelConfig as ModelChecker>::from_toml(&m.config).expect("corruptted database");
 
-            let caps = app.processor.get_capability(&model);
+        let caps = app.processor.get_capability(&config).await;
 
-            Some(ModelList {
-                id: m.id,
-                image_input: caps.image_input,
-                audio_input: caps.audio,
-                other_file_input: caps.ocr != OcrEngine::Disabled,
-                tool: caps.toolcall,
-                display_name: config.display_name,
-            })
-        })
-        .collect::<Vec<_>>();
+        list.push(ModelList {
+            id: m.id,
+            image_input: caps.image_input,
+            audio_input: caps.audio,
+            other_file_input: caps.ocr != OcrEngine::Disabled,
+            tool: caps.toolcall,
+            display_name: config.display_name,
+        });
+    }
     Ok(Json(ModelListResp { list }))
 }
@@ -1,3 +1,4 @@
+mod cache;
 mod check;
 mod create;
 mod delete;
@@ -8,15 +9,17 @@ mod write;
 
 use std::sync::Arc;
 
-use axum::{Router, routing::post};
+use axum::{Router, middleware, routing::post};
 
-use crate::AppState;
+use crate::{AppState, middlewares};
 
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/create", post(create::route))
         .route("/delete", post(delete::route))
         .route("/write", post(write::route))
+        .route("/cache", post(cache::route))
+        .route_layer(middleware::from_extractor::<middlewares::admin::Middleware>())
         .route("/list", post(list::route))
         .route("/read", post(read::route))
         .route("/check", post(check::route))
@@ -1,12 +1,11 @@
 use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
-use entity::model;
-use sea_orm::EntityTrait;
+use protocol::{ModelAccess, UserRole};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
-use crate::{AppState, errors::*, middlewares::auth::UserId};
+use crate::{AppState, errors::*, middlewares::auth::UserId, utils::model::find_accessible};
 
 #[derive(Debug, Deserialize)]
 #[typeshare]
@@ -18,15 +17,18 @@ pub struct ModelReadReq {
 #[typeshare]
 pub struct ModelReadResp {
     raw: String,
+    /// only sent to admins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access: Option<ModelAccess>,
 }
 
 pub async fn route(
     State(app): State<Arc<AppState>>,
-    Extension(UserId(_)): Extension<UserId>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Extension(role): Extension<UserRole>,
     Json(req): Json<ModelReadReq>,
 ) -> JsonResult<ModelReadResp> {
-    let model = model::Entity::find_by_id(req.id)
-        .one(&app.conn)
+    let model = find_accessible(&app.conn, user_id, req.id)
         .await
         .kind(ErrorKind::Internal)?;
 
@@ -36,6 +38,7 @@ pub async fn route(
     })?;
 
     let raw = model.config;
+    let access = (role == UserRole::Admin).then_some(model.access);
 
-    Ok(Json(ModelReadResp { raw }))
+    Ok(Json(ModelReadResp { raw, access }))
 }
@@ -2,8 +2,8 @@ use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
 use entity::model;
-use protocol::ModelConfig;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use protocol::{ModelAccess, ModelConfig};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryTrait, sea_query::Expr};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
@@ -14,6 +14,8 @@ use crate::{AppState, errors::*, middlewares::auth::UserId, utils::model::ModelC
 pub struct ModelWriteReq {
     pub id: i32,
     pub config: String,
+    /// Keep the current access if omitted
+    pub access: Option<ModelAccess>,
 }
 
 #[derive(Debug, Serialize)]
@@ -41,6 +43,9 @@ pub async fn route(
 
     let result = model::Entity::update_many()
         .col_expr(model::Column::Config, config.into())
+        .apply_if(req.access, |q, access| {
+            q.col_expr(model::Column::Access, Expr::value(access))
+        })
         .filter(model::Column::Id.eq(req.id))
         .exec(&app.conn)
         .await
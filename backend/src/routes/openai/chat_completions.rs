@@ -0,0 +1,644 @@
+//! `/v1/chat/completions` over llumen models
+//!
+//! `model` is matched against display names, then llumen model ids, then upstream model ids.
+//! Completions go through the same provider clients as chats, without tools of llumen, retries
+//! or fallback models. Cost is recorded in `usage` against the API key of the caller.
+//!
+//! Providers only report total tokens, so `prompt_tokens` is a local estimate and
+//! `completion_tokens` the rest.
+
+use std::{convert::Infallible, sync::Arc, time::Duration};
+
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::StatusCode,
+    response::{
+        IntoResponse, Response, Sse,
+        sse::{Event, KeepAlive},
+    },
+};
+use entity::usage;
+use protocol::{ModeKind, ModelConfig};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+
+use super::{ApiError, accessible_models};
+use crate::{
+    AppState,
+    chat::TokenCounter,
+    middlewares::auth::{ApiKeyId, UserId},
+    openrouter::{
+        self, CompletionOption, File, FinishReason, Image, Message, MessageToolCall,
+        MessageToolResult, ReasoningEffort, StreamCompletionResp, StreamResult,
+    },
+    utils::budget,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionReq {
+    pub model: String,
+    pub messages: Vec<ReqMessage>,
+    #[serde(default)]
+    pub stream: bool,
+    pub stream_options: Option<StreamOptions>,
+    pub max_tokens: Option<i32>,
+    pub max_completion_tokens: Option<i32>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub reasoning_effort: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<ReqTool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamOptions {
+    #[serde(default)]
+    pub include_usage: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "role", rename_all = "lowercase")]
+pub enum ReqMessage {
+    #[serde(alias = "developer")]
+    System {
+        content: Content,
+    },
+    User {
+        content: Content,
+    },
+    Assistant {
+        content: Option<Content>,
+        #[serde(default)]
+        tool_calls: Vec<ReqToolCall>,
+    },
+    Tool {
+        content: Content,
+        tool_call_id: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+    File { file: FileData },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImageUrl {
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileData {
+    pub filename: Option<String>,
+    /// data URL of the file
+    pub file_data: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReqToolCall {
+    pub id: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReqTool {
+    pub function: FunctionDef,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FunctionDef {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub parameters: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChatCompletionResp {
+    id: String,
+    object: &'static str,
+    created: i64,
+    model: String,
+    choices: Vec<Choice>,
+    usage: UsageResp,
+}
+
+#[derive(Debug, Serialize)]
+struct Choice {
+    index: i32,
+    message: RespMessage,
+    finish_reason: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct RespMessage {
+    role: &'static str,
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_content: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<RespToolCall>,
+}
+
+#[derive(Debug, Serialize)]
+struct RespToolCall {
+    id: String,
+    r#type: &'static str,
+    function: FunctionCall,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct UsageResp {
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    total_tokens: i64,
+    /// cost in USD, as OpenRouter reports it
+    cost: f64,
+}
+
+/// What a completion is billed to
+#[derive(Clone, Copy)]
+struct Billing {
+    user_id: i32,
+    model_id: i32,
+    api_key_id: Option<i32>,
+}
+
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    api_key: Option<Extension<ApiKeyId>>,
+    Json(req): Json<ChatCompletionReq>,
+) -> Result<Response, ApiError> {
+    if let Some(reason) = budget::exceeded(&app.conn, user_id)
+        .await
+        .map_err(ApiError::internal)?
+    {
+        return Err(ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "insufficient_quota",
+            reason,
+        ));
+    }
+
+    let (model, config) = resolve_model(&app, user_id, &req.model).await?;
+    let billing = Billing {
+        user_id,
+        model_id: model.id,
+        api_key_id: api_key.map(|Extension(ApiKeyId(id))| id),
+    };
+
+    let messages = convert_messages(req.messages)?;
+    let prompt_tokens = TokenCounter::for_model(&config).count_messages(&messages) as i64;
+
+    let mut option = CompletionOption::builder().tools(
+        &req.tools
+            .into_iter()
+            .map(|tool| openrouter::Tool {
+                name: tool.function.name,
+                description: tool.function.description,
+                schema: tool
+                    .function
+                    .parameters
+                    .unwrap_or_else(|| json!({"type": "object", "properties": {}})),
+            })
+            .collect::<Vec<_>>(),
+    );
+    if let Some(max_tokens) = req.max_completion_tokens.or(req.max_tokens) {
+        option = option.max_tokens(max_tokens);
+    }
+    if let Some(effort) = req.reasoning_effort.as_deref() {
+        option = option.reasoning_effort(match effort {
+            "none" | "minimal" => ReasoningEffort::None,
+            "low" => ReasoningEffort::Low,
+            "medium" => ReasoningEffort::Medium,
+            "high" => ReasoningEffort::High,
+            _ => ReasoningEffort::Auto,
+        });
+    }
+
+    let provider = app
+        .processor
+        .provider_for(&config)
+        .await
+        .map_err(ApiError::internal)?;
+    let mut upstream: openrouter::Model = config.into();
+    upstream.temperature = req.temperature.or(upstream.temperature);
+    upstream.top_p = req.top_p.or(upstream.top_p);
+
+    let stream = provider
+        .stream(upstream, messages, option.build())
+        .await
+        .map_err(|err| ApiError::new(StatusCode::BAD_GATEWAY, "upstream_error", err))?;
+
+    let id = format!("chatcmpl-{}", random_id());
+    let created = entity::patch::now();
+
+    if !req.stream {
+        let mut stream = stream;
+        while let Some(token) = stream.next().await {
+            if let Err(err) = token {
+                let result = stream.get_result();
+                record(&app, billing, &result).await;
+                return Err(ApiError::new(
+                    StatusCode::BAD_GATEWAY,
+                    "upstream_error",
+                    err,
+                ));
+            }
+        }
+        let result = stream.get_result();
+        record(&app, billing, &result).await;
+
+        let text = result.get_text();
+        let reasoning = reasoning_text(&result.responses);
+        let tool_calls = result
+            .toolcalls
+            .iter()
+            .enumerate()
+            .map(|(idx, call)| RespToolCall {
+                id: call_id(&call.id, &id, idx),
+                r#type: "function",
+                function: FunctionCall {
+                    name: call.name.clone(),
+                    arguments: call.args.clone(),
+                },
+            })
+            .collect();
+        let resp = ChatCompletionResp {
+            id,
+            object: "chat.completion",
+            created,
+            model: req.model,
+            choices: vec![Choice {
+                index: 0,
+                finish_reason: finish_reason(&result),
+                message: RespMessage {
+                    role: "assistant",
+                    content: (!text.is_empty() || result.toolcalls.is_empty()).then_some(text),
+                    reasoning_content: (!reasoning.is_empty()).then_some(reasoning),
+                    tool_calls,
+                },
+            }],
+            usage: usage_of(&result, prompt_tokens),
+        };
+        return Ok(Json(resp).into_response());
+    }
+
+    let include_usage = req.stream_options.is_some_and(|x| x.include_usage);
+    let chunk = Chunk {
+        id,
+        created,
+        model: req.model,
+    };
+    let (tx, rx) = mpsc::channel::<Event>(32);
+
+    // drive the upstream in its own task, so usage is recorded even if the client goes away
+    tokio::spawn(async move {
+        let mut stream = stream;
+        let mut started = Vec::new();
+        let send = |data: Value| {
+            let tx = tx.clone();
+            async move {
+                tx.send(Event::default().data(data.to_string()))
+                    .await
+                    .is_ok()
+            }
+        };
+
+        if !send(chunk.delta(json!({"role": "assistant", "content": ""}))).await {
+            record(&app, billing, &stream.get_result()).await;
+            return;
+        }
+        while let Some(token) = stream.next().await {
+            let delta = match token {
+                Ok(StreamCompletionResp::ResponseToken(text)) if !text.is_empty() => {
+                    json!({"content": text})
+                }
+                Ok(StreamCompletionResp::ReasoningToken(text)) if !text.is_empty() => {
+                    json!({"reasoning_content": text})
+                }
+                Ok(StreamCompletionResp::ToolToken {
+                    idx,
+                    id,
+                    args,
+                    name,
+                }) => {
+                    let call = match started.contains(&idx) {
+                        true if name.is_empty() => {
+                            json!({"index": idx, "function": {"arguments": args}})
+                        }
+                        true => {
+                            json!({"index": idx, "function": {"name": name, "arguments": args}})
+                        }
+                        false => {
+                            started.push(idx);
+                            json!({
+                                "index": idx,
+                                "id": call_id(&id, &chunk.id, idx),
+                                "type": "function",
+                                "function": {"name": name, "arguments": args},
+                            })
+                        }
+                    };
+                    json!({"tool_calls": [call]})
+                }
+                Ok(_) => continue,
+                Err(err) => {
+                    let err = ApiError::new(StatusCode::BAD_GATEWAY, "upstream_error", err);
+                    send(err.body()).await;
+                    record(&app, billing, &stream.get_result()).await;
+                    return;
+                }
+            };
+            if !send(chunk.delta(delta)).await {
+                record(&app, billing, &stream.get_result()).await;
+                return;
+            }
+        }
+
+        let result = stream.get_result();
+        record(&app, billing, &result).await;
+
+        send(chunk.finish(finish_reason(&result))).await;
+        if include_usage {
+            send(chunk.usage(usage_of(&result, prompt_tokens))).await;
+        }
+        let _ = tx.send(Event::default().data("[DONE]")).await;
+    });
+
+    let stream = ReceiverStream::new(rx).map(Ok::<_, Infallible>);
+    Ok(Sse::new(stream)
+        .keep_alive(KeepAlive::new().interval(Duration::from_secs(15)))
+        .into_response())
+}
+
+/// Fields shared by every chunk of a stream
+struct Chunk {
+    id: String,
+    created: i64,
+    model: String,
+}
+
+impl Chunk {
+    fn build(&self, choices: Value, usage: Option<UsageResp>) -> Value {
+        let mut chunk = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": choices,
+        });
+        if let Some(usage) = usage {
+            chunk["usage"] = json!(usage);
+        }
+        chunk
+    }
+
+    fn delta(&self, delta: Value) -> Value {
+        self.build(
+            json!([{"index": 0, "delta": delta, "finish_reason": null}]),
+            None,
+        )
+    }
+
+    fn finish(&self, reason: &str) -> Value {
+        self.build(
+            json!([{"index": 0, "delta": {}, "finish_reason": reason}]),
+            None,
+        )
+    }
+
+    fn usage(&self, usage: UsageResp) -> Value {
+        self.build(json!([]), Some(usage))
+    }
+}
+
+/// Find an accessible model by display name, llumen id or upstream model id
+async fn resolve_model(
+    app: &AppState,
+    user_id: i32,
+    name: &str,
+) -> Result<(entity::model::Model, ModelConfig), ApiError> {
+    let models = accessible_models(app, user_id).await?;
+    let id = name.parse::<i32>().ok();
+
+    let position = (models.iter().position(|(_, c)| c.display_name == name))
+        .or_else(|| models.iter().position(|(m, _)| Some(m.id) == id))
+        .or_else(|| models.iter().position(|(_, c)| c.model_id == name));
+
+    match position {
+        Some(position) => Ok(models.into_iter().nth(position).unwrap()),
+        None => Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            format!("model {} does not exist", name),
+        )),
+    }
+}
+
+fn convert_messages(messages: Vec<ReqMessage>) -> Result<Vec<Message>, ApiError> {
+    let mut converted = Vec::with_capacity(messages.len());
+    for message in messages {
+        match message {
+            ReqMessage::System { content } => converted.push(Message::System(content.text())),
+            ReqMessage::User { content } => {
+                let (text, files) = content.split()?;
+                converted.push(match files.is_empty() {
+                    true => Message::User(text),
+                    false => Message::MultipartUser { text, files },
+                });
+            }
+            ReqMessage::Assistant {
+                content,
+                tool_calls,
+            } => {
+                let content = content.map(|x| x.text()).unwrap_or_default();
+                if !content.is_empty() || tool_calls.is_empty() {
+                    converted.push(Message::Assistant {
+                        content,
+                        annotations: None,
+                        reasoning_details: None,
+                        images: Vec::new(),
+                    });
+                }
+                converted.extend(tool_calls.into_iter().map(|call| {
+                    Message::ToolCall(MessageToolCall {
+                        id: call.id,
+                        name: call.function.name,
+                        arguments: call.function.arguments,
+                    })
+                }));
+            }
+            ReqMessage::Tool {
+                content,
+                tool_call_id,
+            } => converted.push(Message::ToolResult(MessageToolResult {
+                id: tool_call_id,
+                content: content.text(),
+            })),
+        }
+    }
+    Ok(converted)
+}
+
+impl Content {
+    /// Text parts joined, other parts are dropped
+    fn text(self) -> String {
+        match self {
+            Content::Text(text) => text,
+            Content::Parts(parts) => parts
+                .into_iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// Text parts joined and the attached files, only data URLs are accepted
+    fn split(self) -> Result<(String, Vec<File>), ApiError> {
+        let parts = match self {
+            Content::Text(text) => return Ok((text, Vec::new())),
+            Content::Parts(parts) => parts,
+        };
+
+        let mut text = Vec::new();
+        let mut files = Vec::new();
+        for part in parts {
+            let (name, url) = match part {
+                ContentPart::Text { text: x } => {
+                    text.push(x);
+                    continue;
+                }
+                ContentPart::ImageUrl { image_url } => ("image".to_owned(), image_url.url),
+                ContentPart::File { file } => {
+                    (file.filename.unwrap_or("file".to_owned()), file.file_data)
+                }
+            };
+            let data = Image::from_data_url(&url)
+                .map_err(|_| ApiError::invalid_request("only data URLs are supported for files"))?
+                .data;
+            files.push(File { name, data });
+        }
+        Ok((text.join("\n"), files))
+    }
+}
+
+fn reasoning_text(responses: &[StreamCompletionResp]) -> String {
+    responses
+        .iter()
+        .filter_map(|x| match x {
+            StreamCompletionResp::ReasoningToken(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Id of a tool call as the upstream issued it, made up only if the upstream sent none
+fn call_id(id: &str, completion_id: &str, idx: usize) -> String {
+    match id.is_empty() {
+        true => format!("call_{}_{}", completion_id, idx),
+        false => id.to_owned(),
+    }
+}
+
+fn finish_reason(result: &StreamResult) -> &'static str {
+    match result.stop_reason {
+        FinishReason::Length => "length",
+        FinishReason::ToolCalls => "tool_calls",
+        FinishReason::Stop | FinishReason::Error => "stop",
+    }
+}
+
+fn usage_of(result: &StreamResult, prompt_tokens: i64) -> UsageResp {
+    let total_tokens = result.usage.token.max(0);
+    let prompt_tokens = prompt_tokens.min(total_tokens);
+    UsageResp {
+        prompt_tokens,
+        completion_tokens: total_tokens - prompt_tokens,
+        total_tokens,
+        cost: result.usage.cost,
+    }
+}
+
+fn random_id() -> String {
+    (0..24).map(|_| fastrand::alphanumeric()).collect()
+}
+
+/// Bill a completion, failures are logged since the response is already out
+async fn record(app: &AppState, billing: Billing, result: &StreamResult) {
+    let res = usage::ActiveModel {
+        user_id: Set(billing.user_id),
+        model_id: Set(Some(billing.model_id)),
+        api_key_id: Set(billing.api_key_id),
+        mode: Set(ModeKind::Normal),
+        price: Set(result.usage.cost as f32),
+        token_count: Set(result.usage.token as i32),
+        created_at: Set(entity::patch::now()),
+        ..Default::default()
+    }
+    .insert(&app.conn)
+    .await;
+    if let Err(err) = res {
+        log::error!(
+            "Failed to record usage of user {}: {}",
+            billing.user_id,
+            err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_tool_turn() {
+        let messages: Vec<ReqMessage> = serde_json::from_value(json!([
+            {"role": "developer", "content": "be brief"},
+            {"role": "user", "content": [
+                {"type": "text", "text": "look"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0KGgo="}},
+            ]},
+            {"role": "assistant", "content": null, "tool_calls": [
+                {"id": "call_1", "type": "function", "function": {"name": "f", "arguments": "{}"}},
+            ]},
+            {"role": "tool", "tool_call_id": "call_1", "content": "42"},
+        ]))
+        .unwrap();
+
+        let messages = convert_messages(messages).unwrap();
+        assert_eq!(messages.len(), 4);
+        assert!(matches!(&messages[0], Message::System(text) if text == "be brief"));
+        assert!(
+            matches!(&messages[1], Message::MultipartUser { text, files }
+            if text == "look" && files.len() == 1)
+        );
+        assert!(matches!(&messages[2], Message::ToolCall(call) if call.id == "call_1"));
+        assert!(matches!(&messages[3], Message::ToolResult(result) if result.content == "42"));
+    }
+}
@@ -0,0 +1,98 @@
+//! OpenAI-compatible facade, for tools that only speak the OpenAI API
+//!
+//! Served under `/v1` rather than `/api`, errors use the OpenAI format so clients can show them.
+
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use entity::{model, prelude::*};
+use protocol::ModelConfig;
+use sea_orm::EntityTrait;
+use serde_json::json;
+
+use crate::{
+    AppState,
+    utils::model::{ModelChecker, can_access},
+};
+
+mod chat_completions;
+mod models;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/chat/completions", post(chat_completions::route))
+        .route("/models", get(models::route))
+}
+
+/// Error in the OpenAI format, `{"error": {"message", "type", "code"}}`
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    kind: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, kind: &'static str, message: impl ToString) -> Self {
+        Self {
+            status,
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn invalid_request(message: impl ToString) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_request_error", message)
+    }
+
+    pub fn internal(message: impl ToString) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", message)
+    }
+
+    /// Body of the error, also sent as the last event of a failed stream
+    pub fn body(&self) -> serde_json::Value {
+        json!({
+            "error": {
+                "message": self.message,
+                "type": self.kind,
+                "code": null,
+            }
+        })
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body())).into_response()
+    }
+}
+
+/// Models the user can access along with their parsed config
+async fn accessible_models(
+    app: &AppState,
+    user_id: i32,
+) -> Result<Vec<(model::Model, ModelConfig)>, ApiError> {
+    let user = User::find_by_id(user_id)
+        .one(&app.conn)
+        .await
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| ApiError::internal("user not found"))?;
+    let models = Model::find()
+        .all(&app.conn)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(models
+        .into_iter()
+        .filter(|m| can_access(&m.access, &user))
+        .filter_map(|m| {
+            let config = <ModelConfig as ModelChecker>::from_toml(&m.config).ok()?;
+            Some((m, config))
+        })
+        .collect())
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use serde::Serialize;
+
+use super::{ApiError, accessible_models};
+use crate::{AppState, middlewares::auth::UserId};
+
+#[derive(Debug, Serialize)]
+pub struct ModelsResp {
+    object: &'static str,
+    data: Vec<ModelObject>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelObject {
+    /// Display name, which is what `model` of a completion is matched against first
+    id: String,
+    object: &'static str,
+    created: i64,
+    owned_by: &'static str,
+}
+
+/// Models the caller can use, named by display name
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+) -> Result<Json<ModelsResp>, ApiError> {
+    let data = accessible_models(&app, user_id)
+        .await?
+        .into_iter()
+        .map(|(_, config)| ModelObject {
+            id: config.display_name,
+            object: "model",
+            created: 0,
+            owned_by: "llumen",
+        })
+        .collect();
+
+    Ok(Json(ModelsResp {
+        object: "list",
+        data,
+    }))
+}
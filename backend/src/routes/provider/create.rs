@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::{prelude::*, provider};
+use protocol::ProviderType;
+use sea_orm::{ActiveValue::Set, EntityTrait};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId, utils::secret};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ProviderCreateReq {
+    pub name: String,
+    pub provider_type: ProviderType,
+    pub base_url: Option<String>,
+    pub api_key: String,
+    /// openrouter plugins, detected from base url if not set
+    pub plugins: Option<bool>,
+    /// usage accounting, detected from base url if not set
+    pub usage: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ProviderCreateResp {
+    pub id: i32,
+}
+
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(_)): Extension<UserId>,
+    Json(req): Json<ProviderCreateReq>,
+) -> JsonResult<ProviderCreateResp> {
+    let api_key = secret::encrypt(&app.provider_key, &req.api_key).kind(ErrorKind::Internal)?;
+
+    let id = Provider::insert(provider::ActiveModel {
+        name: Set(req.name),
+        kind: Set(req.provider_type),
+        base_url: Set(req.base_url.filter(|x| !x.is_empty())),
+        api_key: Set(api_key),
+        plugins: Set(req.plugins),
+        usage: Set(req.usage),
+        ..Default::default()
+    })
+    .exec(&app.conn)
+    .await
+    .kind(ErrorKind::Internal)?
+    .last_insert_id;
+
+    Ok(Json(ProviderCreateResp { id }))
+}
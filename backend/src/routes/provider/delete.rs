@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::{model, provider};
+use protocol::ModelConfig;
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId, utils::model::ModelChecker};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ProviderDeleteReq {
+    pub id: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ProviderDeleteResp {
+    pub deleted: bool,
+}
+
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(_)): Extension<UserId>,
+    Json(req): Json<ProviderDeleteReq>,
+) -> JsonResult<ProviderDeleteResp> {
+    // chats of these models would fail at completion time
+    let models = model::Entity::find()
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+    let used_by: Vec<_> = models
+        .iter()
+        .filter_map(|x| <ModelConfig as ModelChecker>::from_toml(&x.config).ok())
+        .filter(|x| x.provider_id == Some(req.id))
+        .map(|x| x.display_name)
+        .collect();
+    if !used_by.is_empty() {
+        return Err(Json(Error {
+            error: ErrorKind::MalformedRequest,
+            reason: format!(
+                "provider is used by {}, move them to another provider first",
+                used_by.join(", ")
+            ),
+        }));
+    }
+
+    let result = provider::Entity::delete_by_id(req.id)
+        .exec(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    app.processor.invalidate_provider(req.id);
+
+    Ok(Json(ProviderDeleteResp {
+        deleted: result.rows_affected > 0,
+    }))
+}
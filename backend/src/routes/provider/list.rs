@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::provider;
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::read::ProviderReadResp;
+use crate::{AppState, errors::*, middlewares::auth::UserId};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ProviderListReq {}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ProviderListResp {
+    pub list: Vec<ProviderReadResp>,
+}
+
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(_)): Extension<UserId>,
+    Json(_): Json<ProviderListReq>,
+) -> JsonResult<ProviderListResp> {
+    let providers = provider::Entity::find()
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    let list = providers.into_iter().map(Into::into).collect();
+
+    Ok(Json(ProviderListResp { list }))
+}
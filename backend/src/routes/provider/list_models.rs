@@ -1,28 +1,40 @@
-use axum::{extract::State, http::StatusCode, Json};
-use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use crate::{providers::{ProviderConfig, ModelInfo}, AppState};
+use axum::{Extension, Json, extract::State};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId, providers::ModelInfo};
 
 #[derive(Debug, Deserialize)]
-pub struct ListModelsReq {
-    pub provider_config: ProviderConfig,
+#[typeshare]
+pub struct ProviderModelsReq {
+    pub provider_id: i32,
 }
 
 #[derive(Debug, Serialize)]
-pub struct ListModelsResp {
+#[typeshare]
+pub struct ProviderModelsResp {
     pub models: Vec<ModelInfo>,
+    /// Why the provider couldn't be reached, `models` is the built-in list then
     pub error: Option<String>,
 }
 
-pub async fn handle(
-    State(_state): State<Arc<AppState>>,
-    Json(req): Json<ListModelsReq>,
-) -> Result<Json<ListModelsResp>, StatusCode> {
-    let models = req.provider_config.get_default_models();
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(_)): Extension<UserId>,
+    Json(req): Json<ProviderModelsReq>,
+) -> JsonResult<ProviderModelsResp> {
+    let listing = app
+        .processor
+        .list_models(req.provider_id)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    let (models, error) = listing.ok_or_else(|| Error {
+        error: ErrorKind::ResourceNotFound,
+        reason: "provider not found".to_owned(),
+    })?;
 
-    Ok(Json(ListModelsResp {
-        models,
-        error: None,
-    }))
+    Ok(Json(ProviderModelsResp { models, error }))
 }
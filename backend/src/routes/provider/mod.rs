@@ -1,12 +1,25 @@
-mod test_connection;
+mod create;
+mod delete;
+mod list;
 mod list_models;
+mod read;
+mod test_connection;
+mod write;
 
-use axum::{Router, routing::{post, get}};
-use crate::AppState;
 use std::sync::Arc;
 
+use axum::{Router, middleware, routing::post};
+
+use crate::{AppState, middlewares};
+
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
+        .route("/create", post(create::route))
+        .route("/delete", post(delete::route))
+        .route("/write", post(write::route))
+        .route("/list", post(list::route))
+        .route("/read", post(read::route))
         .route("/test", post(test_connection::handle))
-        .route("/models", post(list_models::handle))
+        .route("/models", post(list_models::route))
+        .route_layer(middleware::from_extractor::<middlewares::admin::Middleware>())
 }
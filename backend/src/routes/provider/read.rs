@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::provider;
+use protocol::ProviderType;
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ProviderReadReq {
+    pub id: i32,
+}
+
+/// API key is never sent back to client
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ProviderReadResp {
+    pub id: i32,
+    pub name: String,
+    pub provider_type: ProviderType,
+    pub base_url: Option<String>,
+    pub has_api_key: bool,
+    pub plugins: Option<bool>,
+    pub usage: Option<bool>,
+}
+
+impl From<provider::Model> for ProviderReadResp {
+    fn from(model: provider::Model) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            provider_type: model.kind,
+            base_url: model.base_url,
+            has_api_key: !model.api_key.is_empty(),
+            plugins: model.plugins,
+            usage: model.usage,
+        }
+    }
+}
+
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(_)): Extension<UserId>,
+    Json(req): Json<ProviderReadReq>,
+) -> JsonResult<ProviderReadResp> {
+    let provider = provider::Entity::find_by_id(req.id)
+        .one(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    let provider = provider.ok_or_else(|| Error {
+        error: ErrorKind::ResourceNotFound,
+        reason: "provider not found".to_owned(),
+    })?;
+
+    Ok(Json(provider.into()))
+}
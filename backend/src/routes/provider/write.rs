@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::provider;
+use protocol::ProviderType;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId, utils::secret};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct ProviderWriteReq {
+    pub id: i32,
+    pub name: String,
+    pub provider_type: ProviderType,
+    pub base_url: Option<String>,
+    /// keep the stored key if not set
+    pub api_key: Option<String>,
+    /// openrouter plugins, detected from base url if not set
+    pub plugins: Option<bool>,
+    /// usage accounting, detected from base url if not set
+    pub usage: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct ProviderWriteResp {
+    pub wrote: bool,
+}
+
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(_)): Extension<UserId>,
+    Json(req): Json<ProviderWriteReq>,
+) -> JsonResult<ProviderWriteResp> {
+    let provider = provider::Entity::find_by_id(req.id)
+        .one(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    let Some(provider) = provider else {
+        return Ok(Json(ProviderWriteResp { wrote: false }));
+    };
+
+    let mut provider: provider::ActiveModel = provider.into();
+    provider.name = Set(req.name);
+    provider.kind = Set(req.provider_type);
+    provider.base_url = Set(req.base_url.filter(|x| !x.is_empty()));
+    provider.plugins = Set(req.plugins);
+    provider.usage = Set(req.usage);
+    if let Some(api_key) = req.api_key {
+        provider.api_key =
+            Set(secret::encrypt(&app.provider_key, &api_key).kind(ErrorKind::Internal)?);
+    }
+
+    provider.update(&app.conn).await.kind(ErrorKind::Internal)?;
+
+    app.processor.invalidate_provider(req.id);
+
+    Ok(Json(ProviderWriteResp { wrote: true }))
+}
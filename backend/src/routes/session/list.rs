@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::{prelude::*, session};
+use sea_orm::{QueryOrder, prelude::*};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{
+    AppState,
+    errors::*,
+    middlewares::auth::{SessionId, UserId},
+};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct SessionListReq {}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct SessionListResp {
+    pub list: Vec<SessionList>,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct SessionList {
+    pub id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// unix timestamp in seconds
+    #[typeshare(serialized_as = "number")]
+    pub created_at: i64,
+    /// unix timestamp in seconds
+    #[typeshare(serialized_as = "number")]
+    pub expires_at: i64,
+    /// whether this is the session making the request
+    pub current: bool,
+}
+
+/// Active sessions of the current user, newest first
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Extension(SessionId(session_id)): Extension<SessionId>,
+    Json(_): Json<SessionListReq>,
+) -> JsonResult<SessionListResp> {
+    let list = Session::find()
+        .filter(session::Column::UserId.eq(user_id))
+        .filter(session::Column::RevokedAt.is_null())
+        .filter(session::Column::ExpiresAt.gt(entity::patch::now()))
+        .order_by_desc(session::Column::Id)
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .into_iter()
+        .map(|x| SessionList {
+            id: x.id,
+            user_agent: x.user_agent,
+            created_at: x.created_at,
+            expires_at: x.expires_at,
+            current: x.id == session_id,
+        })
+        .collect();
+
+    Ok(Json(SessionListResp { list }))
+}
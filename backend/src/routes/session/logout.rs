@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::SessionId, utils::session};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct SessionLogoutReq {}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct SessionLogoutResp {}
+
+/// Revoke the token used for the request
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(SessionId(session_id)): Extension<SessionId>,
+    Json(_): Json<SessionLogoutReq>,
+) -> JsonResult<SessionLogoutResp> {
+    session::revoke(&app.conn, session_id)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    Ok(Json(SessionLogoutResp {}))
+}
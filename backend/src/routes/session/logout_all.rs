@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{
+    AppState,
+    errors::*,
+    middlewares::auth::{SessionId, UserId},
+    utils::session,
+};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct SessionLogoutAllReq {
+    /// Stay logged in on the device making the request
+    #[serde(default)]
+    pub keep_current: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct SessionLogoutAllResp {
+    pub revoked: i32,
+}
+
+/// Log out of every device
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Extension(SessionId(session_id)): Extension<SessionId>,
+    Json(req): Json<SessionLogoutAllReq>,
+) -> JsonResult<SessionLogoutAllResp> {
+    let except = req.keep_current.then_some(session_id);
+    let revoked = session::revoke_all(&app.conn, user_id, except)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    log::info!("user({}) logged out of {} sessions", user_id, revoked);
+
+    Ok(Json(SessionLogoutAllResp {
+        revoked: revoked as i32,
+    }))
+}
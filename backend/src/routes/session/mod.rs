@@ -0,0 +1,16 @@
+use std::sync::Arc;
+
+use axum::{Router, routing::post};
+
+use crate::AppState;
+
+mod list;
+mod logout;
+mod logout_all;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/list", post(list::route))
+        .route("/logout", post(logout::route))
+        .route("/logout_all", post(logout_all::route))
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::{HeaderMap, HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use protocol::UserRole;
+
+use super::report::{UsageReportReq, aggregate};
+use crate::{AppState, errors::*, middlewares::auth::UserId};
+
+/// Same report as `/api/usage/report`, as a CSV file
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Extension(role): Extension<UserRole>,
+    Json(mut req): Json<UsageReportReq>,
+) -> Result<Response, AppError> {
+    // members only see their own usage
+    if role != UserRole::Admin {
+        req.user_id = Some(user_id);
+    }
+    let list = aggregate(&app, &req).await?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record([
+            "day", "user_id", "username", "model_id", "model", "mode", "cost", "token", "count",
+        ])
+        .kind(ErrorKind::Internal)?;
+    for row in list {
+        writer
+            .write_record([
+                row.day,
+                row.user_id.to_string(),
+                row.username,
+                row.model_id.map(|x| x.to_string()).unwrap_or_default(),
+                row.model_name.unwrap_or_default(),
+                row.mode.as_str().to_owned(),
+                row.cost.to_string(),
+                row.token.to_string(),
+                row.count.to_string(),
+            ])
+            .kind(ErrorKind::Internal)?;
+    }
+    let body = writer.into_inner().kind(ErrorKind::Internal)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/csv; charset=utf-8"),
+    );
+    let filename = format!("attachment; filename=\"usage-{}-{}.csv\"", req.from, req.to);
+    if let Ok(value) = HeaderValue::from_str(&filename) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+
+    Ok((headers, body).into_response())
+}
@@ -0,0 +1,14 @@
+use std::sync::Arc;
+
+use axum::{Router, routing::post};
+
+use crate::AppState;
+
+mod export;
+mod report;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/report", post(report::route))
+        .route("/export", post(export::route))
+}
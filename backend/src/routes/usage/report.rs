@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::{model, prelude::*, usage, user};
+use protocol::{ModeKind, ModelConfig, UserRole};
+use sea_orm::{ActiveEnum, QueryOrder, QuerySelect, QueryTrait, prelude::*};
+use serde::{Deserialize, Serialize};
+use time::{Date, Duration, OffsetDateTime, macros::format_description};
+use typeshare::typeshare;
+
+use crate::{
+    AppState, errors::*, middlewares::auth::UserId, utils::chat::ChatMode,
+    utils::model::ModelChecker,
+};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct UsageReportReq {
+    /// First day of the range, `YYYY-MM-DD` in UTC
+    pub from: String,
+    /// Last day of the range(inclusive), `YYYY-MM-DD` in UTC
+    pub to: String,
+    /// Only report usage of this user, default to every user, members always get their own
+    pub user_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct UsageReportResp {
+    pub list: Vec<UsageReportRow>,
+}
+
+/// Usage of one user with one model and mode on one day
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct UsageReportRow {
+    /// `YYYY-MM-DD` in UTC
+    pub day: String,
+    pub user_id: i32,
+    pub username: String,
+    /// null if the model was deleted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    pub mode: ChatMode,
+    pub cost: f32,
+    pub token: i32,
+    /// number of completions
+    pub count: i32,
+}
+
+#[derive(Debug, sea_orm::FromQueryResult)]
+struct Aggregate {
+    /// days since unix epoch
+    day: i64,
+    user_id: i32,
+    model_id: Option<i32>,
+    mode: i32,
+    cost: Option<f64>,
+    token: Option<i64>,
+    count: i64,
+}
+
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Extension(role): Extension<UserRole>,
+    Json(mut req): Json<UsageReportReq>,
+) -> JsonResult<UsageReportResp> {
+    // members only see their own usage
+    if role != UserRole::Admin {
+        req.user_id = Some(user_id);
+    }
+    let list = aggregate(&app, &req).await?;
+    Ok(Json(UsageReportResp { list }))
+}
+
+/// Sum up usage by day, user, model and mode, ordered by day
+pub(super) async fn aggregate(
+    app: &AppState,
+    req: &UsageReportReq,
+) -> Result<Vec<UsageReportRow>, AppError> {
+    let format = format_description!("[year]-[month]-[day]");
+    let from = Date::parse(&req.from, format).kind(ErrorKind::MalformedRequest)?;
+    let to = Date::parse(&req.to, format).kind(ErrorKind::MalformedRequest)?;
+    if from > to {
+        return Err(Json(Error {
+            error: ErrorKind::MalformedRequest,
+            reason: "from must not be after to".to_owned(),
+        }));
+    }
+    let since = from.midnight().assume_utc().unix_timestamp();
+    let until = (to.midnight().assume_utc() + Duration::DAY).unix_timestamp();
+
+    let day = Expr::col(usage::Column::CreatedAt).div(SECONDS_PER_DAY);
+    let rows = Usage::find()
+        .filter(usage::Column::CreatedAt.gte(since))
+        .filter(usage::Column::CreatedAt.lt(until))
+        .apply_if(req.user_id, |q, user_id| {
+            q.filter(usage::Column::UserId.eq(user_id))
+        })
+        .select_only()
+        .column_as(day.clone(), "day")
+        .column(usage::Column::UserId)
+        .column(usage::Column::ModelId)
+        .column(usage::Column::Mode)
+        .column_as(usage::Column::Price.sum(), "cost")
+        .column_as(usage::Column::TokenCount.sum(), "token")
+        .column_as(usage::Column::Id.count(), "count")
+        .group_by(day.clone())
+        .group_by(usage::Column::UserId)
+        .group_by(usage::Column::ModelId)
+        .group_by(usage::Column::Mode)
+        .order_by_asc(day)
+        .order_by_asc(usage::Column::UserId)
+        .into_model::<Aggregate>()
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?;
+
+    let usernames: HashMap<i32, String> = User::find()
+        .select_only()
+        .column(user::Column::Id)
+        .column(user::Column::Name)
+        .into_tuple()
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .into_iter()
+        .collect();
+    let model_names: HashMap<i32, String> = Model::find()
+        .all(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .into_iter()
+        .filter_map(|m: model::Model| {
+            let config = <ModelConfig as ModelChecker>::from_toml(&m.config).ok()?;
+            Some((m.id, config.display_name))
+        })
+        .collect();
+
+    let list = rows
+        .into_iter()
+        .map(|row| {
+            let day = OffsetDateTime::from_unix_timestamp(row.day * SECONDS_PER_DAY)
+                .map(|x| x.date().to_string())
+                .unwrap_or_default();
+            UsageReportRow {
+                day,
+                user_id: row.user_id,
+                username: usernames.get(&row.user_id).cloned().unwrap_or_default(),
+                model_id: row.model_id,
+                model_name: row.model_id.and_then(|id| model_names.get(&id).cloned()),
+                mode: ModeKind::try_from_value(&row.mode)
+                    .unwrap_or(ModeKind::Normal)
+                    .into(),
+                cost: row.cost.unwrap_or_default() as f32,
+                token: row.token.unwrap_or_default().min(i32::MAX as i64) as i32,
+                count: row.count as i32,
+            }
+        })
+        .collect();
+
+    Ok(list)
+}
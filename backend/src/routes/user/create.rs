@@ -1,8 +1,9 @@
 use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
-use entity::{prelude::*, user};
-use sea_orm::{ActiveValue, EntityTrait};
+use entity::user;
+use protocol::UserRole;
+use sea_orm::{ActiveModelTrait, ActiveValue};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
@@ -13,6 +14,10 @@ use crate::{AppState, errors::*, middlewares::auth::UserId};
 pub struct UserCreateReq {
     pub username: String,
     pub password: String,
+    /// Default to member
+    pub role: Option<UserRole>,
+    /// Ask for a new password on first login, default to false
+    pub must_change_password: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -26,19 +31,22 @@ pub async fn route(
     Extension(UserId(_)): Extension<UserId>,
     Json(req): Json<UserCreateReq>,
 ) -> JsonResult<UserCreateResp> {
+    app.password_policy
+        .check(&req.username, &req.password)
+        .kind(ErrorKind::WeakPassword)?;
+
     let password_hash = app.hasher.hash_password(&req.password);
     let new_user = user::ActiveModel {
         name: ActiveValue::Set(req.username),
         password: ActiveValue::Set(password_hash),
+        role: ActiveValue::Set(req.role.unwrap_or_default()),
+        must_change_password: ActiveValue::Set(req.must_change_password.unwrap_or_default()),
         ..Default::default()
     };
 
-    let new_user = User::insert(new_user)
-        .exec(&app.conn)
-        .await
-        .kind(ErrorKind::Internal)?;
+    let new_user = new_user.insert(&app.conn).await.kind(ErrorKind::Internal)?;
 
     Ok(Json(UserCreateResp {
-        user_id: new_user.last_insert_id,
+        user_id: new_user.id,
     }))
 }
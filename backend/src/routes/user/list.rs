@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
 use entity::user;
+use protocol::UserRole;
 use sea_orm::EntityTrait;
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
@@ -19,6 +20,12 @@ pub struct UserListResp {
 pub struct UserList {
     pub id: i32,
     pub name: String,
+    pub role: UserRole,
+    pub groups: Vec<String>,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    /// Locked after too many failed logins, see `/api/user/unlock`
+    pub locked: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -38,8 +45,13 @@ pub async fn route(
         .into_iter()
         .filter_map(|m| {
             Some(UserList {
+                locked: app.login_guard.is_locked(&m.name),
                 id: m.id,
                 name: m.name,
+                role: m.role,
+                groups: m.groups.0,
+                display_name: m.display_name,
+                email: m.email,
             })
         })
         .collect::<Vec<_>>();
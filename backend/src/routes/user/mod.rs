@@ -1,20 +1,23 @@
 use std::sync::Arc;
 
-use axum::{Router, routing::post};
+use axum::{Router, middleware, routing::post};
 
-use crate::AppState;
+use crate::{AppState, middlewares};
 
 mod create;
 mod delete;
 mod list;
 mod read;
+mod unlock;
 mod update;
 
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/create", post(create::route))
         .route("/delete", post(delete::route))
+        .route("/list", post(list::route))
+        .route("/unlock", post(unlock::route))
+        .route_layer(middleware::from_extractor::<middlewares::admin::Middleware>())
         .route("/read", post(read::route))
         .route("/update", post(update::route))
-        .route("/list", post(list::route))
 }
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
 use entity::prelude::*;
-use protocol::UserPreference;
+use protocol::{UserBudget, UserPreference, UserRole};
 use sea_orm::EntityTrait;
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
@@ -12,7 +12,7 @@ use crate::{AppState, errors::*, middlewares::auth::UserId};
 #[derive(Debug, Deserialize)]
 #[typeshare]
 pub struct UserReadReq {
-    /// If omit will use the current user instead
+    /// If omit will use the current user instead, only admins can read other users
     pub user_id: Option<i32>,
 }
 
@@ -22,13 +22,25 @@ pub struct UserReadResp {
     pub user_id: i32,
     pub username: String,
     pub preference: UserPreference,
+    pub budget: UserBudget,
+    pub role: UserRole,
+    pub groups: Vec<String>,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
 }
 
 pub async fn route(
     State(app): State<Arc<AppState>>,
     Extension(UserId(user_id)): Extension<UserId>,
+    Extension(role): Extension<UserRole>,
     Json(req): Json<UserReadReq>,
 ) -> JsonResult<UserReadResp> {
+    if role != UserRole::Admin && req.user_id.is_some_and(|id| id != user_id) {
+        return Err(Json(Error {
+            error: ErrorKind::Forbidden,
+            reason: "only admins can read other users".to_owned(),
+        }));
+    }
     let user_id = req.user_id.unwrap_or(user_id);
 
     let res = User::find_by_id(user_id)
@@ -42,5 +54,10 @@ pub async fn route(
         user_id: res.id,
         username: res.name,
         preference: res.preference,
+        budget: res.budget,
+        role: res.role,
+        groups: res.groups.0,
+        display_name: res.display_name,
+        email: res.email,
     }))
 }
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, extract::State};
+use entity::prelude::*;
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::{AppState, errors::*, middlewares::auth::UserId};
+
+#[derive(Debug, Deserialize)]
+#[typeshare]
+pub struct UserUnlockReq {
+    pub user_id: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[typeshare]
+pub struct UserUnlockResp {
+    /// Whether the user was locked
+    pub unlocked: bool,
+}
+
+/// Clear failed logins of a user, lifting its lockout
+pub async fn route(
+    State(app): State<Arc<AppState>>,
+    Extension(UserId(user_id)): Extension<UserId>,
+    Json(req): Json<UserUnlockReq>,
+) -> JsonResult<UserUnlockResp> {
+    let model = User::find_by_id(req.user_id)
+        .one(&app.conn)
+        .await
+        .kind(ErrorKind::Internal)?
+        .ok_or("user not found")
+        .kind(ErrorKind::ResourceNotFound)?;
+
+    let unlocked = app.login_guard.unlock(&model.name);
+    if unlocked {
+        log::info!("user({}) is unlocked by {}", model.id, user_id);
+    }
+
+    Ok(Json(UserUnlockResp { unlocked }))
+}
@@ -2,20 +2,37 @@ use std::sync::Arc;
 
 use axum::{Extension, Json, extract::State};
 use entity::prelude::*;
-use protocol::UserPreference;
+use protocol::{UserBudget, UserGroups, UserPreference, UserRole};
 use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, TransactionTrait};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
-use crate::{AppState, errors::*, middlewares::auth::UserId};
+use crate::{
+    AppState,
+    errors::*,
+    middlewares::auth::{ApiKeyId, SessionId, UserId},
+    utils::{api_key, login_guard::Account, session},
+};
 
 #[derive(Debug, Deserialize)]
 #[typeshare]
 pub struct UserUpdateReq {
-    /// If omit will use the current user instead
+    /// If omit will use the current user instead, only admins can update other users
     pub user_id: Option<i32>,
     pub preference: Option<UserPreference>,
     pub password: Option<String>,
+    /// Required to change your own password
+    pub current_password: Option<String>,
+    /// Ask for a new password on next login and log the user out, admin only
+    ///
+    /// Setting a password clears it unless this is set to true.
+    pub force_password_change: Option<bool>,
+    /// Replaces the whole budget, unset limits are removed, admin only
+    pub budget: Option<UserBudget>,
+    /// Admin only, admins cannot change their own role
+    pub role: Option<UserRole>,
+    /// Replaces the groups used by model access, admin only
+    pub groups: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -26,20 +43,54 @@ pub struct UserUpdateResp {
 
 pub async fn route(
     State(app): State<Arc<AppState>>,
-    Extension(UserId(user_id)): Extension<UserId>,
+    Extension(UserId(self_id)): Extension<UserId>,
+    Extension(self_role): Extension<UserRole>,
+    // absent for API keys
+    session: Option<Extension<SessionId>>,
+    key: Option<Extension<ApiKeyId>>,
     Json(req): Json<UserUpdateReq>,
 ) -> JsonResult<UserUpdateResp> {
     let UserUpdateReq {
         user_id: user_id_req,
         preference,
         password,
+        current_password,
+        force_password_change,
+        budget,
+        role,
+        groups,
     } = req;
-    let user_id = user_id_req.unwrap_or(user_id);
+    let user_id = user_id_req.unwrap_or(self_id);
+
+    let admin_only = user_id != self_id
+        || budget.is_some()
+        || role.is_some()
+        || groups.is_some()
+        || force_password_change.is_some();
+    if admin_only && self_role != UserRole::Admin {
+        return Err(Json(Error {
+            error: ErrorKind::Forbidden,
+            reason: "only admins can update other users, budgets, roles, groups and resets"
+                .to_owned(),
+        }));
+    }
+    // keep at least one admin around
+    if user_id == self_id && role.is_some_and(|role| role != UserRole::Admin) {
+        return Err(Json(Error {
+            error: ErrorKind::MalformedRequest,
+            reason: "You cannot demote yourself".to_owned(),
+        }));
+    }
 
     // Please note that update to same value does not result in error
     // But update no value does result in error
     debug_assert!(
-        preference.is_some() || password.is_some(),
+        preference.is_some()
+            || password.is_some()
+            || force_password_change.is_some()
+            || budget.is_some()
+            || role.is_some()
+            || groups.is_some(),
         "no field to update"
     );
 
@@ -69,13 +120,66 @@ pub async fn route(
         }
         active_model.preference = sea_orm::ActiveValue::Set(new_preference);
     }
-    if let Some(password) = password {
-        let password_hash = app.hasher.hash_password(&password);
+    if let Some(password) = &password {
+        // admins reset passwords of others without knowing them
+        if user_id == self_id {
+            // throttled like logins, or this would be a way around them
+            let account = Account {
+                name: active_model.name.as_ref(),
+                admin: self_role == UserRole::Admin,
+            };
+            app.login_guard
+                .check(None, Some(account))
+                .kind(ErrorKind::TooManyAttempts)?;
+            let current = active_model.password.as_ref();
+            if !current_password.is_some_and(|x| app.hasher.verify_password(current, &x)) {
+                app.login_guard.fail(None, Some(account));
+                return Err(Json(Error {
+                    error: ErrorKind::LoginFail,
+                    reason: "current password is incorrect".to_owned(),
+                }));
+            }
+            app.login_guard.succeed(None, Some(account));
+        }
+        app.password_policy
+            .check(active_model.name.as_ref(), password)
+            .kind(ErrorKind::WeakPassword)?;
+
+        let password_hash = app.hasher.hash_password(password);
         active_model.password = sea_orm::ActiveValue::Set(password_hash);
+        active_model.must_change_password = sea_orm::ActiveValue::Set(false);
+    }
+    if let Some(force) = force_password_change {
+        active_model.must_change_password = sea_orm::ActiveValue::Set(force);
+    }
+    if let Some(budget) = budget {
+        active_model.budget = sea_orm::ActiveValue::Set(budget);
+    }
+    if let Some(role) = role {
+        active_model.role = sea_orm::ActiveValue::Set(role);
+    }
+    if let Some(groups) = groups {
+        active_model.groups = sea_orm::ActiveValue::Set(UserGroups(groups));
     }
 
     active_model.update(&txn).await.kind(ErrorKind::Internal)?;
 
+    // a new password logs out every other device and key, the caller stays logged in
+    if password.is_some() || force_password_change == Some(true) {
+        let except = session
+            .filter(|_| user_id == self_id)
+            .map(|Extension(SessionId(id))| id);
+        session::revoke_all(&txn, user_id, except)
+            .await
+            .kind(ErrorKind::Internal)?;
+        let except = key
+            .filter(|_| user_id == self_id)
+            .map(|Extension(ApiKeyId(id))| id);
+        api_key::revoke_all(&txn, user_id, except)
+            .await
+            .kind(ErrorKind::Internal)?;
+    }
+
     txn.commit().await.kind(ErrorKind::Internal)?;
 
     Ok(Json(UserUpdateResp { user_id }))
@@ -0,0 +1,154 @@
+//! API keys let scripts authenticate without logging in
+//!
+//! A key is `llm_` followed by 32 random bytes in URL safe base64. Only its sha256 is stored,
+//! the key itself is shown once on creation.
+
+use anyhow::Context;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use entity::{api_key, prelude::*, user};
+use protocol::ApiKeyScope;
+use sea_orm::{DatabaseConnection, DbErr, QueryTrait, prelude::*};
+use sha2::{Digest, Sha256};
+
+pub const KEY_PREFIX: &str = "llm_";
+/// Characters of a key kept in clear, to tell keys apart
+const SHOWN_LEN: usize = KEY_PREFIX.len() + 8;
+/// How often `last_used_at` is written, in seconds
+const TOUCH_INTERVAL: i64 = 60;
+
+/// Actions that change nothing, the second segment of a route like `/chat/read`
+const READ_ACTIONS: &[&str] = &[
+    "read", "paginate", "list", "ids", "sse", "image", "report", "export", "search",
+];
+/// Modules a `chat` key may use in full
+const CHAT_MODULES: &[&str] = &["chat", "message", "file"];
+/// Modules only a login session may use, so a leaked key cannot mint more keys
+const SESSION_MODULES: &[&str] = &["session", "api_key"];
+
+/// A new key, returns `(key, shown prefix, hash)`
+pub fn generate() -> anyhow::Result<(String, String, String)> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes)
+        .map_err(|err| anyhow::anyhow!("{}", err))
+        .context("no randomness from the OS")?;
+    let key = format!("{}{}", KEY_PREFIX, URL_SAFE_NO_PAD.encode(bytes));
+    let prefix = key[..SHOWN_LEN].to_owned();
+    let hash = hash(&key);
+    Ok((key, prefix, hash))
+}
+
+pub fn hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|x| format!("{:02x}", x))
+        .collect()
+}
+
+/// Key and its user, if it's neither revoked nor expired
+pub async fn find_active(
+    conn: &DatabaseConnection,
+    key: &str,
+) -> Result<Option<(api_key::Model, user::Model)>, DbErr> {
+    let now = entity::patch::now();
+    let res = ApiKey::find()
+        .filter(api_key::Column::Hash.eq(hash(key)))
+        .filter(api_key::Column::RevokedAt.is_null())
+        .filter(
+            api_key::Column::ExpiresAt
+                .is_null()
+                .or(api_key::Column::ExpiresAt.gt(now)),
+        )
+        .find_also_related(User)
+        .one(conn)
+        .await?;
+    let Some((api_key, Some(user))) = res else {
+        return Ok(None);
+    };
+
+    if api_key
+        .last_used_at
+        .is_none_or(|x| x + TOUCH_INTERVAL < now)
+    {
+        ApiKey::update_many()
+            .col_expr(api_key::Column::LastUsedAt, Expr::value(now))
+            .filter(api_key::Column::Id.eq(api_key.id))
+            .exec(conn)
+            .await?;
+    }
+
+    Ok(Some((api_key, user)))
+}
+
+/// Revoke every key of a user but `except`, returns how many were revoked
+pub async fn revoke_all(
+    conn: &impl ConnectionTrait,
+    user_id: i32,
+    except: Option<i32>,
+) -> Result<u64, DbErr> {
+    let res = ApiKey::update_many()
+        .col_expr(
+            api_key::Column::RevokedAt,
+            Expr::value(entity::patch::now()),
+        )
+        .filter(api_key::Column::UserId.eq(user_id))
+        .filter(api_key::Column::RevokedAt.is_null())
+        .apply_if(except, |q, id| q.filter(api_key::Column::Id.ne(id)))
+        .exec(conn)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+/// Whether a key of `scope` may call `path`, like `/api/chat/read` or `/v1/models`
+pub fn allows(scope: ApiKeyScope, path: &str) -> bool {
+    // OpenAI-compatible routes only list models or chat
+    if let Some(path) = path.strip_prefix("/v1") {
+        return path == "/models" || scope != ApiKeyScope::Read;
+    }
+
+    let path = path.strip_prefix("/api").unwrap_or(path);
+    let mut segments = path.trim_start_matches('/').split('/');
+    let module = segments.next().unwrap_or_default();
+    let action = segments.next().unwrap_or_default();
+
+    if SESSION_MODULES.contains(&module) {
+        return false;
+    }
+    match scope {
+        ApiKeyScope::Full => true,
+        ApiKeyScope::Chat => CHAT_MODULES.contains(&module) || READ_ACTIONS.contains(&action),
+        ApiKeyScope::Read => READ_ACTIONS.contains(&action),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_key() {
+        let (key, prefix, hash) = generate().unwrap();
+        assert!(key.starts_with("llm_"));
+        assert!(key.starts_with(&prefix));
+        assert_eq!(hash, super::hash(&key));
+        assert_eq!(hash.len(), 64);
+    }
+
+    #[test]
+    fn scopes() {
+        assert!(allows(ApiKeyScope::Full, "/api/user/update"));
+        assert!(!allows(ApiKeyScope::Full, "/api/api_key/create"));
+        assert!(!allows(ApiKeyScope::Full, "/api/session/logout"));
+
+        assert!(allows(ApiKeyScope::Chat, "/api/message/create"));
+        assert!(allows(ApiKeyScope::Chat, "/api/model/list"));
+        assert!(allows(ApiKeyScope::Chat, "/v1/chat/completions"));
+        assert!(!allows(ApiKeyScope::Chat, "/api/user/update"));
+
+        assert!(allows(ApiKeyScope::Read, "/api/chat/paginate"));
+        assert!(allows(ApiKeyScope::Read, "/api/file/read/3"));
+        assert!(allows(ApiKeyScope::Read, "/v1/models"));
+        assert!(!allows(ApiKeyScope::Read, "/api/chat/create"));
+        assert!(!allows(ApiKeyScope::Read, "/api/model/write"));
+        assert!(!allows(ApiKeyScope::Read, "/v1/chat/completions"));
+    }
+}
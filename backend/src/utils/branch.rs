@@ -0,0 +1,164 @@
+//! Messages of a chat form a tree through `parent_id`
+//!
+//! Editing a user message adds a sibling and regenerating a reply adds an alternative, so nothing
+//! is lost. The chat keeps the leaf of the selected branch in `leaf_id`, the path from the root to
+//! it is what gets shown and sent to the model.
+
+use std::collections::{BTreeMap, HashMap};
+
+use entity::{chat, message, prelude::*};
+use sea_orm::{QuerySelect, prelude::*};
+
+#[derive(Debug, Default)]
+pub struct Tree {
+    parents: BTreeMap<i32, Option<i32>>,
+    /// children of each message, `None` for roots, oldest first
+    children: HashMap<Option<i32>, Vec<i32>>,
+}
+
+impl FromIterator<(i32, Option<i32>)> for Tree {
+    fn from_iter<T: IntoIterator<Item = (i32, Option<i32>)>>(iter: T) -> Self {
+        let parents: BTreeMap<_, _> = iter.into_iter().collect();
+        let mut children: HashMap<_, Vec<_>> = HashMap::new();
+        for (&id, &parent) in &parents {
+            children.entry(parent).or_default().push(id);
+        }
+        Self { parents, children }
+    }
+}
+
+impl Tree {
+    pub async fn load(conn: &impl ConnectionTrait, chat_id: i32) -> Result<Self, DbErr> {
+        let nodes: Vec<(i32, Option<i32>)> = Message::find()
+            .filter(message::Column::ChatId.eq(chat_id))
+            .select_only()
+            .column(message::Column::Id)
+            .column(message::Column::ParentId)
+            .into_tuple()
+            .all(conn)
+            .await?;
+        Ok(nodes.into_iter().collect())
+    }
+
+    pub fn contains(&self, id: i32) -> bool {
+        self.parents.contains_key(&id)
+    }
+
+    pub fn parent(&self, id: i32) -> Option<i32> {
+        self.parents.get(&id).copied().flatten()
+    }
+
+    /// Ids from the root down to `leaf`, empty if `leaf` isn't in the tree
+    pub fn path(&self, leaf: i32) -> Vec<i32> {
+        let mut path = Vec::new();
+        let mut current = Some(leaf).filter(|id| self.contains(*id));
+        // parents are older, a longer walk means a broken tree
+        while let Some(id) = current
+            && path.len() <= self.parents.len()
+        {
+            path.push(id);
+            current = self.parent(id);
+        }
+        path.reverse();
+        path
+    }
+
+    /// Messages sharing the parent of `id`, itself included, oldest first
+    pub fn siblings(&self, id: i32) -> &[i32] {
+        match self.parents.get(&id) {
+            Some(parent) => &self.children[parent],
+            None => &[],
+        }
+    }
+
+    /// Leaf reached by following the newest child from `id`
+    pub fn newest_leaf(&self, id: i32) -> i32 {
+        let mut current = id;
+        while let Some(&child) = self.children.get(&Some(current)).and_then(|x| x.last()) {
+            current = child;
+        }
+        current
+    }
+
+    /// Leaf of the selected branch, following the newest messages if `leaf_id` is unset or gone
+    pub fn leaf(&self, leaf_id: Option<i32>) -> Option<i32> {
+        match leaf_id.filter(|id| self.contains(*id)) {
+            Some(id) => Some(self.newest_leaf(id)),
+            None => self.parents.keys().last().copied(),
+        }
+    }
+
+    /// `id` and all of its descendants
+    pub fn subtree(&self, id: i32) -> Vec<i32> {
+        let mut ids = Vec::new();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            ids.push(id);
+            if let Some(children) = self.children.get(&Some(id)) {
+                stack.extend(children);
+            }
+        }
+        ids
+    }
+
+    /// Ids of the selected branch of a chat
+    pub fn selected(&self, chat: &chat::Model) -> Vec<i32> {
+        self.leaf(chat.leaf_id)
+            .map(|leaf| self.path(leaf))
+            .unwrap_or_default()
+    }
+}
+
+/// Select the branch ending at `leaf_id`
+pub async fn set_leaf(
+    conn: &impl ConnectionTrait,
+    chat_id: i32,
+    leaf_id: Option<i32>,
+) -> Result<(), DbErr> {
+    Chat::update_many()
+        .col_expr(chat::Column::LeafId, Expr::value(leaf_id))
+        .filter(chat::Column::Id.eq(chat_id))
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///  1 ─ 2 ┬ 3 ─ 4
+    ///        └ 5 ┬ 6
+    ///            └ 7
+    fn tree() -> Tree {
+        [
+            (1, None),
+            (2, Some(1)),
+            (3, Some(2)),
+            (4, Some(3)),
+            (5, Some(2)),
+            (6, Some(5)),
+            (7, Some(5)),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn walk_branches() {
+        let tree = tree();
+        assert_eq!(tree.path(4), vec![1, 2, 3, 4]);
+        assert_eq!(tree.path(6), vec![1, 2, 5, 6]);
+        assert!(tree.path(8).is_empty());
+        assert_eq!(tree.siblings(5), &[3, 5]);
+        assert_eq!(tree.siblings(1), &[1]);
+        assert_eq!(tree.newest_leaf(2), 7);
+        assert_eq!(tree.leaf(Some(3)), Some(4));
+        assert_eq!(tree.leaf(None), Some(7));
+        assert_eq!(tree.leaf(Some(9)), Some(7));
+
+        let mut subtree = tree.subtree(5);
+        subtree.sort();
+        assert_eq!(subtree, vec![5, 6, 7]);
+    }
+}
@@ -0,0 +1,110 @@
+//! Spending budgets of users, counted from the `usage` table
+//!
+//! Windows are calendar based in UTC: a daily budget resets at midnight, a monthly budget on the
+//! first day of the month.
+
+use entity::{prelude::*, usage};
+use protocol::UserBudget;
+use sea_orm::{DatabaseConnection, DbErr, QuerySelect, prelude::*};
+use time::{OffsetDateTime, Time};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Spent {
+    pub cost: f32,
+    pub token: i64,
+}
+
+/// Usage of a user since a unix timestamp
+pub async fn spent_since(
+    conn: &DatabaseConnection,
+    user_id: i32,
+    since: i64,
+) -> Result<Spent, DbErr> {
+    let (cost, token) = Usage::find()
+        .filter(usage::Column::UserId.eq(user_id))
+        .filter(usage::Column::CreatedAt.gte(since))
+        .select_only()
+        .column_as(usage::Column::Price.sum(), "cost")
+        .column_as(usage::Column::TokenCount.sum(), "token")
+        .into_tuple::<(Option<f64>, Option<i64>)>()
+        .one(conn)
+        .await?
+        .unwrap_or_default();
+
+    Ok(Spent {
+        cost: cost.unwrap_or_default() as f32,
+        token: token.unwrap_or_default(),
+    })
+}
+
+/// Describe the first budget of the user that is used up, if any
+pub async fn exceeded(conn: &DatabaseConnection, user_id: i32) -> Result<Option<String>, DbErr> {
+    let Some(user) = User::find_by_id(user_id).one(conn).await? else {
+        return Ok(None);
+    };
+    let UserBudget {
+        daily_cost,
+        daily_token,
+        monthly_cost,
+        monthly_token,
+    } = user.budget;
+
+    let now = OffsetDateTime::now_utc();
+    let windows = [
+        ("daily", day_start(now), daily_cost, daily_token),
+        ("monthly", month_start(now), monthly_cost, monthly_token),
+    ];
+
+    for (name, since, cost, token) in windows {
+        if cost.is_none() && token.is_none() {
+            continue;
+        }
+        let spent = spent_since(conn, user_id, since).await?;
+        if let Some(cost) = cost
+            && spent.cost >= cost
+        {
+            return Ok(Some(format!("{} cost budget of {} is used up", name, cost)));
+        }
+        if let Some(token) = token
+            && spent.token >= token as i64
+        {
+            return Ok(Some(format!(
+                "{} token budget of {} is used up",
+                name, token
+            )));
+        }
+    }
+
+    Ok(None)
+}
+
+pub fn day_start(now: OffsetDateTime) -> i64 {
+    now.replace_time(Time::MIDNIGHT).unix_timestamp()
+}
+
+pub fn month_start(now: OffsetDateTime) -> i64 {
+    now.replace_time(Time::MIDNIGHT)
+        .replace_day(1)
+        .expect("every month has a first day")
+        .unix_timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn window_start() {
+        let now = datetime!(2026-10-17 15:30 UTC);
+        assert_eq!(
+            day_start(now),
+            datetime!(2026-10-17 0:00 UTC).unix_timestamp()
+        );
+        assert_eq!(
+            month_start(now),
+            datetime!(2026-10-01 0:00 UTC).unix_timestamp()
+        );
+    }
+}
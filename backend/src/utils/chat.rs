@@ -10,6 +10,16 @@ pub enum ChatMode {
     Research,
 }
 
+impl ChatMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatMode::Normal => "normal",
+            ChatMode::Search => "search",
+            ChatMode::Research => "research",
+        }
+    }
+}
+
 impl From<protocol::ModeKind> for ChatMode {
     fn from(value: protocol::ModeKind) -> Self {
         match value {
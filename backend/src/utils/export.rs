@@ -0,0 +1,367 @@
+//! Render a chat for download
+//!
+//! Markdown and HTML show one branch the way the chat page does. The HTML file is the Markdown
+//! rendered with files inlined as data URLs, so it opens without the server. Raw HTML in messages
+//! is shown as text instead of being rendered, and links or images with a scheme other than
+//! http, https or mailto point nowhere, except the data URLs of inlined files.
+//!
+//! [`ChatExport`] is the lossless format, it keeps every branch and the content of every file.
+
+use std::collections::{HashMap, HashSet};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use protocol::{AssistantChunk, Deep, MessageInner};
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, html};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::chat::ChatMode;
+
+/// Bumped on changes that older readers can't handle
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatExport {
+    pub version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub mode: ChatMode,
+    /// last message of the selected branch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaf_id: Option<i32>,
+    /// unix timestamp in seconds
+    pub created_at: i64,
+    /// unix timestamp in seconds
+    pub updated_at: i64,
+    /// oldest first, ids are only meaningful within the export
+    pub messages: Vec<ExportMessage>,
+    pub files: Vec<ExportFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportMessage {
+    pub id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<i32>,
+    pub inner: MessageInner,
+    pub token_count: i32,
+    pub price: f32,
+    /// unix timestamp in seconds
+    pub created_at: i64,
+}
+
+/// A file referenced by `FileMetadata` or `AssistantChunk::Image`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportFile {
+    pub id: i32,
+    pub mime_type: String,
+    /// base64
+    pub data: String,
+}
+
+/// Content of a file to inline
+#[derive(Debug, Clone)]
+pub struct File {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Markdown of the messages, files are referenced by name
+pub fn markdown<'a>(title: &str, messages: impl IntoIterator<Item = &'a MessageInner>) -> String {
+    Writer::new(None).render(title, messages).out
+}
+
+/// Self-contained HTML page of the messages
+pub fn html<'a>(
+    title: &str,
+    messages: impl IntoIterator<Item = &'a MessageInner>,
+    files: &HashMap<i32, File>,
+) -> String {
+    let writer = Writer::new(Some(files)).render(title, messages);
+    let parser = Parser::new_ext(&writer.out, OPTIONS).map(|event| match event {
+        Event::Html(x) | Event::InlineHtml(x) => Event::Text(x),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: checked(dest_url, &writer.inlined),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: checked(dest_url, &writer.inlined),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut body = String::new();
+    html::push_html(&mut body, parser);
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+const OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_STRIKETHROUGH)
+    .union(Options::ENABLE_TASKLISTS)
+    .union(Options::ENABLE_FOOTNOTES);
+
+const STYLE: &str = "body{max-width:48rem;margin:2rem auto;padding:0 1rem;font-family:sans-serif;\
+line-height:1.6}pre{overflow-x:auto;padding:.75rem;background:#f4f4f5;border-radius:.375rem}\
+blockquote{margin-left:0;padding-left:1rem;border-left:3px solid #d4d4d8;color:#52525b}\
+img{max-width:100%}h2{border-bottom:1px solid #e4e4e7}table{border-collapse:collapse}\
+td,th{border:1px solid #d4d4d8;padding:.25rem .5rem}";
+
+/// Ids of files the messages refer to
+pub fn file_ids<'a>(messages: impl IntoIterator<Item = &'a MessageInner>) -> Vec<i32> {
+    let mut ids = Vec::new();
+    for message in messages {
+        match message {
+            MessageInner::User { files, .. } => ids.extend(files.iter().map(|x| x.id)),
+            MessageInner::Assistant(chunks) => ids.extend(chunks.iter().filter_map(|x| match x {
+                AssistantChunk::Image(id) => Some(*id),
+                _ => None,
+            })),
+        }
+    }
+    ids
+}
+
+/// The URL if it is safe to show, `#` otherwise
+fn checked<'a>(url: CowStr<'a>, inlined: &HashSet<String>) -> CowStr<'a> {
+    match inlined.contains(url.as_ref()) || safe_url(&url) {
+        true => url,
+        false => "#".into(),
+    }
+}
+
+/// Whether a link can't run script, relative URLs have no scheme and are kept
+fn safe_url(url: &str) -> bool {
+    // browsers drop these before looking at the scheme
+    let url: String = url
+        .trim_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    match url.find([':', '/', '?', '#']) {
+        Some(end) if url[end..].starts_with(':') => {
+            let scheme = url[..end].to_ascii_lowercase();
+            matches!(scheme.as_str(), "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    html::push_html(&mut escaped, [Event::Text(text.into())].into_iter());
+    escaped
+}
+
+struct Writer<'a> {
+    out: String,
+    /// inline files when set
+    files: Option<&'a HashMap<i32, File>>,
+    /// data URLs of inlined files, the only ones allowed in HTML
+    inlined: HashSet<String>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(files: Option<&'a HashMap<i32, File>>) -> Self {
+        Self {
+            out: String::new(),
+            files,
+            inlined: HashSet::new(),
+        }
+    }
+
+    fn render<'b>(
+        mut self,
+        title: &str,
+        messages: impl IntoIterator<Item = &'b MessageInner>,
+    ) -> Self {
+        self.block(&format!("# {}", title.trim()));
+        for message in messages {
+            match message {
+                MessageInner::User { text, files } => {
+                    self.block("## User");
+                    self.block(text);
+                    for file in files {
+                        self.file(file.id, &file.name);
+                    }
+                }
+                MessageInner::Assistant(chunks) => {
+                    self.block("## Assistant");
+                    self.chunks(chunks);
+                }
+            }
+        }
+        self
+    }
+
+    fn chunks(&mut self, chunks: &[AssistantChunk]) {
+        for chunk in chunks {
+            match chunk {
+                AssistantChunk::Text(text) => self.block(text),
+                AssistantChunk::Reasoning(text) => {
+                    self.quote(&format!("**Reasoning**\n\n{}", text.trim()))
+                }
+                AssistantChunk::ToolCall { name, arg, .. } => {
+                    self.block(&format!("**Tool call** `{}`", name));
+                    self.fenced(arg, "json");
+                }
+                AssistantChunk::ToolResult { response, .. } => {
+                    self.block("**Tool result**");
+                    self.fenced(response, "");
+                }
+                AssistantChunk::Error(text) => self.quote(&format!("**Error:** {}", text.trim())),
+                AssistantChunk::DeepAgent(deep) => self.deep(deep),
+                AssistantChunk::Image(id) => self.file(*id, "image"),
+                // provider metadata, not shown in the chat either
+                AssistantChunk::Annotation(_) | AssistantChunk::ReasoningDetail(_) => {}
+            }
+        }
+    }
+
+    /// The plan of a deep research, the report follows as text
+    fn deep(&mut self, deep: &Deep) {
+        self.block(&format!("### Research plan: {}", deep.title.trim()));
+        self.block(&deep.thought);
+        for (i, step) in deep.steps.iter().enumerate() {
+            self.block(&format!("#### {}. {}", i + 1, step.title.trim()));
+            self.block(&step.description);
+            self.chunks(&step.progress);
+        }
+    }
+
+    fn file(&mut self, id: i32, name: &str) {
+        let name = name.replace(['[', ']', '\n'], " ");
+        let block = match self.files.and_then(|files| files.get(&id)) {
+            Some(file) => {
+                let url = format!(
+                    "data:{};base64,{}",
+                    file.mime_type,
+                    STANDARD.encode(&file.data)
+                );
+                let block = match file.mime_type.starts_with("image/") {
+                    true => format!("![{}](<{}>)", name, url),
+                    false => format!("📎 [{}](<{}>)", name, url),
+                };
+                self.inlined.insert(url);
+                block
+            }
+            None => format!("📎 {}", name),
+        };
+        self.block(&block);
+    }
+
+    fn quote(&mut self, text: &str) {
+        let quoted = text
+            .lines()
+            .map(|line| match line.is_empty() {
+                true => ">".to_owned(),
+                false => format!("> {}", line),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.block(&quoted);
+    }
+
+    /// Code block with a fence longer than any backtick run inside
+    fn fenced(&mut self, text: &str, lang: &str) {
+        let longest = text
+            .split(|c| c != '`')
+            .map(str::len)
+            .max()
+            .unwrap_or_default();
+        let fence = "`".repeat(longest.max(2) + 1);
+        self.block(&format!("{fence}{lang}\n{}\n{fence}", text.trim_end()));
+    }
+
+    fn block(&mut self, text: &str) {
+        let text = text.trim_matches('\n');
+        if text.trim().is_empty() {
+            return;
+        }
+        self.out.push_str(text);
+        self.out.push_str("\n\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use protocol::FileMetadata;
+
+    use super::*;
+
+    #[test]
+    fn render_chat() {
+        let messages = [
+            MessageInner::User {
+                text: "what is <b>?".to_owned(),
+                files: vec![FileMetadata {
+                    name: "cat.png".to_owned(),
+                    id: 1,
+                }],
+            },
+            MessageInner::Assistant(vec![
+                AssistantChunk::Reasoning("think\nmore".to_owned()),
+                AssistantChunk::ToolCall {
+                    id: "0".to_owned(),
+                    arg: "{\"q\":\"```\"}".to_owned(),
+                    name: "search".to_owned(),
+                },
+                AssistantChunk::Text("a tag".to_owned()),
+            ]),
+        ];
+
+        let markdown = markdown("Tags", &messages);
+        assert!(markdown.starts_with("# Tags\n\n## User\n\nwhat is <b>?\n\n📎 cat.png\n\n"));
+        assert!(markdown.contains("> **Reasoning**\n>\n> think\n> more\n\n"));
+        assert!(markdown.contains("````json\n{\"q\":\"```\"}\n````\n\n"));
+
+        let files = HashMap::from([(
+            1,
+            File {
+                mime_type: "image/png".to_owned(),
+                data: b"png".to_vec(),
+            },
+        )]);
+        let html = html("<Tags>", &messages, &files);
+        assert!(html.contains("<title>&lt;Tags&gt;</title>"));
+        assert!(html.contains("what is &lt;b&gt;?"));
+        assert!(html.contains("<img src=\"data:image/png;base64,cG5n\" alt=\"cat.png\" />"));
+    }
+
+    #[test]
+    fn unsafe_links() {
+        let messages = [MessageInner::Assistant(vec![AssistantChunk::Text(
+            "[a](javascript:alert(1)) [b](<java\tscript:alert(1)>) ![c](data:text/html,x) \
+             <JavaScript:alert(1)> [d](https://example.com) [e](mailto:a@b.c) [f](docs/a:b)"
+                .to_owned(),
+        )])];
+        let html = html("Links", &messages, &HashMap::new());
+        assert!(!html.contains("data:"));
+        assert!(html.contains("<a href=\"#\">a</a> <a href=\"#\">b</a>"));
+        assert!(html.contains("<a href=\"#\">JavaScript:alert(1)</a>"));
+        assert!(html.contains("<img src=\"#\" alt=\"c\" />"));
+        assert!(html.contains("href=\"https://example.com\""));
+        assert!(html.contains("href=\"mailto:a@b.c\""));
+        assert!(html.contains("href=\"docs/a:b\""));
+    }
+}
@@ -0,0 +1,58 @@
+//! Trusted-header authentication behind an SSO proxy
+//!
+//! The proxy authenticates the user and passes its name in `TRUSTED_HEADER`. With
+//! `TRUSTED_HEADER_PROVISION` set, unknown names are created on first sight.
+
+use std::env::var;
+
+use protocol::UserRole;
+
+#[derive(Debug, Clone)]
+pub struct HeaderAuthConfig {
+    /// header holding the username
+    pub header: String,
+    /// create users that don't exist yet
+    pub provision: bool,
+    /// role of provisioned users
+    pub default_role: UserRole,
+    /// groups of provisioned users
+    pub default_groups: Vec<String>,
+    /// header holding the display name, updated on every login
+    pub display_name_header: Option<String>,
+    /// header holding the email, updated on every login
+    pub email_header: Option<String>,
+}
+
+impl HeaderAuthConfig {
+    /// Read from `TRUSTED_HEADER*` environment variables, `None` if header auth is disabled
+    pub fn from_env() -> Option<Self> {
+        let header = var("TRUSTED_HEADER").ok()?;
+        let provision = var("TRUSTED_HEADER_PROVISION")
+            .is_ok_and(|x| matches!(x.to_lowercase().as_str(), "1" | "true" | "yes"));
+        let default_role = match var("TRUSTED_HEADER_DEFAULT_ROLE").as_deref() {
+            Ok("admin") => UserRole::Admin,
+            Ok("member") | Err(_) => UserRole::Member,
+            Ok(x) => {
+                log::warn!("Unknown TRUSTED_HEADER_DEFAULT_ROLE {}, using member", x);
+                UserRole::Member
+            }
+        };
+        let default_groups = var("TRUSTED_HEADER_DEFAULT_GROUPS")
+            .map(|x| {
+                x.split(',')
+                    .map(|x| x.trim().to_owned())
+                    .filter(|x| !x.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            header,
+            provision,
+            default_role,
+            default_groups,
+            display_name_header: var("TRUSTED_HEADER_NAME").ok(),
+            email_header: var("TRUSTED_HEADER_EMAIL").ok(),
+        })
+    }
+}
@@ -0,0 +1,353 @@
+//! Throttle password guessing on `/api/auth/login`
+//!
+//! Failures are counted per client IP and per username. After a few free attempts each failure
+//! doubles the wait before the next attempt is accepted. A username with too many failures is
+//! locked until an admin clears it or the lockout runs out, whichever comes first.
+//!
+//! Admins back off like everyone else but are never locked. Anyone can fail logins of a
+//! username, so locking them would let a stranger keep the only admin, the one who could unlock
+//! it, out for good.
+//!
+//! Only usernames that exist are counted, so their counters are bounded by the users. IP
+//! counters are capped at [`MAX_ENTRIES`], past that the least recently used one is dropped.
+//! Counters live in memory, so restarting the server clears them too.
+
+use std::{
+    collections::HashMap,
+    env::var,
+    net::{IpAddr, SocketAddr},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::http::HeaderMap;
+
+/// Failures accepted before backoff starts
+const FREE_ATTEMPTS: u32 = 3;
+/// Wait after the first failure past the free ones, doubled on each further failure
+const BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(15 * 60);
+/// Failures of a username that lock it
+const LOCKOUT_FAILURES: u32 = 10;
+const LOCKOUT: Duration = Duration::from_secs(24 * 60 * 60);
+/// Counters idle for this long are forgotten
+const FORGET_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+/// Counters kept before idle ones are swept and the oldest IPs dropped
+const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Ip(IpAddr),
+    User(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    failures: u32,
+    retry_at: Instant,
+    last: Instant,
+    /// `retry_at` is the end of a lockout rather than a backoff
+    locked: bool,
+}
+
+/// Existing user a login is attempted for
+#[derive(Debug, Clone, Copy)]
+pub struct Account<'a> {
+    pub name: &'a str,
+    /// admins are never locked
+    pub admin: bool,
+}
+
+/// Why an attempt is refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denied {
+    /// Too many recent failures, retry after the duration
+    Backoff(Duration),
+    /// The username is locked until an admin clears it
+    Locked,
+}
+
+impl std::fmt::Display for Denied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Denied::Backoff(wait) => write!(
+                f,
+                "too many failed logins, retry in {} seconds",
+                wait.as_secs().max(1)
+            ),
+            Denied::Locked => write!(
+                f,
+                "account is locked after too many failed logins, ask an admin to unlock it"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LoginGuard {
+    /// header holding the client IP when behind a reverse proxy, like `X-Real-IP`
+    ip_header: Option<String>,
+    entries: Mutex<HashMap<Key, Entry>>,
+}
+
+impl LoginGuard {
+    /// Read `REAL_IP_HEADER` from environment, the peer address is used if it's not set
+    pub fn from_env() -> Self {
+        Self {
+            ip_header: var("REAL_IP_HEADER").ok(),
+            ..Default::default()
+        }
+    }
+
+    /// IP of the client, from the configured header or the connection
+    pub fn client_ip(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+        let header = self.ip_header.as_deref().and_then(|name| {
+            let value = headers.get_all(name).iter().next_back()?.to_str().ok()?;
+            // proxies append the address they see to X-Forwarded-For, anything before the
+            // last one came from the client
+            value.rsplit(',').next()?.trim().parse().ok()
+        });
+        header.or(peer.map(|x| x.ip()))
+    }
+
+    /// Whether an attempt may go ahead, `account` is `None` if the username doesn't exist
+    pub fn check(&self, ip: Option<IpAddr>, account: Option<Account>) -> Result<(), Denied> {
+        self.check_at(Instant::now(), ip, account)
+    }
+
+    /// Count a failed attempt, `account` is `None` if the username doesn't exist
+    pub fn fail(&self, ip: Option<IpAddr>, account: Option<Account>) {
+        self.fail_at(Instant::now(), ip, account)
+    }
+
+    /// Reset the counters after a successful login
+    pub fn succeed(&self, ip: Option<IpAddr>, account: Option<Account>) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(account) = account {
+            entries.remove(&Key::User(account.name.to_owned()));
+        }
+        if let Some(ip) = ip {
+            entries.remove(&Key::Ip(ip));
+        }
+    }
+
+    /// Whether a username is locked
+    pub fn is_locked(&self, username: &str) -> bool {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&Key::User(username.to_owned()))
+            .is_some_and(|x| x.locked && x.retry_at > Instant::now())
+    }
+
+    /// Clear the lockout and failures of a username, returns whether it was locked
+    pub fn unlock(&self, username: &str) -> bool {
+        let locked = self.is_locked(username);
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(&Key::User(username.to_owned()));
+        locked
+    }
+
+    fn check_at(
+        &self,
+        now: Instant,
+        ip: Option<IpAddr>,
+        account: Option<Account>,
+    ) -> Result<(), Denied> {
+        let entries = self.entries.lock().unwrap();
+        let user = account.and_then(|x| entries.get(&Key::User(x.name.to_owned())));
+        if let Some(user) = user
+            && user.retry_at > now
+        {
+            return Err(match user.locked {
+                true => Denied::Locked,
+                false => Denied::Backoff(user.retry_at - now),
+            });
+        }
+        if let Some(ip) = ip.and_then(|ip| entries.get(&Key::Ip(ip)))
+            && ip.retry_at > now
+        {
+            return Err(Denied::Backoff(ip.retry_at - now));
+        }
+        Ok(())
+    }
+
+    fn fail_at(&self, now: Instant, ip: Option<IpAddr>, account: Option<Account>) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| now.duration_since(entry.last) < FORGET_AFTER);
+        }
+
+        let keys = ip
+            .map(Key::Ip)
+            .into_iter()
+            .chain(account.map(|x| Key::User(x.name.to_owned())));
+        for key in keys {
+            if entries.len() >= MAX_ENTRIES && !entries.contains_key(&key) {
+                let oldest = entries
+                    .iter()
+                    .filter(|(key, _)| matches!(key, Key::Ip(_)))
+                    .min_by_key(|(_, entry)| entry.last)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+
+            let entry = entries.entry(key.clone()).or_insert(Entry {
+                failures: 0,
+                retry_at: now,
+                last: now,
+                locked: false,
+            });
+            entry.failures += 1;
+            entry.last = now;
+            entry.retry_at = now + delay(entry.failures);
+
+            let lockable = account.is_some_and(|x| !x.admin);
+            match key {
+                Key::User(name) if lockable && entry.failures >= LOCKOUT_FAILURES => {
+                    log::warn!(
+                        "user {} is locked after {} failed logins",
+                        name,
+                        entry.failures
+                    );
+                    entry.retry_at = now + LOCKOUT;
+                    entry.locked = true;
+                }
+                Key::User(name) if entry.failures > FREE_ATTEMPTS => {
+                    log::warn!("{} failed logins for user {}", entry.failures, name);
+                }
+                Key::Ip(ip) if entry.failures > FREE_ATTEMPTS => {
+                    log::warn!("{} failed logins from {}", entry.failures, ip);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Wait before the next attempt after `failures` failures
+fn delay(failures: u32) -> Duration {
+    if failures <= FREE_ATTEMPTS {
+        return Duration::ZERO;
+    }
+    let exponent = (failures - FREE_ATTEMPTS - 1).min(16);
+    (BASE_DELAY * 2u32.pow(exponent)).min(MAX_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_delay() {
+        assert_eq!(delay(FREE_ATTEMPTS), Duration::ZERO);
+        assert_eq!(delay(FREE_ATTEMPTS + 1), Duration::from_secs(1));
+        assert_eq!(delay(FREE_ATTEMPTS + 3), Duration::from_secs(4));
+        assert_eq!(delay(100), MAX_DELAY);
+    }
+
+    const ALICE: Account = Account {
+        name: "alice",
+        admin: false,
+    };
+    const ADMIN: Account = Account {
+        name: "admin",
+        admin: true,
+    };
+
+    #[test]
+    fn backoff_then_lockout() {
+        let guard = LoginGuard::default();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let mut now = Instant::now();
+
+        for _ in 0..FREE_ATTEMPTS {
+            assert_eq!(guard.check_at(now, Some(ip), Some(ALICE)), Ok(()));
+            guard.fail_at(now, Some(ip), Some(ALICE));
+        }
+        assert_eq!(guard.check_at(now, Some(ip), Some(ALICE)), Ok(()));
+        guard.fail_at(now, Some(ip), Some(ALICE));
+        assert_eq!(
+            guard.check_at(now, Some(ip), Some(ALICE)),
+            Err(Denied::Backoff(Duration::from_secs(1)))
+        );
+        // the IP is throttled for other usernames too
+        let bob = Account {
+            name: "bob",
+            admin: false,
+        };
+        assert!(guard.check_at(now, Some(ip), Some(bob)).is_err());
+
+        while guard.check_at(now, None, Some(ALICE)) != Err(Denied::Locked) {
+            now += MAX_DELAY;
+            guard.fail_at(now, None, Some(ALICE));
+        }
+        assert!(guard.unlock("alice"));
+        assert_eq!(guard.check_at(now, None, Some(ALICE)), Ok(()));
+    }
+
+    #[test]
+    fn admins_back_off_but_never_lock() {
+        let guard = LoginGuard::default();
+        let mut now = Instant::now();
+
+        for _ in 0..LOCKOUT_FAILURES * 2 {
+            now += MAX_DELAY;
+            guard.fail_at(now, None, Some(ADMIN));
+        }
+        assert_eq!(
+            guard.check_at(now, None, Some(ADMIN)),
+            Err(Denied::Backoff(MAX_DELAY))
+        );
+        assert!(!guard.is_locked("admin"));
+        assert_eq!(guard.check_at(now + MAX_DELAY, None, Some(ADMIN)), Ok(()));
+    }
+
+    #[test]
+    fn unknown_usernames_only_counted_by_ip() {
+        let guard = LoginGuard::default();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let now = Instant::now();
+
+        for _ in 0..LOCKOUT_FAILURES {
+            guard.fail_at(now, None, None);
+        }
+        assert!(guard.entries.lock().unwrap().is_empty());
+
+        for _ in 0..=FREE_ATTEMPTS {
+            guard.fail_at(now, Some(ip), None);
+        }
+        assert!(guard.check_at(now, Some(ip), None).is_err());
+    }
+
+    #[test]
+    fn oldest_ip_dropped_when_full() {
+        let guard = LoginGuard::default();
+        let now = Instant::now();
+
+        guard.fail_at(now, None, Some(ALICE));
+        for i in 0..MAX_ENTRIES as u32 {
+            let ip = IpAddr::from(i.to_be_bytes());
+            guard.fail_at(now + Duration::from_millis(i as u64), Some(ip), None);
+        }
+        let entries = guard.entries.lock().unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert!(entries.contains_key(&Key::User("alice".to_owned())));
+        assert!(!entries.contains_key(&Key::Ip(IpAddr::from([0, 0, 0, 0]))));
+    }
+
+    #[test]
+    fn rightmost_forwarded_address() {
+        let guard = LoginGuard {
+            ip_header: Some("x-forwarded-for".to_owned()),
+            ..Default::default()
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "1.2.3.4, 10.0.0.7".parse().unwrap());
+        assert_eq!(
+            guard.client_ip(&headers, None),
+            Some("10.0.0.7".parse().unwrap())
+        );
+    }
+}
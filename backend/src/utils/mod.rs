@@ -1,6 +1,16 @@
+pub mod api_key;
 pub mod blob;
+pub mod branch;
+pub mod budget;
 pub mod chat;
+pub mod export;
+pub mod header_auth;
 pub mod logger;
+pub mod login_guard;
 pub mod model;
+pub mod oidc;
 pub mod password_hash;
+pub mod password_policy;
+pub mod secret;
+pub mod session;
 pub mod webp;
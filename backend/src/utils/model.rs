@@ -1,4 +1,6 @@
-use protocol::ModelConfig;
+use entity::{model, prelude::*, user};
+use protocol::{ModelAccess, ModelConfig, UserRole};
+use sea_orm::{DatabaseConnection, DbErr, EntityTrait};
 use serde::de::DeserializeOwned;
 
 use crate::openrouter;
@@ -21,6 +23,7 @@ impl From<ModelConfig> for openrouter::Model {
             top_k: value.parameter.top_k,
             top_p: value.parameter.top_p,
             capability,
+            price: value.price,
         }
     }
 }
@@ -72,6 +75,40 @@ impl ModelChecker for ModelConfig {
             }
         }
 
+        if let Some(backoff) = self.retry.backoff
+            && (backoff < 0.0 || !backoff.is_finite())
+        {
+            anyhow::bail!("retry backoff must be a non-negative number");
+        }
+
+        if self.fallback.iter().any(|id| id.contains(":online")) {
+            anyhow::bail!("\"online\" suffix are not allowed in fallback models");
+        }
+
         Ok(())
     }
 }
+
+/// Whether a user can see and use a model, admins can use every model
+pub fn can_access(access: &ModelAccess, user: &user::Model) -> bool {
+    if user.role == UserRole::Admin || (access.users.is_empty() && access.groups.is_empty()) {
+        return true;
+    }
+    access.users.contains(&user.id)
+        || (user.groups.0.iter()).any(|group| access.groups.contains(group))
+}
+
+/// Find a model by id, as if it doesn't exist when the user cannot access it
+pub async fn find_accessible(
+    conn: &DatabaseConnection,
+    user_id: i32,
+    model_id: i32,
+) -> Result<Option<model::Model>, DbErr> {
+    let (Some(user), Some(model)) = (
+        User::find_by_id(user_id).one(conn).await?,
+        Model::find_by_id(model_id).one(conn).await?,
+    ) else {
+        return Ok(None);
+    };
+    Ok(can_access(&model.access, &user).then_some(model))
+}
@@ -0,0 +1,538 @@
+//! OpenID Connect login with authorization code and PKCE
+//!
+//! The ID token comes straight from the token endpoint over TLS, so its signature isn't checked
+//! (OpenID Connect Core 3.1.3.7), only issuer, audience, expiry and nonce are.
+//!
+//! Nothing is kept on the server between redirect and callback. State, nonce and PKCE verifier
+//! are sealed into a cookie of the browser starting the login, and the callback must present
+//! the same state, so a callback URL of another browser's login is useless.
+
+use std::env::var;
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use pasetors::{
+    claims::{Claims, ClaimsValidationRules},
+    keys::SymmetricKey,
+    local,
+    token::UntrustedToken,
+    version4::V4,
+};
+use protocol::UserRole;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+/// How long a login may take between redirect and callback
+const PENDING_TTL: Duration = Duration::from_secs(10 * 60);
+/// Cookie carrying a login from redirect to callback
+pub const LOGIN_COOKIE: &str = "llumen_oidc";
+/// Login cookies are sealed with the token key, this keeps them from passing as tokens
+const LOGIN_ASSERTION: &[u8] = b"llumen-oidc-login";
+
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    /// `.../api/auth/oidc/callback` as registered at the issuer
+    pub redirect_url: String,
+    pub scopes: String,
+    /// claim used as username
+    pub username_claim: String,
+    /// claim holding roles or groups, users with one of `admin_values` become admins
+    pub role_claim: Option<String>,
+    pub admin_values: Vec<String>,
+    /// claim copied into the groups of a user
+    pub groups_claim: Option<String>,
+}
+
+impl OidcConfig {
+    /// Read from `OIDC_*` environment variables, `None` if OIDC is not configured
+    pub fn from_env() -> Option<Self> {
+        let list = |name: &str| {
+            var(name)
+                .map(|x| {
+                    x.split(',')
+                        .map(|x| x.trim().to_owned())
+                        .filter(|x| !x.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Some(Self {
+            issuer: var("OIDC_ISSUER").ok()?,
+            client_id: var("OIDC_CLIENT_ID").ok()?,
+            client_secret: var("OIDC_CLIENT_SECRET").ok(),
+            redirect_url: var("OIDC_REDIRECT_URL").ok()?,
+            scopes: var("OIDC_SCOPES").unwrap_or("openid profile email".to_owned()),
+            username_claim: var("OIDC_USERNAME_CLAIM").unwrap_or("preferred_username".to_owned()),
+            role_claim: var("OIDC_ROLE_CLAIM").ok(),
+            admin_values: list("OIDC_ADMIN_VALUES"),
+            groups_claim: var("OIDC_GROUPS_CLAIM").ok(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+    access_token: Option<String>,
+}
+
+struct Pending {
+    verifier: String,
+    nonce: String,
+}
+
+/// A started login
+pub struct Authorization {
+    /// URL of the issuer to redirect to
+    pub url: String,
+    /// sealed login, see [`Oidc::cookie`]
+    pub cookie: String,
+}
+
+/// Identity of a user who completed the login
+#[derive(Debug, Clone, PartialEq)]
+pub struct OidcIdentity {
+    pub subject: String,
+    pub username: String,
+    /// `None` if roles are not mapped from claims
+    pub role: Option<UserRole>,
+    /// `None` if groups are not mapped from claims
+    pub groups: Option<Vec<String>>,
+}
+
+pub struct Oidc {
+    config: OidcConfig,
+    http: reqwest::Client,
+    discovery: OnceCell<Discovery>,
+}
+
+impl Oidc {
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            discovery: OnceCell::new(),
+        }
+    }
+
+    /// `Set-Cookie` value keeping a sealed login until the callback, an empty one clears it
+    ///
+    /// `SameSite=Lax` still sends it on the top-level redirect back from the issuer.
+    pub fn cookie(&self, login: &str) -> String {
+        let url = Url::parse(&self.config.redirect_url).ok();
+        let path = url.as_ref().map_or("/", |x| x.path());
+        let max_age = match login.is_empty() {
+            true => 0,
+            false => PENDING_TTL.as_secs(),
+        };
+        let secure = match url.as_ref().is_some_and(|x| x.scheme() == "https") {
+            true => "; Secure",
+            false => "",
+        };
+        format!(
+            "{}={}; Path={}; Max-Age={}; HttpOnly; SameSite=Lax{}",
+            LOGIN_COOKIE, login, path, max_age, secure
+        )
+    }
+
+    /// Issuer metadata, fetched on first use and kept afterwards
+    async fn discovery(&self) -> anyhow::Result<&Discovery> {
+        self.discovery
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer.trim_end_matches('/')
+                );
+                let discovery: Discovery = self
+                    .http
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context("malformed openid configuration")?;
+                Ok(discovery)
+            })
+            .await
+    }
+
+    /// Start a login, sealing its secrets with `key`
+    pub async fn authorize(&self, key: &SymmetricKey<V4>) -> anyhow::Result<Authorization> {
+        let discovery = self.discovery().await?;
+
+        let state = random_string()?;
+        let nonce = random_string()?;
+        let verifier = random_string()?;
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+        let mut url = Url::parse(&discovery.authorization_endpoint)?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("scope", &self.config.scopes)
+            .append_pair("state", &state)
+            .append_pair("nonce", &nonce)
+            .append_pair("code_challenge", &challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        let mut claims = Claims::new()?;
+        claims.set_expires_in(&PENDING_TTL)?;
+        claims.add_additional("state", state)?;
+        claims.add_additional("nonce", nonce)?;
+        claims.add_additional("verifier", verifier)?;
+        let cookie = local::encrypt(key, &claims, None, Some(LOGIN_ASSERTION))?;
+
+        Ok(Authorization {
+            url: url.into(),
+            cookie,
+        })
+    }
+
+    /// Finish a login from the callback of the issuer
+    ///
+    /// `cookie` is the sealed login of the browser, it must be the one `state` was issued with.
+    pub async fn exchange(
+        &self,
+        key: &SymmetricKey<V4>,
+        code: &str,
+        state: &str,
+        cookie: Option<&str>,
+    ) -> anyhow::Result<OidcIdentity> {
+        let pending = open_login(key, cookie, state)?;
+        let discovery = self.discovery().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.config.redirect_url),
+            ("client_id", &self.config.client_id),
+            ("code_verifier", &pending.verifier),
+        ];
+        if let Some(secret) = &self.config.client_secret {
+            form.push(("client_secret", secret));
+        }
+        let token: TokenResponse = self
+            .http
+            .post(&discovery.token_endpoint)
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()
+            .context("token endpoint rejected the code")?
+            .json()
+            .await
+            .context("malformed token response")?;
+
+        let mut claims = decode_claims(&token.id_token)?;
+        self.validate(discovery, &claims, &pending.nonce)?;
+
+        // userinfo may carry claims the ID token leaves out, the subject must match
+        if let (Some(endpoint), Some(access_token)) =
+            (&discovery.userinfo_endpoint, &token.access_token)
+        {
+            let userinfo: Map<String, Value> = self
+                .http
+                .get(endpoint)
+                .bearer_auth(access_token)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await
+                .context("malformed userinfo")?;
+            if userinfo.get("sub") != claims.get("sub") {
+                bail!("userinfo is of another subject");
+            }
+            claims.extend(userinfo);
+        }
+
+        self.identity(&claims)
+    }
+
+    fn validate(
+        &self,
+        discovery: &Discovery,
+        claims: &Map<String, Value>,
+        nonce: &str,
+    ) -> anyhow::Result<()> {
+        if claims.get("iss").and_then(Value::as_str) != Some(discovery.issuer.as_str()) {
+            bail!("ID token is from another issuer");
+        }
+        let audience = match claims.get("aud") {
+            Some(Value::String(aud)) => aud == &self.config.client_id,
+            Some(Value::Array(aud)) => aud.iter().any(|x| x == &self.config.client_id),
+            _ => false,
+        };
+        if !audience {
+            bail!("ID token is for another client");
+        }
+        let exp = claims.get("exp").and_then(Value::as_i64).unwrap_or(0);
+        if exp <= entity::patch::now() {
+            bail!("ID token is expired");
+        }
+        if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            bail!("ID token nonce mismatch");
+        }
+        Ok(())
+    }
+
+    fn identity(&self, claims: &Map<String, Value>) -> anyhow::Result<OidcIdentity> {
+        let subject = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .context("missing sub claim")?
+            .to_owned();
+        let username = claims
+            .get(&self.config.username_claim)
+            .and_then(Value::as_str)
+            .filter(|x| !x.is_empty())
+            .with_context(|| format!("missing {} claim", self.config.username_claim))?
+            .to_owned();
+
+        let role = self.config.role_claim.as_ref().map(|claim| {
+            let values = claim_values(claims, claim);
+            match values.iter().any(|x| self.config.admin_values.contains(x)) {
+                true => UserRole::Admin,
+                false => UserRole::Member,
+            }
+        });
+        let groups = (self.config.groups_claim.as_ref()).map(|claim| claim_values(claims, claim));
+
+        Ok(OidcIdentity {
+            subject,
+            username,
+            role,
+            groups,
+        })
+    }
+}
+
+/// Secrets of the login sealed in `cookie`, if it was started with `state`
+fn open_login(
+    key: &SymmetricKey<V4>,
+    cookie: Option<&str>,
+    state: &str,
+) -> anyhow::Result<Pending> {
+    const EXPIRED: &str = "unknown or expired login, please try again";
+    let token = UntrustedToken::try_from(cookie.context(EXPIRED)?).context(EXPIRED)?;
+    let token = local::decrypt(
+        key,
+        &token,
+        &ClaimsValidationRules::new(),
+        None,
+        Some(LOGIN_ASSERTION),
+    )
+    .context(EXPIRED)?;
+    let claims = token.payload_claims().context(EXPIRED)?;
+    let claim = |name: &str| {
+        claims
+            .get_claim(name)
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+            .context(EXPIRED)
+    };
+
+    if claim("state")? != state {
+        bail!("login was started in another browser, please try again");
+    }
+    Ok(Pending {
+        verifier: claim("verifier")?,
+        nonce: claim("nonce")?,
+    })
+}
+
+/// Payload of a JWT, without checking its signature
+fn decode_claims(jwt: &str) -> anyhow::Result<Map<String, Value>> {
+    let payload = jwt.split('.').nth(1).context("malformed ID token")?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .context("malformed ID token")?;
+    serde_json::from_slice(&payload).context("malformed ID token")
+}
+
+/// A claim as a list of strings, whether it's a single string or an array
+fn claim_values(claims: &Map<String, Value>, claim: &str) -> Vec<String> {
+    match claims.get(claim) {
+        Some(Value::String(x)) => vec![x.clone()],
+        Some(Value::Array(x)) => x
+            .iter()
+            .filter_map(|x| x.as_str().map(str::to_owned))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 32 random bytes from the OS, URL safe
+pub fn random_string() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|err| anyhow::anyhow!("no randomness: {}", err))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        Form, Json, Router,
+        routing::{get, post},
+    };
+    use pasetors::keys::Generate;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Issuer answering every code with an ID token for `alice`, nonce and verifier are echoed
+    /// from the last authorization request
+    async fn mock_issuer(nonce: Arc<Mutex<String>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        let issuer = base.clone();
+        let app = Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                get({
+                    let base = base.clone();
+                    move || async move {
+                        Json(json!({
+                            "issuer": base,
+                            "authorization_endpoint": format!("{}/authorize", base),
+                            "token_endpoint": format!("{}/token", base),
+                        }))
+                    }
+                }),
+            )
+            .route(
+                "/token",
+                post(
+                    move |Form(form): Form<HashMap<String, String>>| async move {
+                        assert_eq!(form["grant_type"], "authorization_code");
+                        assert_eq!(form["code"], "code-1");
+                        assert!(!form["code_verifier"].is_empty());
+                        let claims = json!({
+                            "iss": issuer,
+                            "aud": "llumen",
+                            "sub": "1234",
+                            "exp": entity::patch::now() + 60,
+                            "nonce": nonce.lock().unwrap().clone(),
+                            "preferred_username": "alice",
+                            "groups": ["staff", "llumen-admins"],
+                        });
+                        let jwt = format!(
+                            "{}.{}.",
+                            URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#),
+                            URL_SAFE_NO_PAD.encode(claims.to_string())
+                        );
+                        Json(json!({ "id_token": jwt, "access_token": "access" }))
+                    },
+                ),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        base
+    }
+
+    fn config(issuer: String) -> OidcConfig {
+        OidcConfig {
+            issuer,
+            client_id: "llumen".to_owned(),
+            client_secret: None,
+            redirect_url: "http://localhost/api/auth/oidc/callback".to_owned(),
+            scopes: "openid".to_owned(),
+            username_claim: "preferred_username".to_owned(),
+            role_claim: Some("groups".to_owned()),
+            admin_values: vec!["llumen-admins".to_owned()],
+            groups_claim: Some("groups".to_owned()),
+        }
+    }
+
+    /// Query of the authorization URL
+    fn query(login: &Authorization) -> HashMap<String, String> {
+        let url = Url::parse(&login.url).unwrap();
+        url.query_pairs().into_owned().collect()
+    }
+
+    #[tokio::test]
+    async fn login_with_mock_issuer() {
+        let key = SymmetricKey::<V4>::generate().unwrap();
+        let nonce = Arc::new(Mutex::new(String::new()));
+        let oidc = Oidc::new(config(mock_issuer(nonce.clone()).await));
+
+        let login = oidc.authorize(&key).await.unwrap();
+        let query = query(&login);
+        assert_eq!(query["code_challenge_method"], "S256");
+        assert!(!login.cookie.contains(&query["state"]));
+        *nonce.lock().unwrap() = query["nonce"].clone();
+
+        let identity = oidc
+            .exchange(&key, "code-1", &query["state"], Some(&login.cookie))
+            .await
+            .unwrap();
+        assert_eq!(
+            identity,
+            OidcIdentity {
+                subject: "1234".to_owned(),
+                username: "alice".to_owned(),
+                role: Some(UserRole::Admin),
+                groups: Some(vec!["staff".to_owned(), "llumen-admins".to_owned()]),
+            }
+        );
+
+        // the callback of a login started by another browser
+        let other = oidc.authorize(&key).await.unwrap();
+        assert!(
+            oidc.exchange(&key, "code-1", &query["state"], Some(&other.cookie))
+                .await
+                .is_err()
+        );
+        assert!(
+            oidc.exchange(&key, "code-1", &query["state"], None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_wrong_nonce() {
+        let key = SymmetricKey::<V4>::generate().unwrap();
+        let nonce = Arc::new(Mutex::new("forged".to_owned()));
+        let oidc = Oidc::new(config(mock_issuer(nonce).await));
+
+        let login = oidc.authorize(&key).await.unwrap();
+        let state = query(&login)["state"].clone();
+        assert!(
+            oidc.exchange(&key, "code-1", &state, Some(&login.cookie))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn cookie_attributes() {
+        let oidc = Oidc::new(config("https://auth.example.com".to_owned()));
+        assert_eq!(
+            oidc.cookie("v4.local.x"),
+            "llumen_oidc=v4.local.x; Path=/api/auth/oidc/callback; Max-Age=600; HttpOnly; SameSite=Lax"
+        );
+        assert!(oidc.cookie("").contains("Max-Age=0"));
+    }
+}
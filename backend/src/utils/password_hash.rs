@@ -1,3 +1,5 @@
+use std::sync::OnceLock;
+
 use argon2;
 
 const SALT_LEN: usize = 16;
@@ -5,12 +7,20 @@ const SALT_LEN: usize = 16;
 #[derive(Default)]
 pub struct Hasher {
     config: argon2::Config<'static>,
+    /// hash checked when there is no user, see [`Hasher::verify_nobody`]
+    dummy: OnceLock<String>,
 }
 
 impl Hasher {
     pub fn verify_password(&self, hash: &str, password: &str) -> bool {
         return argon2::verify_encoded(&hash, password.as_bytes()).unwrap();
     }
+    /// Take as long as [`Hasher::verify_password`] for a username that doesn't exist, so
+    /// response times don't tell which usernames do
+    pub fn verify_nobody(&self, password: &str) {
+        let hash = self.dummy.get_or_init(|| self.hash_unusable());
+        self.verify_password(hash, password);
+    }
     pub fn hash_password(&self, password: &str) -> String {
         let salt = {
             (0..SALT_LEN)
@@ -22,4 +32,12 @@ impl Hasher {
 
         return hash;
     }
+    /// Hash of a random secret nobody knows, for users who only log in through SSO
+    pub fn hash_unusable(&self) -> String {
+        let mut secret = [0u8; 32];
+        getrandom::fill(&mut secret).expect("no randomness from the OS");
+        let salt = (0..SALT_LEN).map(|_| fastrand::u8(..)).collect::<Vec<u8>>();
+
+        argon2::hash_encoded(&secret, &salt, &self.config).unwrap()
+    }
 }
@@ -0,0 +1,116 @@
+//! Rules a new password has to meet
+//!
+//! Applied wherever a password is set: user creation, password changes and the forced change on
+//! login. Existing passwords are never checked, so tightening the policy doesn't lock anyone out.
+
+use std::{collections::HashSet, env::var, fs};
+
+const DEFAULT_MIN_LENGTH: usize = 8;
+/// Hashing cost grows with length, long inputs are rejected before reaching argon2
+const MAX_LENGTH: usize = 256;
+
+/// Always rejected, including the seeded default password
+const COMMON: &[&str] = &[
+    "P@88w0rd",
+    "password",
+    "password1",
+    "passw0rd",
+    "p@ssw0rd",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "qwertyuiop",
+    "iloveyou",
+    "admin123",
+    "letmein1",
+    "welcome1",
+    "changeme",
+];
+
+#[derive(Debug)]
+pub struct PasswordPolicy {
+    min_length: usize,
+    breached: HashSet<String>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: DEFAULT_MIN_LENGTH,
+            breached: COMMON.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Read `PASSWORD_MIN_LENGTH` and `PASSWORD_BREACHED_LIST` from environment
+    ///
+    /// The breached list is a local text file with one password per line.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+
+        if let Ok(x) = var("PASSWORD_MIN_LENGTH") {
+            match x.parse() {
+                Ok(x) => policy.min_length = x,
+                Err(_) => log::warn!("Invalid PASSWORD_MIN_LENGTH {}, using default", x),
+            }
+        }
+
+        if let Ok(path) = var("PASSWORD_BREACHED_LIST") {
+            match fs::read_to_string(&path) {
+                Ok(content) => {
+                    policy.breached.extend(
+                        content
+                            .lines()
+                            .map(|x| x.trim_end_matches('\r'))
+                            .filter(|x| !x.is_empty())
+                            .map(ToOwned::to_owned),
+                    );
+                    log::info!("Loaded {} breached passwords", policy.breached.len());
+                }
+                Err(err) => log::warn!("Cannot read PASSWORD_BREACHED_LIST {}: {}", path, err),
+            }
+        }
+
+        policy
+    }
+
+    /// Check a new password of `username`, the error is shown to the user
+    pub fn check(&self, username: &str, password: &str) -> Result<(), String> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Err(format!(
+                "password must be at least {} characters",
+                self.min_length
+            ));
+        }
+        if length > MAX_LENGTH {
+            return Err(format!(
+                "password must be at most {} characters",
+                MAX_LENGTH
+            ));
+        }
+        if password.eq_ignore_ascii_case(username) {
+            return Err("password must not be the username".to_owned());
+        }
+        if self.breached.contains(password) {
+            return Err("password is too common, it appears in known data breaches".to_owned());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_weak_passwords() {
+        let policy = PasswordPolicy::default();
+        assert!(policy.check("alice", "short").is_err());
+        assert!(policy.check("alice1234", "ALICE1234").is_err());
+        assert!(policy.check("admin", "P@88w0rd").is_err());
+        assert!(policy.check("alice", &"a".repeat(MAX_LENGTH + 1)).is_err());
+        assert!(policy.check("alice", "correct horse battery").is_ok());
+    }
+}
@@ -0,0 +1,43 @@
+//! Encryption of secrets stored in database(provider API keys)
+//!
+//! Secrets are sealed as PASETO v4.local tokens with `provider_key` from config table.
+
+use anyhow::Context;
+use pasetors::{keys::SymmetricKey, token::UntrustedToken, version4::V4};
+
+pub fn encrypt(key: &SymmetricKey<V4>, secret: &str) -> anyhow::Result<Vec<u8>> {
+    // PASETO doesn't accept empty message, some endpoints(ollama) don't need a key
+    if secret.is_empty() {
+        return Ok(Vec::new());
+    }
+    let token = pasetors::version4::LocalToken::encrypt(key, secret.as_bytes(), None, None)
+        .context("cannot encrypt secret")?;
+    Ok(token.into_bytes())
+}
+
+pub fn decrypt(key: &SymmetricKey<V4>, sealed: &[u8]) -> anyhow::Result<String> {
+    if sealed.is_empty() {
+        return Ok(String::new());
+    }
+    let token = std::str::from_utf8(sealed).context("malformed secret")?;
+    let token = UntrustedToken::try_from(token).context("malformed secret")?;
+    let token = pasetors::version4::LocalToken::decrypt(key, &token, None, None)
+        .context("cannot decrypt secret, is provider_key changed?")?;
+    Ok(token.payload().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use pasetors::keys::Generate;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let key = SymmetricKey::<V4>::generate().unwrap();
+        let sealed = encrypt(&key, "sk-or-v1-secret").unwrap();
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+        assert_eq!(decrypt(&key, &sealed).unwrap(), "sk-or-v1-secret");
+        assert_eq!(decrypt(&key, &encrypt(&key, "").unwrap()).unwrap(), "");
+    }
+}
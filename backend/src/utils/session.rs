@@ -0,0 +1,90 @@
+//! Sessions back every issued token, so a token can be revoked before it expires
+
+use entity::{prelude::*, session, user};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, DatabaseConnection, DbErr, QueryTrait, prelude::*,
+};
+
+/// Length of the random `jti` claim
+const JTI_LEN: usize = 32;
+
+/// Record a new session, returns its `jti`
+pub async fn create(
+    conn: &DatabaseConnection,
+    user_id: i32,
+    user_agent: Option<String>,
+    expires_at: i64,
+) -> Result<String, DbErr> {
+    let now = entity::patch::now();
+
+    // expired sessions are of no use, drop them while we are here
+    Session::delete_many()
+        .filter(session::Column::UserId.eq(user_id))
+        .filter(session::Column::ExpiresAt.lt(now))
+        .exec(conn)
+        .await?;
+
+    let jti = (0..JTI_LEN)
+        .map(|_| fastrand::alphanumeric())
+        .collect::<String>();
+    session::ActiveModel {
+        jti: Set(jti.clone()),
+        user_id: Set(user_id),
+        user_agent: Set(user_agent),
+        created_at: Set(now),
+        expires_at: Set(expires_at),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    Ok(jti)
+}
+
+/// Session of a `jti` and its user, if it's neither revoked nor expired
+pub async fn find_active(
+    conn: &DatabaseConnection,
+    jti: &str,
+) -> Result<Option<(session::Model, user::Model)>, DbErr> {
+    let res = Session::find()
+        .filter(session::Column::Jti.eq(jti))
+        .filter(session::Column::RevokedAt.is_null())
+        .filter(session::Column::ExpiresAt.gt(entity::patch::now()))
+        .find_also_related(User)
+        .one(conn)
+        .await?;
+    Ok(res.and_then(|(session, user)| Some((session, user?))))
+}
+
+/// Revoke a single session
+pub async fn revoke(conn: &DatabaseConnection, session_id: i32) -> Result<(), DbErr> {
+    Session::update_many()
+        .col_expr(
+            session::Column::RevokedAt,
+            Expr::value(entity::patch::now()),
+        )
+        .filter(session::Column::Id.eq(session_id))
+        .filter(session::Column::RevokedAt.is_null())
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+/// Revoke every session of a user but `except`, returns how many were revoked
+pub async fn revoke_all(
+    conn: &impl ConnectionTrait,
+    user_id: i32,
+    except: Option<i32>,
+) -> Result<u64, DbErr> {
+    let res = Session::update_many()
+        .col_expr(
+            session::Column::RevokedAt,
+            Expr::value(entity::patch::now()),
+        )
+        .filter(session::Column::UserId.eq(user_id))
+        .filter(session::Column::RevokedAt.is_null())
+        .apply_if(except, |q, id| q.filter(session::Column::Id.ne(id)))
+        .exec(conn)
+        .await?;
+    Ok(res.rows_affected)
+}
@@ -2,9 +2,20 @@ import { token } from '$lib/store';
 import { page } from '$app/state';
 import { goto } from '$app/navigation';
 import { CreateMutation, type CreateMutationResult } from './state';
-import { APIFetch } from './state/errorHandle';
+import { APIFetch, apiBase } from './state/errorHandle';
+import { dispatchError } from '$lib/error';
 
-import type { LoginReq, LoginResp, RenewResp, RenewReq, HeaderAuthResp } from './types';
+import type {
+	LoginReq,
+	LoginResp,
+	RenewResp,
+	RenewReq,
+	HeaderAuthResp,
+	OidcInfoReq,
+	OidcInfoResp,
+	SessionLogoutReq,
+	SessionLogoutResp
+} from './types';
 import { onDestroy } from 'svelte';
 
 export interface User {
@@ -44,6 +55,11 @@ export async function RenewToken(originalToken: string) {
 	}
 }
 
+export async function Logout() {
+	await APIFetch<SessionLogoutResp, SessionLogoutReq>('session/logout', {});
+	token.set(undefined);
+}
+
 export async function TryHeaderAuth() {
 	const res = await APIFetch<HeaderAuthResp>('auth/header');
 
@@ -60,6 +76,38 @@ export async function TryHeaderAuth() {
 	}
 }
 
+export async function OidcEnabled() {
+	const res = await APIFetch<OidcInfoResp, OidcInfoReq>('auth/oidc', {});
+	return res?.enabled ?? false;
+}
+
+export const oidcStartUrl = apiBase + 'auth/oidc/start';
+
+/** Pick up the result of an OpenID Connect login, handed over in the URL fragment */
+export function TryOidcCallback() {
+	const params = new URLSearchParams(window.location.hash.slice(1));
+	const value = params.get('token');
+	const exp = params.get('exp');
+	const error = params.get('error');
+	if (!value && !error) return;
+
+	history.replaceState(null, '', window.location.pathname + window.location.search);
+
+	if (error) {
+		dispatchError('login_fail', error);
+	} else if (value && exp) {
+		const now = new Date();
+		const expireAt = new Date(exp);
+		const renewAt = new Date(now.getTime() + (expireAt.getTime() - now.getTime()) / 2);
+
+		token.set({
+			value,
+			expireAt: expireAt.toString(),
+			renewAt: renewAt.toString()
+		});
+	}
+}
+
 export function initAuth() {
 	const unsubscribers = [
 		token.subscribe((token) => {
@@ -13,9 +13,13 @@ import type {
 	ChatDeleteResp,
 	ChatUpdateReq,
 	ChatUpdateResp,
+	ChatSearchReq,
+	ChatSearchResp,
+	ChatExportReq,
+	ImportSummary,
 	MessageCreateReqFile
 } from './types';
-import { ChatPaginateReqOrder, ChatMode } from './types';
+import { ChatPaginateReqOrder, ChatMode, ChatExportFormat } from './types';
 import {
 	CreateInfiniteQuery,
 	CreateMutation,
@@ -27,11 +31,12 @@ import {
 	type QueryResult,
 	type RawMutationResult
 } from './state';
-import { APIFetch } from './state/errorHandle';
+import { APIFetch, RawAPIFetch } from './state/errorHandle';
 import type { MutationResult } from './state/mutate';
 import { UpdateInfiniteQueryDataById } from './state';
 import { upload } from './files';
 import { pushUserMessage } from './message.svelte';
+import { dispatchError } from '$lib/error';
 
 export interface CreateRoomRequest {
 	message: string;
@@ -85,7 +90,9 @@ export function createRoom(): RawMutationResult<CreateRoomRequest, ChatCreateRes
 				key: ['chatPaginate'],
 				data: {
 					id: chatId,
-					model_id: param.modelId
+					model_id: param.modelId,
+					created_at: Math.floor(Date.now() / 1000),
+					updated_at: Math.floor(Date.now() / 1000)
 				}
 			});
 
@@ -181,3 +188,37 @@ export function updateRoomTitle(id: number, title: string) {
 		id
 	});
 }
+
+// Ranked matches in messages and titles of the current user's chats
+export function searchChats(query: string) {
+	return APIFetch<ChatSearchResp, ChatSearchReq>('chat/search', { query });
+}
+
+const exportExtension: Record<ChatExportFormat, string> = {
+	[ChatExportFormat.Markdown]: 'md',
+	[ChatExportFormat.Html]: 'html',
+	[ChatExportFormat.Json]: 'json'
+};
+
+// Download a chat as a file named after its title
+export async function exportChat(id: number, format: ChatExportFormat, title: string) {
+	const response = await RawAPIFetch<ChatExportReq>('chat/export', { id, format });
+	if (!response.ok) {
+		dispatchError('network', 'Fail to export');
+		return;
+	}
+
+	const url = URL.createObjectURL(await response.blob());
+	const link = document.createElement('a');
+	link.href = url;
+	link.download = `${title.replace(/[\\/:*?"<>|]/g, '_')}.${exportExtension[format]}`;
+	link.click();
+	URL.revokeObjectURL(url);
+}
+
+// Import chats from a ChatGPT, Claude or OpenWebUI export
+export function importChats(file: File) {
+	const formData = new FormData();
+	formData.append('file', file);
+	return APIFetch<ImportSummary, FormData>('chat/import', formData);
+}
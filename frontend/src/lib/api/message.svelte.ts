@@ -3,11 +3,14 @@ import oboe from 'oboe';
 
 import { APIFetch, getError, RawAPIFetch } from './state/errorHandle';
 
-import { CreateMutation, CreateRawMutation } from './state';
-import type { MutationResult, RawMutationResult } from './state/mutate';
+import { CreateMutation } from './state';
+import type { MutationResult } from './state/mutate';
 import type {
-	MessageDeleteReq,
 	MessageCreateReq,
+	MessageEditReq,
+	MessageRegenerateReq,
+	MessageSelectReq,
+	MessageSelectResp,
 	MessageCreateResp,
 	MessagePaginateResp,
 	MessagePaginateReq,
@@ -88,6 +91,9 @@ const Handlers: {
 			},
 			token_count: 0,
 			price: 0,
+			siblings: [],
+			created_at: Math.floor(Date.now() / 1000),
+			updated_at: Math.floor(Date.now() / 1000),
 			stream: true
 		};
 		pushMessage(message);
@@ -169,6 +175,12 @@ const Handlers: {
 		cursor!.offset = 0;
 	},
 
+	model() {
+		// model answering, nothing to render yet
+		cursor!.index++;
+		cursor!.offset = 0;
+	},
+
 	error(err) {
 		const firstMsg = messages[0] as AssistantMessage;
 
@@ -489,6 +501,9 @@ export function pushUserMessage(user_id: number, content: string, files: FileMet
 		},
 		token_count: 0,
 		price: 0,
+		siblings: [],
+		created_at: Math.floor(Date.now() / 1000),
+		updated_at: Math.floor(Date.now() / 1000),
 		stream: true
 	});
 }
@@ -502,22 +517,29 @@ export function createMessage(): MutationResult<MessageCreateReq, MessageCreateR
 	});
 }
 
-export function updateMessage(): RawMutationResult<
-	MessageCreateReq & { msgId: number },
-	MessageCreateResp
-> {
-	const { mutate: create } = createMessage();
-	return CreateRawMutation({
-		mutator: (param) => {
-			return new Promise(async (resolve, reject) => {
-				await APIFetch<MessageDeleteReq, MessageDeleteReq>('message/delete', {
-					id: param.msgId
-				});
-
-				messages = messages.filter((x) => x.id < param.msgId);
-
-				await create(param, resolve);
-			});
+// Editing adds a sibling of the user message, the old branch stays reachable by selectBranch
+export function editMessage(): MutationResult<MessageEditReq, MessageCreateResp> {
+	return CreateMutation({
+		path: 'message/edit',
+		onSuccess: (data, param) => {
+			messages = messages.filter((x) => x.id < param.id);
+			pushUserMessage(data.user_id, param.text, param.files || []);
 		}
 	});
 }
+
+// Regenerating adds an alternative reply to the same user message
+export function regenerateMessage(): MutationResult<MessageRegenerateReq, MessageCreateResp> {
+	return CreateMutation({
+		path: 'message/regenerate',
+		onSuccess: (_, param) => {
+			messages = messages.filter((x) => x.id < param.id);
+		}
+	});
+}
+
+// Show the branch going through a message, usually a sibling of the current one
+export async function selectBranch(chatId: number, id: number) {
+	const res = await APIFetch<MessageSelectResp, MessageSelectReq>('message/select', { id });
+	if (res != undefined) await syncMessages(chatId);
+}
@@ -2,6 +2,59 @@
  Generated by typeshare 1.13.3
 */
 
+/** What an API key may be used for */
+export enum ApiKeyScope {
+	/** Everything the user can do, except managing sessions and API keys */
+	Full = 'full',
+	/** Chatting, along with everything `read` allows */
+	Chat = 'chat',
+	/** Reading chats, models and usage, nothing is changed */
+	Read = 'read'
+}
+
+export interface ApiKeyCreateReq {
+	name: string;
+	/** Defaults to `full` */
+	scope?: ApiKeyScope;
+	/** unix timestamp in seconds, the key never expires if omitted */
+	expires_at?: number;
+}
+
+export interface ApiKeyCreateResp {
+	id: number;
+	/** The key, it cannot be read again */
+	key: string;
+}
+
+export interface ApiKeyList {
+	id: number;
+	name: string;
+	/** Start of the key, like `llm_AbCd1234` */
+	prefix: string;
+	scope: ApiKeyScope;
+	/** unix timestamp in seconds */
+	created_at: number;
+	/** unix timestamp in seconds */
+	expires_at?: number;
+	/** unix timestamp in seconds */
+	last_used_at?: number;
+	/** Cost of completions requested with this key */
+	cost: number;
+	token: number;
+}
+
+export interface ApiKeyListReq {}
+
+export interface ApiKeyListResp {
+	list: ApiKeyList[];
+}
+
+export interface ApiKeyRevokeReq {
+	id: number;
+}
+
+export interface ApiKeyRevokeResp {}
+
 export enum ChatMode {
 	Normal = 'normal',
 	Search = 'search',
@@ -25,6 +78,20 @@ export interface ChatDeleteResp {
 	deleted: boolean;
 }
 
+export enum ChatExportFormat {
+	/** The selected branch, files are referenced by name */
+	Markdown = 'markdown',
+	/** The selected branch as a single page with files inlined */
+	Html = 'html',
+	/** Every branch and file, see `ChatExport` */
+	Json = 'json'
+}
+
+export interface ChatExportReq {
+	id: number;
+	format: ChatExportFormat;
+}
+
 export interface ChatHaltReq {
 	id: number;
 }
@@ -38,6 +105,12 @@ export enum ChatPaginateReqOrder {
 	Lt = 'lt'
 }
 
+export enum ChatPaginateReqSort {
+	Id = 'id',
+	/** last activity, `id` is still the cursor */
+	Activity = 'activity'
+}
+
 export interface ChatPaginateReqLimit {
 	/**
 	 * Default to the beginning
@@ -47,6 +120,8 @@ export interface ChatPaginateReqLimit {
 	id?: number;
 	order: ChatPaginateReqOrder;
 	limit?: number;
+	/** Default to id */
+	sort?: ChatPaginateReqSort;
 }
 
 /**
@@ -62,6 +137,10 @@ export interface ChatPaginateRespList {
 	id: number;
 	model_id?: number;
 	title?: string;
+	/** unix timestamp in seconds */
+	created_at: number;
+	/** unix timestamp in seconds of the last activity */
+	updated_at: number;
 }
 
 export interface ChatPaginateResp {
@@ -78,6 +157,30 @@ export interface ChatReadResp {
 	title?: string;
 }
 
+export interface ChatSearchHit {
+	chat_id: number;
+	/** null when the title matched */
+	message_id?: number;
+	title?: string;
+	/**
+	 * Text around the match, matched words are wrapped in `<mark>` and `</mark>`
+	 *
+	 * The rest is raw text and has to be escaped before rendering as HTML.
+	 */
+	snippet: string;
+}
+
+export interface ChatSearchReq {
+	/** Words to look for, the last one also matches as a prefix */
+	query: string;
+	/** Default to 20, at most 100 */
+	limit?: number;
+}
+
+export interface ChatSearchResp {
+	list: ChatSearchHit[];
+}
+
 export interface ChatUpdateReq {
 	chat_id: number;
 	title?: string;
@@ -151,6 +254,11 @@ export enum ErrorKind {
 	 * Frontend should redirect to login page.
 	 */
 	Unauthorized = 'unauthorized',
+	/**
+	 * User is authenticated but lacks the role required for the action.
+	 * Frontend should hide admin features from members.
+	 */
+	Forbidden = 'forbidden',
 	/**
 	 * Session token is malformed, expired, or tampered with.
 	 * Usually means the user needs to log in again.
@@ -194,7 +302,27 @@ export enum ErrorKind {
 	 * - Tool rejected input parameters
 	 * Frontend should show tool-specific error context.
 	 */
-	ToolCallFail = 'tool_call_fail'
+	ToolCallFail = 'tool_call_fail',
+	/**
+	 * User has used up a spending budget set by an admin.
+	 * Frontend should show the reason, new messages are rejected until the budget resets.
+	 */
+	QuotaExceeded = 'quota_exceeded',
+	/**
+	 * Too many failed logins for the username or from the client.
+	 * Frontend should show the reason, which tells how long to wait or that an admin must unlock.
+	 */
+	TooManyAttempts = 'too_many_attempts',
+	/**
+	 * Password is correct but has to be changed before logging in.
+	 * Frontend should ask for a new password and log in again with `new_password`.
+	 */
+	PasswordChangeRequired = 'password_change_required',
+	/**
+	 * New password doesn't meet the password policy.
+	 * Frontend should show the reason and ask for another password.
+	 */
+	WeakPassword = 'weak_password'
 }
 
 /**
@@ -239,9 +367,20 @@ export interface HeaderAuthResp {
 	exp?: string;
 }
 
+/** What an import added */
+export interface ImportSummary {
+	chats: number;
+	messages: number;
+	files: number;
+	/** Attachments referenced by the export but not included in it, or too large to import */
+	missing_files: number;
+}
+
 export interface LoginReq {
 	username: string;
 	password: string;
+	/** Required when login fails with `password_change_required` */
+	new_password?: string;
 }
 
 export interface LoginResp {
@@ -271,6 +410,15 @@ export interface MessageDeleteReq {
 	id: number;
 }
 
+export interface MessageEditReq {
+	/** User message to edit */
+	id: number;
+	model_id: number;
+	mode: ChatMode;
+	text: string;
+	files: MessageCreateReqFile[];
+}
+
 export enum MessagePaginateReqOrder {
 	/** greater than */
 	Gt = 'gt',
@@ -311,10 +459,66 @@ export interface MessagePaginateRespList {
 	token_count: number;
 	price: number;
 	inner: MessageInner;
+	parent_id?: number;
+	/** Alternatives of this message including itself, oldest first */
+	siblings: number[];
+	/** unix timestamp in seconds */
+	created_at: number;
+	/** unix timestamp in seconds */
+	updated_at: number;
 }
 
 export interface MessagePaginateResp {
 	list: MessagePaginateRespList[];
+	/**
+	 * Estimated tokens of the selected branch if sent to its model again
+	 *
+	 * Only counted on the first page, a `limit` request without `id`.
+	 */
+	chat_token_count?: number;
+}
+
+export interface MessageRegenerateReq {
+	/** Assistant message to regenerate */
+	id: number;
+	/** Default to the model of the chat */
+	model_id?: number;
+	/** Default to the mode of the chat */
+	mode?: ChatMode;
+}
+
+export interface MessageSelectReq {
+	/** Message to show, usually a sibling of a message in the current branch */
+	id: number;
+}
+
+export interface MessageSelectResp {
+	/** Last message of the newly selected branch */
+	leaf_id: number;
+}
+
+/** Who can use a model besides admins, a model without any entry is open to everyone */
+export interface ModelAccess {
+	users?: number[];
+	/** Group names, matched against the groups of a user */
+	groups?: string[];
+}
+
+export interface ModelCacheReq {}
+
+/** State of the OpenRouter model metadata cache */
+export interface ModelCacheResp {
+	/** RFC 3339, omitted if never refreshed */
+	refreshed_at?: string;
+	count: number;
+}
+
+export interface ModelCapabilities {
+	text: boolean;
+	vision: boolean;
+	image_gen: boolean;
+	files: boolean;
+	audio: boolean;
 }
 
 export interface ModelCheckReq {
@@ -327,6 +531,8 @@ export interface ModelCheckResp {
 
 export interface ModelCreateReq {
 	config: string;
+	/** Default to everyone */
+	access?: ModelAccess;
 }
 
 export interface ModelCreateResp {
@@ -352,6 +558,28 @@ export interface ModelIdsResp {
 	ids: string[];
 }
 
+/** Upstream API used to serve a model. */
+export enum ProviderType {
+	OpenAI = 'openai',
+	Anthropic = 'anthropic',
+	Google = 'google',
+	OpenRouter = 'openrouter'
+}
+
+export interface ModelPricing {
+	input_cost_per_1m: number;
+	output_cost_per_1m: number;
+}
+
+export interface ModelInfo {
+	id: string;
+	display_name: string;
+	provider: ProviderType;
+	capabilities: ModelCapabilities;
+	pricing?: ModelPricing;
+	context_window?: number;
+}
+
 export interface ModelList {
 	id: number;
 	display_name: string;
@@ -373,11 +601,15 @@ export interface ModelReadReq {
 
 export interface ModelReadResp {
 	raw: string;
+	/** only sent to admins */
+	access?: ModelAccess;
 }
 
 export interface ModelWriteReq {
 	id: number;
 	config: string;
+	/** Keep the current access if omitted */
+	access?: ModelAccess;
 }
 
 export interface ModelWriteResp {
@@ -385,6 +617,83 @@ export interface ModelWriteResp {
 	wrote: boolean;
 }
 
+export interface OidcInfoReq {}
+
+export interface OidcInfoResp {
+	enabled: boolean;
+}
+
+export interface ProviderCreateReq {
+	name: string;
+	provider_type: ProviderType;
+	base_url?: string;
+	api_key: string;
+	/** openrouter plugins, detected from base url if not set */
+	plugins?: boolean;
+	/** usage accounting, detected from base url if not set */
+	usage?: boolean;
+}
+
+export interface ProviderCreateResp {
+	id: number;
+}
+
+export interface ProviderDeleteReq {
+	id: number;
+}
+
+export interface ProviderDeleteResp {
+	deleted: boolean;
+}
+
+export interface ProviderListReq {}
+
+/** API key is never sent back to client */
+export interface ProviderReadResp {
+	id: number;
+	name: string;
+	provider_type: ProviderType;
+	base_url?: string;
+	has_api_key: boolean;
+	plugins?: boolean;
+	usage?: boolean;
+}
+
+export interface ProviderListResp {
+	list: ProviderReadResp[];
+}
+
+export interface ProviderModelsReq {
+	provider_id: number;
+}
+
+export interface ProviderModelsResp {
+	models: ModelInfo[];
+	/** Why the provider couldn't be reached, `models` is the built-in list then */
+	error?: string;
+}
+
+export interface ProviderReadReq {
+	id: number;
+}
+
+export interface ProviderWriteReq {
+	id: number;
+	name: string;
+	provider_type: ProviderType;
+	base_url?: string;
+	/** keep the stored key if not set */
+	api_key?: string;
+	/** openrouter plugins, detected from base url if not set */
+	plugins?: boolean;
+	/** usage accounting, detected from base url if not set */
+	usage?: boolean;
+}
+
+export interface ProviderWriteResp {
+	wrote: boolean;
+}
+
 export interface RenewReq {
 	token: string;
 }
@@ -398,6 +707,36 @@ export interface Resp {
 	deleted: boolean;
 }
 
+export interface SessionList {
+	id: number;
+	user_agent?: string;
+	/** unix timestamp in seconds */
+	created_at: number;
+	/** unix timestamp in seconds */
+	expires_at: number;
+	/** whether this is the session making the request */
+	current: boolean;
+}
+
+export interface SessionListReq {}
+
+export interface SessionListResp {
+	list: SessionList[];
+}
+
+export interface SessionLogoutAllReq {
+	/** Stay logged in on the device making the request */
+	keep_current?: boolean;
+}
+
+export interface SessionLogoutAllResp {
+	revoked: number;
+}
+
+export interface SessionLogoutReq {}
+
+export interface SessionLogoutResp {}
+
 export interface SseCursor {
 	index: number;
 	offset: number;
@@ -435,9 +774,57 @@ export interface SseStart {
 	version: number;
 }
 
+export interface UsageReportReq {
+	/** First day of the range, `YYYY-MM-DD` in UTC */
+	from: string;
+	/** Last day of the range(inclusive), `YYYY-MM-DD` in UTC */
+	to: string;
+	/** Only report usage of this user, default to every user, members always get their own */
+	user_id?: number;
+}
+
+/** Usage of one user with one model and mode on one day */
+export interface UsageReportRow {
+	/** `YYYY-MM-DD` in UTC */
+	day: string;
+	user_id: number;
+	username: string;
+	/** null if the model was deleted */
+	model_id?: number;
+	model_name?: string;
+	mode: ChatMode;
+	cost: number;
+	token: number;
+	/** number of completions */
+	count: number;
+}
+
+export interface UsageReportResp {
+	list: UsageReportRow[];
+}
+
+/** Spending limits of a user, unset limits are unlimited */
+export interface UserBudget {
+	daily_cost?: number;
+	daily_token?: number;
+	monthly_cost?: number;
+	monthly_token?: number;
+}
+
+/** What a user is allowed to manage */
+export enum UserRole {
+	/** Manages users, models and providers */
+	Admin = 'admin',
+	Member = 'member'
+}
+
 export interface UserCreateReq {
 	username: string;
 	password: string;
+	/** Default to member */
+	role?: UserRole;
+	/** Ask for a new password on first login, default to false */
+	must_change_password?: boolean;
 }
 
 export interface UserCreateResp {
@@ -455,6 +842,12 @@ export interface UserDeleteResp {
 export interface UserList {
 	id: number;
 	name: string;
+	role: UserRole;
+	groups: string[];
+	display_name?: string;
+	email?: string;
+	/** Locked after too many failed logins, see `/api/user/unlock` */
+	locked: boolean;
 }
 
 export interface UserListReq {}
@@ -470,7 +863,7 @@ export interface UserPreference {
 }
 
 export interface UserReadReq {
-	/** If omit will use the current user instead */
+	/** If omit will use the current user instead, only admins can read other users */
 	user_id?: number;
 }
 
@@ -478,13 +871,41 @@ export interface UserReadResp {
 	user_id: number;
 	username: string;
 	preference: UserPreference;
+	budget: UserBudget;
+	role: UserRole;
+	groups: string[];
+	display_name?: string;
+	email?: string;
+}
+
+export interface UserUnlockReq {
+	user_id: number;
+}
+
+export interface UserUnlockResp {
+	/** Whether the user was locked */
+	unlocked: boolean;
 }
 
 export interface UserUpdateReq {
-	/** If omit will use the current user instead */
+	/** If omit will use the current user instead, only admins can update other users */
 	user_id?: number;
 	preference?: UserPreference;
 	password?: string;
+	/** Required to change your own password */
+	current_password?: string;
+	/**
+	 * Ask for a new password on next login and log the user out, admin only
+	 *
+	 * Setting a password clears it unless this is set to true.
+	 */
+	force_password_change?: boolean;
+	/** Replaces the whole budget, unset limits are removed, admin only */
+	budget?: UserBudget;
+	/** Admin only, admins cannot change their own role */
+	role?: UserRole;
+	/** Replaces the groups used by model access, admin only */
+	groups?: string[];
 }
 
 export interface UserUpdateResp {
@@ -495,6 +916,7 @@ export type ChatPaginateReq =
 	| { t: 'limit'; c: ChatPaginateReqLimit }
 	| { t: 'range'; c: ChatPaginateReqRange };
 
+/** Only messages of the selected branch are returned, see `/api/message/select` */
 export type MessagePaginateReq =
 	| { t: 'limit'; c: MessagePaginateReqLimit }
 	| { t: 'range'; c: MessagePaginateReqRange };
@@ -530,6 +952,8 @@ export type MessagePaginateReq =
  * - `ToolResult(SseRespToolResult)` / `DeepStepToolResult(SseRespToolResult)`: tool outputs.
  * - `Start(SseStart)`: indicates the beginning of processing for a new assistant message.
  * - `Title(String)`: an updated or generated title for the chat.
+ * - `Model(String)`: id of the model answering, sent before its answer starts and again when a
+ * fallback model takes over.
  * - `Error(String)`: an error message to surface to the client.
  *
  * Important: the client should treat text-bearing variants (`Token`, `Reasoning`,
@@ -545,6 +969,7 @@ export type SseResp =
 	| { t: 'tool_result'; c: SseRespToolResult }
 	| { t: 'complete'; c: SseRespMessageComplete }
 	| { t: 'title'; c: string }
+	| { t: 'model'; c: string }
 	| { t: 'error'; c: string }
 	| { t: 'start'; c: SseStart }
 	| { t: 'deep_plan'; c: string }
@@ -32,7 +32,9 @@ export function initError() {
 	// dispatch error
 	const error = useError();
 	const unsubscriber = error.subscribe((error) => {
-		if (error?.error == 'malformed_token') token.set(undefined);
+		// revoked sessions and deleted users are unauthorized
+		if (error?.error == 'malformed_token' || error?.error == 'unauthorized')
+			token.set(undefined);
 	});
 
 	onDestroy(() => unsubscriber());
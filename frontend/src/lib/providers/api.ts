@@ -25,20 +25,14 @@ export async function testConnection(config: ProviderConfig): Promise<{ success:
 	return response.json();
 }
 
-export async function listModels(config: ProviderConfig): Promise<ModelInfo[]> {
+export async function listModels(providerId: number): Promise<ModelInfo[]> {
 	const response = await fetch(`${API_BASE}/models`, {
 		method: 'POST',
 		headers: {
 			'Content-Type': 'application/json',
 			Authorization: `Bearer ${localStorage.getItem('token') || ''}`
 		},
-		body: JSON.stringify({
-			provider_config: {
-				provider_type: config.providerType,
-				api_key: config.apiKey,
-				base_url: config.baseUrl
-			}
-		})
+		body: JSON.stringify({ provider_id: providerId })
 	});
 
 	if (!response.ok) {